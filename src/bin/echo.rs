@@ -22,23 +22,21 @@ fn double(mut v: TestStruct) -> Result<TestStruct, Fault> {
 }
 
 pub fn main() {
-    let socket = net::SocketAddr::new(net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let socket = net::SocketAddr::new(net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)), 0);
     let mut server = Server::new();
     server.register_simple("echo", echo);
     server.register_simple("double", double);
-    thread::spawn(move || {
-        let bound_server = server.bind(&socket).unwrap();
-        let socket = bound_server.local_addr();
-        println!("{}", socket);
-        bound_server.run()
-    });
-    let mut client = Client::new().unwrap();
+    let bound_server = server.bind(&socket).unwrap();
+    let socket = bound_server.local_addr();
+    println!("{}", socket);
+    thread::spawn(move || bound_server.run());
+    let client = Client::new().unwrap();
     let req = TestStruct {
         foo: 42,
         bar: "baz".into(),
     };
     println!("Sending: {:?}", req);
-    let uri = "http://localhost:8080/".parse().unwrap();
+    let uri = format!("http://{}/", socket).parse().unwrap();
     let res: Result<Result<TestStruct, _>, _> = client.call(&uri, "echo", req.clone());
     println!("Echo Received: {:?}", res);
     let res: Result<Result<TestStruct, _>, _> = client.call(&uri, "double", req.clone());
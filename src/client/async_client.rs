@@ -0,0 +1,439 @@
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+use super::async_io_transport::AsyncIoTransport;
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+use super::async_transport::AsyncHttpTransport;
+#[cfg(target_arch = "wasm32")]
+use super::fetch_transport::FetchTransport;
+use super::observer::Outcome;
+use super::request::Config;
+use super::timer;
+use super::{
+    multicall, AsyncTransport, BatchExecutor, CallOptions, Capabilities, ClientBuilder, Exchange,
+    Reply, ResponseMeta,
+};
+use crate::error::{ErrorKind, Result};
+use crate::xmlfmt::{
+    from_params, into_params_with, Call, Extensions, Fault, Params, ParseOptions, Response, Value,
+};
+use crate::Url;
+use serde::{Deserialize, Serialize};
+use std;
+use std::future::{poll_fn, Future};
+use std::io::Write;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::Poll;
+
+/// Non-blocking counterpart of `Client`.
+///
+/// Calls are driven by the surrounding tokio runtime, so they must be awaited from within one,
+/// unless the `async-std` feature is enabled, with which they run on any executor, such as
+/// async-std's or smol's. Connections are kept alive and reused between calls, and the client is cheap to clone.
+///
+/// On `wasm32` targets, calls are instead made with `FetchTransport` and driven by the
+/// JavaScript event loop, as through `wasm_bindgen_futures::spawn_local`.
+#[derive(Clone)]
+pub struct AsyncClient {
+    transport: Arc<dyn AsyncTransport>,
+    config: Config,
+}
+
+impl AsyncClient {
+    pub fn new() -> Result<AsyncClient> {
+        AsyncClient::from_builder(&ClientBuilder::new())
+    }
+
+    /// Encodes the parameters of typed calls with the extensions, as `Client::set_extensions`
+    /// does.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.config.extensions = extensions;
+    }
+
+    /// Reads responses with the options, as `Client::set_parse_options` does.
+    pub fn set_parse_options(&mut self, options: ParseOptions) {
+        self.config.parse_options = options;
+    }
+
+    pub(super) fn from_builder(builder: &ClientBuilder) -> Result<AsyncClient> {
+        let transport: Arc<dyn AsyncTransport> = match builder.async_transport {
+            Some(ref transport) => transport.clone(),
+            #[cfg(unix)]
+            None if builder.unix_socket.is_some() => {
+                bail!("Unix sockets are only supported by the blocking client.")
+            }
+            #[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+            None => Arc::new(AsyncIoTransport::new(builder)?),
+            #[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+            None => Arc::new(AsyncHttpTransport::new(builder)?),
+            #[cfg(target_arch = "wasm32")]
+            None => Arc::new(FetchTransport::new()),
+        };
+        Ok(AsyncClient {
+            transport,
+            config: builder.config()?,
+        })
+    }
+
+    /// The URL set with `ClientBuilder::url`, which calls made with `invoke` go to.
+    pub fn url(&self) -> Option<&Url> {
+        self.config.url.as_ref()
+    }
+
+    /// The last HTTP exchange made by the client or its clones, if it was built with
+    /// `ClientBuilder::capture_wire`.
+    pub fn last_exchange(&self) -> Option<Exchange> {
+        self.config.last_exchange()
+    }
+
+    /// Makes the call to the client's URL, like `call_value` does to a given one.
+    pub async fn invoke_value<Tkey>(&self, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.call_value(self.config.url()?, name, params).await
+    }
+
+    /// Makes the call to the client's URL, like `call` does to a given one.
+    pub async fn invoke<'a, Tkey, Treq, Tres>(
+        &self,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        self.call(self.config.url()?, name, req).await
+    }
+
+    /// Makes the call with the given values as parameters, returning the values of the result.
+    ///
+    /// The values are written out directly, without going through serde, which suits callers
+    /// building them dynamically, such as proxies and gateways.
+    pub async fn call_value<Tkey>(&self, uri: &Url, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.call_value_with_options(uri, name, params, &CallOptions::default())
+            .await
+    }
+
+    pub async fn call_value_with_options<Tkey>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.exchange(uri, name.into(), params, options, |reply| {
+            self.config.response(reply)
+        })
+        .await
+    }
+
+    /// Makes the call, also returning the HTTP details of the reply, like
+    /// `Client::call_value_with_meta`.
+    pub async fn call_value_with_meta<Tkey>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+    ) -> Result<(Response, ResponseMeta)>
+    where
+        Tkey: Into<String>,
+    {
+        let stopwatch = timer::Stopwatch::start();
+        let (response, meta) = self
+            .exchange(uri, name.into(), params, options, |reply| {
+                let meta = ResponseMeta::new(&reply);
+                self.config.response(reply).map(|response| (response, meta))
+            })
+            .await?;
+        Ok((response, meta.with_elapsed(stopwatch.elapsed())))
+    }
+
+    /// Makes the call, decoding the base64 values of the response into `sink`, like
+    /// `Client::call_value_base64_into`.
+    pub async fn call_value_base64_into<Tkey, W>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        sink: &mut W,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+        W: Write,
+    {
+        self.call_value_base64_into_with_options(uri, name, params, &CallOptions::default(), sink)
+            .await
+    }
+
+    pub async fn call_value_base64_into_with_options<Tkey, W>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+        sink: &mut W,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+        W: Write,
+    {
+        self.exchange(uri, name.into(), params, options, |reply| {
+            self.config.response_base64_into(reply, sink)
+        })
+        .await
+    }
+
+    async fn exchange<T, F>(
+        &self,
+        uri: &Url,
+        name: String,
+        params: Params,
+        options: &CallOptions,
+        interpret: F,
+    ) -> Result<T>
+    where
+        T: Outcome,
+        F: FnMut(Reply) -> Result<T>,
+    {
+        use crate::xmlfmt::value::ToXml;
+        let call = Call { name, params };
+        let body_str = call.to_xml();
+
+        let stopwatch = timer::Stopwatch::start();
+        let result = self
+            .run(&call.name, uri, &body_str, options, interpret)
+            .await;
+        self.config.report(&call.name, stopwatch.elapsed(), &result);
+        result
+    }
+
+    /// Makes the attempts of a call, within its deadline and until it is cancelled.
+    async fn run<T, F>(
+        &self,
+        method: &str,
+        uri: &Url,
+        body: &str,
+        options: &CallOptions,
+        interpret: F,
+    ) -> Result<T>
+    where
+        F: FnMut(Reply) -> Result<T>,
+    {
+        let calls = self.attempts(method, uri, body, options, interpret);
+        let calls = async {
+            match self.config.deadline(options) {
+                Some(deadline) => timer::timeout(deadline, calls)
+                    .await
+                    .ok_or_else(|| ErrorKind::TimedOut("Call deadline exceeded".into()))?,
+                None => calls.await,
+            }
+        };
+        let token = match options.cancellation {
+            Some(ref token) => token,
+            None => return calls.await,
+        };
+        let mut calls = pin!(calls);
+        let mut cancelled = pin!(token.cancelled());
+        // The token goes first, so that no request is started for a cancelled call.
+        poll_fn(|cx| {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(ErrorKind::Cancelled.into()));
+            }
+            calls.as_mut().poll(cx)
+        })
+        .await
+    }
+
+    async fn attempts<T, F>(
+        &self,
+        method: &str,
+        uri: &Url,
+        body: &str,
+        options: &CallOptions,
+        mut interpret: F,
+    ) -> Result<T>
+    where
+        F: FnMut(Reply) -> Result<T>,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match self.attempt(uri, body, options, &mut interpret).await {
+                Err(err) => err,
+                result => return result,
+            };
+            match self.config.retry.backoff_after(attempt, method, &err) {
+                Some(backoff) => timer::sleep(backoff).await,
+                None => return Err(err),
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn attempt<T, F>(
+        &self,
+        uri: &Url,
+        body: &str,
+        options: &CallOptions,
+        interpret: &mut F,
+    ) -> Result<T>
+    where
+        F: FnMut(Reply) -> Result<T>,
+    {
+        let mut url = uri.clone();
+        let mut hops = 0;
+        loop {
+            let request = self.config.request(uri, &url, body, options)?;
+            let reply = self.transport.send(&request).await?;
+            self.config.observe(&request, &reply)?;
+            match self.config.redirect.target(&request, &reply, hops)? {
+                Some(target) => url = target,
+                None => return interpret(reply),
+            }
+            hops += 1;
+        }
+    }
+
+    /// Sends the calls in a single `system.multicall` request, like `Client::multicall`.
+    pub async fn multicall(
+        &self,
+        uri: &Url,
+        calls: &[Call],
+    ) -> Result<std::result::Result<Vec<std::result::Result<Value, Fault>>, Fault>> {
+        let params = multicall::pack(calls);
+        match self.call_value(uri, "system.multicall", params).await? {
+            Ok(params) => multicall::unpack(params, calls.len()).map(Ok),
+            Err(fault) => Ok(Err(fault)),
+        }
+    }
+
+    /// Asks the server which specifications and extensions it implements, like
+    /// `Client::capabilities`.
+    pub async fn capabilities(
+        &self,
+        uri: &Url,
+    ) -> Result<std::result::Result<Capabilities, Fault>> {
+        match self
+            .call_value(uri, "system.getCapabilities", Vec::new())
+            .await?
+        {
+            Ok(params) => Capabilities::from_params(params).map(Ok),
+            Err(fault) => Ok(Err(fault)),
+        }
+    }
+
+    /// Returns an executor for calls to the URL, to be queued and then made concurrently.
+    pub fn batch(&self, uri: &Url) -> BatchExecutor {
+        BatchExecutor::new(self.clone(), uri.clone())
+    }
+
+    /// Makes the call with parameters serialized from `req`, deserializing the result into `Tres`.
+    ///
+    /// A fault is returned as the inner error, and `?` converts it into an error of kind
+    /// `ErrorKind::Fault`, so `client.call(..)??` yields the result or an error either way.
+    pub async fn call<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        self.call_with_options(uri, name, req, &CallOptions::default())
+            .await
+    }
+
+    pub async fn call_with_options<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+        options: &CallOptions,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        match self
+            .call_value_with_options(
+                uri,
+                name,
+                into_params_with(&req, self.config.extensions)?,
+                options,
+            )
+            .await
+        {
+            Ok(Ok(v)) => from_params(v).map(Ok).map_err(Into::into),
+            Ok(Err(v)) => Ok(Err(v)),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Makes the call with serde, also returning the HTTP details of the reply, like
+    /// `Client::call_with_meta`.
+    pub async fn call_with_meta<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+        options: &CallOptions,
+    ) -> Result<(std::result::Result<Tres, Fault>, ResponseMeta)>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        match self
+            .call_value_with_meta(
+                uri,
+                name,
+                into_params_with(&req, self.config.extensions)?,
+                options,
+            )
+            .await?
+        {
+            (Ok(v), meta) => Ok((Ok(from_params(v)?), meta)),
+            (Err(fault), meta) => Ok((Err(fault), meta)),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::server::{Rejection, Rejections, Server};
+    use crate::xmlfmt::Value;
+
+    #[test]
+    fn sends_calls_as_xml() {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        server.set_rejections(Rejections::new().wrong_content_type(Rejection::new(415)));
+        let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let url = format!("http://{}/", bound.local_addr()).parse().unwrap();
+        std::thread::spawn(move || bound.run());
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let client = AsyncClient::new().unwrap();
+        let params = vec![Value::Int(4)];
+        let response = runtime
+            .block_on(client.call_value(&url, "echo", params.clone()))
+            .unwrap();
+        assert_eq!(response, Ok(params));
+    }
+}
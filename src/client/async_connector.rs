@@ -0,0 +1,207 @@
+use super::dns::Resolver;
+use super::proxy::{Proxies, ProxyKind};
+use hyper1::header::HeaderValue;
+use hyper1::rt::{Read, ReadBufCursor, Write};
+use hyper1::Uri;
+use hyper_util::client::legacy::connect::dns::Name;
+#[cfg(feature = "socks")]
+use hyper_util::client::legacy::connect::proxy::SocksV5;
+use hyper_util::client::legacy::connect::proxy::Tunnel;
+use hyper_util::client::legacy::connect::{Connected, Connection, HttpConnector};
+use hyper_util::rt::TokioIo;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tower_service::Service;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Establishes the TCP connections of an `AsyncClient`, going through a proxy where configured.
+///
+/// Plain HTTP connections to hosts behind an HTTP proxy go to the proxy itself, while all other
+/// proxied connections are relayed through it, with `CONNECT` or SOCKS5.
+#[derive(Clone)]
+pub struct ProxyConnector {
+    http: HttpConnector<DnsResolver>,
+    proxies: Proxies,
+    #[cfg(feature = "socks")]
+    resolver: Resolver,
+}
+
+impl ProxyConnector {
+    pub fn new(
+        proxies: Proxies,
+        resolver: Resolver,
+        local_address: Option<IpAddr>,
+    ) -> ProxyConnector {
+        let mut http = HttpConnector::new_with_resolver(DnsResolver(resolver.clone()));
+        http.set_local_address(local_address);
+        ProxyConnector {
+            http,
+            proxies,
+            #[cfg(feature = "socks")]
+            resolver,
+        }
+    }
+
+    /// Lets the connector open connections for HTTPS URLs, to run TLS over.
+    #[cfg(feature = "tls")]
+    pub fn enforce_http(&mut self, enforce: bool) {
+        self.http.enforce_http(enforce);
+    }
+}
+
+/// Looks up host names for hyper with the client's `Resolver`, on a blocking thread as hyper's
+/// own resolver does.
+#[derive(Clone)]
+pub struct DnsResolver(Resolver);
+
+impl DnsResolver {
+    async fn resolve(&self, host: String) -> io::Result<Vec<SocketAddr>> {
+        let resolver = self.0.clone();
+        // hyper fills in the port of the URL.
+        tokio::task::spawn_blocking(move || resolver.resolve(&host, 0))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+impl Service<Name> for DnsResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let resolver = self.clone();
+        Box::pin(async move {
+            Ok(resolver
+                .resolve(name.as_str().to_owned())
+                .await?
+                .into_iter())
+        })
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = MaybeProxied<TokioIo<TcpStream>>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let scheme = dst.scheme_str().unwrap_or("").to_owned();
+        let proxy = self
+            .proxies
+            .find(&scheme, dst.host().unwrap_or(""))
+            .cloned();
+        let mut http = self.http.clone();
+        #[cfg(feature = "socks")]
+        let resolver = DnsResolver(self.resolver.clone());
+        Box::pin(async move {
+            let proxy = match proxy {
+                Some(proxy) => proxy,
+                None => {
+                    let io = http.call(dst).await?;
+                    return Ok(MaybeProxied { io, proxied: false });
+                }
+            };
+            let proxy_uri: Uri = format!("http://{}:{}", proxy.host, proxy.port).parse()?;
+            match proxy.kind {
+                ProxyKind::Http if scheme == "http" => {
+                    let io = http.call(proxy_uri).await?;
+                    Ok(MaybeProxied { io, proxied: true })
+                }
+                ProxyKind::Http => {
+                    let mut tunnel = Tunnel::new(proxy_uri, http);
+                    if let Some(authorization) = proxy.authorization() {
+                        tunnel = tunnel.with_auth(HeaderValue::from_str(&authorization)?);
+                    }
+                    let io = tunnel.call(dst).await?;
+                    Ok(MaybeProxied { io, proxied: false })
+                }
+                #[cfg(feature = "socks")]
+                ProxyKind::Socks5 { remote_dns } => {
+                    let mut socks = SocksV5::new(proxy_uri, http).local_dns(!remote_dns);
+                    if let Some((username, password)) = proxy.credentials {
+                        socks = socks.with_auth(username, password);
+                    }
+                    // The handshake assumes port 443 for destinations without an explicit port.
+                    let port = dst
+                        .port_u16()
+                        .unwrap_or(if scheme == "https" { 443 } else { 80 });
+                    let host = dst.host().unwrap_or("").to_owned();
+                    // Resolving here lets the client's `Resolver` apply to local lookups.
+                    let authority = if remote_dns {
+                        format!("{}:{}", host, port)
+                    } else {
+                        let addr = resolver.resolve(host).await?.into_iter().next();
+                        let addr = addr.ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "Could not resolve to any addresses",
+                            )
+                        })?;
+                        SocketAddr::new(addr.ip(), port).to_string()
+                    };
+                    let dst = Uri::builder()
+                        .scheme(scheme.as_str())
+                        .authority(authority)
+                        .path_and_query("/")
+                        .build()?;
+                    let io = socks.call(dst).await?;
+                    Ok(MaybeProxied { io, proxied: false })
+                }
+            }
+        })
+    }
+}
+
+/// A connection that tells hyper whether requests on it must use the absolute URL form.
+pub struct MaybeProxied<T> {
+    io: T,
+    proxied: bool,
+}
+
+impl<T: Connection> Connection for MaybeProxied<T> {
+    fn connected(&self) -> Connected {
+        self.io.connected().proxy(self.proxied)
+    }
+}
+
+impl<T: Read + Unpin> Read for MaybeProxied<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for MaybeProxied<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,299 @@
+use super::connector::Connector;
+use super::timer;
+use super::{AsyncTransport, BoxFuture, ClientBuilder, Reply, Request};
+use crate::error::{Error, ErrorKind, Result, ResultExt};
+use async_io::Async;
+use bytes::Bytes;
+use futures_io::{AsyncRead, AsyncWrite};
+use http_body_util::{BodyExt, Full};
+use hyper1::client::conn::http1::{self, Connection, SendRequest};
+use hyper1::header::{HeaderName, HeaderValue, HOST, PROXY_AUTHORIZATION};
+use hyper1::{Method, Request as HyperRequest, Uri};
+use smol_hyper::rt::FuturesIo;
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::{pin, Pin};
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tls")]
+use rustls::pki_types::ServerName;
+#[cfg(feature = "tls")]
+use rustls::ClientConfig;
+#[cfg(feature = "tls")]
+use std::convert::TryFrom;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+trait Duplex: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Duplex for T {}
+
+type Io = FuturesIo<Box<dyn Duplex>>;
+
+/// The built-in transport of `AsyncClient` with the `async-std` feature, which runs on any
+/// executor, such as async-std's or smol's.
+///
+/// Sockets are driven by the reactor of `async-io`, and connections by the calls using them
+/// rather than by spawned tasks. Connections are opened, and proxy handshakes made, on the
+/// thread pool of `blocking`, so the read and write timeouts of the builder do not apply.
+pub struct AsyncIoTransport {
+    connector: Connector,
+    #[cfg(feature = "tls")]
+    tls: Arc<ClientConfig>,
+    idle: Mutex<HashMap<String, Vec<IdleConn>>>,
+    max_idle: usize,
+    idle_timeout: Option<Duration>,
+}
+
+/// An HTTP/1.1 connection, made of the handle sending requests on it and the future performing
+/// its I/O.
+struct Conn {
+    sender: SendRequest<Full<Bytes>>,
+    connection: Pin<Box<Connection<Io, Full<Bytes>>>>,
+}
+
+struct IdleConn {
+    conn: Conn,
+    since: Instant,
+}
+
+impl AsyncIoTransport {
+    pub fn new(builder: &ClientBuilder) -> Result<AsyncIoTransport> {
+        Ok(AsyncIoTransport {
+            connector: Connector {
+                connect_timeout: builder.connect_timeout,
+                proxies: builder.proxies()?,
+                resolver: builder.resolver(),
+                local_address: builder.local_address,
+                #[cfg(feature = "tls")]
+                tls: None,
+            },
+            #[cfg(feature = "tls")]
+            tls: builder.tls_config()?,
+            idle: Mutex::new(HashMap::new()),
+            max_idle: builder.pool_max_idle,
+            idle_timeout: builder.pool_idle_timeout,
+        })
+    }
+
+    async fn exchange(&self, call: &Request) -> Result<Reply> {
+        let url = call.url();
+        let scheme = url.scheme();
+        let host = url.host_str().ok_or("The URL has no host.")?;
+        let port = url.port_or_known_default().unwrap_or(80);
+        let forwarded = scheme == "http"
+            && self
+                .connector
+                .proxies
+                .find(scheme, host)
+                .is_some_and(|proxy| proxy.forwards_http());
+
+        // Requests to an HTTP proxy carry the whole URL, and others only the path.
+        let target = if forwarded {
+            url.as_str().to_owned()
+        } else {
+            match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_owned(),
+            }
+        };
+        let uri: Uri = target
+            .parse()
+            .chain_err(|| "Failed to convert the URL for hyper.")?;
+        let mut request = HyperRequest::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Full::new(Bytes::from(call.body().to_vec())))
+            .chain_err(|| "Failed to build the HTTP request.")?;
+        for (name, value) in call.headers() {
+            request.headers_mut().insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+                HeaderValue::from_str(value)
+                    .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+            );
+        }
+        if !request.headers().contains_key(HOST) {
+            let authority = match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_owned(),
+            };
+            let value = HeaderValue::from_str(&authority).chain_err(|| "Invalid URL host.")?;
+            request.headers_mut().insert(HOST, value);
+        }
+        if forwarded {
+            let proxy = self.connector.proxies.find(scheme, host);
+            if let Some(authorization) = proxy.and_then(|proxy| proxy.authorization()) {
+                let value = HeaderValue::from_str(&authorization)
+                    .chain_err(|| "Invalid proxy credentials.")?;
+                request.headers_mut().insert(PROXY_AUTHORIZATION, value);
+            }
+        }
+
+        // The first byte timeout runs from here, through connecting and sending the request.
+        let limit = call.first_byte_timeout();
+        let started = Instant::now();
+        let key = format!("{}://{}:{}", scheme, host, port);
+        let mut conn = match self.checkout(&key).await {
+            Some(conn) => conn,
+            None => timer::first_byte(limit, self.connect(scheme, host, port)).await?,
+        };
+        let Conn {
+            ref mut sender,
+            ref mut connection,
+        } = conn;
+        let response = async {
+            let limit = limit.map(|limit| limit.saturating_sub(started.elapsed()));
+            let response = timer::first_byte(limit, async {
+                poll_fn(|cx| sender.poll_ready(cx))
+                    .await
+                    .chain_err(|| ErrorKind::Transport("Failed to run the HTTP request.".into()))?;
+                sender
+                    .send_request(request)
+                    .await
+                    .chain_err(|| ErrorKind::Transport("Failed to run the HTTP request.".into()))
+            })
+            .await?;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.as_str().to_owned(), value)
+                })
+                .collect::<Vec<_>>();
+            let body = response
+                .into_body()
+                .collect()
+                .await
+                .chain_err(|| {
+                    ErrorKind::Transport("Failed to read the HTTP response body.".into())
+                })?
+                .to_bytes();
+            let reply = Reply::new(status, body.to_vec());
+            Ok(headers
+                .into_iter()
+                .fold(reply, |reply, (name, value)| reply.with_header(name, value)))
+        };
+        let (reply, open) = drive(connection, response).await;
+        if open && reply.is_ok() {
+            self.checkin(key, conn);
+        }
+        reply
+    }
+
+    /// Takes an idle connection to the server from the pool, if one is still usable.
+    async fn checkout(&self, key: &str) -> Option<Conn> {
+        loop {
+            let idle = self.idle.lock().ok()?.get_mut(key)?.pop()?;
+            let expired = self
+                .idle_timeout
+                .is_some_and(|timeout| idle.since.elapsed() >= timeout);
+            let mut conn = idle.conn;
+            // Polling the connection once processes a close by the server while it was idle.
+            let closed =
+                poll_fn(|cx| Poll::Ready(conn.connection.as_mut().poll(cx).is_ready())).await;
+            if !expired && !closed && !conn.sender.is_closed() {
+                return Some(conn);
+            }
+        }
+    }
+
+    fn checkin(&self, key: String, conn: Conn) {
+        if let Ok(mut idle) = self.idle.lock() {
+            let conns = idle.entry(key).or_default();
+            if conns.len() < self.max_idle {
+                conns.push(IdleConn {
+                    conn,
+                    since: Instant::now(),
+                });
+            }
+        }
+    }
+
+    async fn connect(&self, scheme: &str, host: &str, port: u16) -> Result<Conn> {
+        if scheme != "http" && !cfg!(feature = "tls") {
+            bail!("Unsupported URL scheme: {}", scheme);
+        }
+        let connector = self.connector.clone();
+        let (address, url_scheme) = (host.to_owned(), scheme.to_owned());
+        let stream = blocking::unblock(move || {
+            let host = address.trim_start_matches('[').trim_end_matches(']');
+            connector.open(host, port, &url_scheme)
+        })
+        .await
+        .map_err(connect_error)?;
+        let stream = Async::new(stream).map_err(connect_error)?;
+        let io: Box<dyn Duplex> = match scheme {
+            #[cfg(feature = "tls")]
+            "https" => Box::new(self.tls_connect(stream, host).await?),
+            _ => Box::new(stream),
+        };
+        let (sender, connection) = http1::handshake(FuturesIo::new(io))
+            .await
+            .chain_err(|| ErrorKind::Connect("Failed to set up the HTTP connection.".into()))?;
+        Ok(Conn {
+            sender,
+            connection: Box::pin(connection),
+        })
+    }
+
+    #[cfg(feature = "tls")]
+    async fn tls_connect(
+        &self,
+        stream: Async<std::net::TcpStream>,
+        host: &str,
+    ) -> Result<futures_rustls::client::TlsStream<Async<std::net::TcpStream>>> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let name =
+            ServerName::try_from(host.to_owned()).chain_err(|| "Invalid TLS server name.")?;
+        futures_rustls::TlsConnector::from(self.tls.clone())
+            .connect(name, stream)
+            .await
+            .map_err(connect_error)
+    }
+}
+
+impl AsyncTransport for AsyncIoTransport {
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>> {
+        Box::pin(self.exchange(request))
+    }
+}
+
+/// Runs the future along with the I/O of the connection it uses, returning whether the
+/// connection is still open.
+async fn drive<T, F>(
+    connection: &mut Pin<Box<Connection<Io, Full<Bytes>>>>,
+    future: F,
+) -> (Result<T>, bool)
+where
+    F: Future<Output = Result<T>>,
+{
+    let mut future = pin!(future);
+    let mut open = true;
+    poll_fn(|cx| {
+        if open && connection.as_mut().poll(cx).is_ready() {
+            open = false;
+        }
+        match future.as_mut().poll(cx) {
+            Poll::Ready(result) => Poll::Ready((result, open)),
+            Poll::Pending if open => Poll::Pending,
+            Poll::Pending => {
+                let details = "The server closed the connection.".to_owned();
+                Poll::Ready((Err(ErrorKind::Transport(details).into()), false))
+            }
+        }
+    })
+    .await
+}
+
+/// Nothing has been sent while connecting, so all failures of it, timeouts included, are of kind
+/// `Connect`.
+fn connect_error(err: io::Error) -> Error {
+    let details = "Failed to connect to the server.".to_owned();
+    Error::with_chain(err, ErrorKind::Connect(details))
+}
@@ -0,0 +1,150 @@
+use super::async_connector::ProxyConnector;
+use super::proxy::Proxies;
+use super::timer;
+use super::{AsyncTransport, BoxFuture, ClientBuilder, Reply, Request};
+use crate::error::{Error, ErrorKind, Result, ResultExt};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper1::header::{HeaderMap, HeaderName, HeaderValue, PROXY_AUTHORIZATION};
+use hyper1::{Method, Request as HyperRequest, Uri};
+use hyper_timeout::TimeoutConnector;
+use hyper_util::client::legacy::Client as HyperClient;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+
+#[cfg(feature = "tls")]
+type Connector = hyper_rustls::HttpsConnector<ProxyConnector>;
+#[cfg(not(feature = "tls"))]
+type Connector = ProxyConnector;
+
+/// The built-in transport of `AsyncClient`, running on hyper's pooling client.
+pub struct AsyncHttpTransport {
+    client: HyperClient<TimeoutConnector<Connector>, Full<Bytes>>,
+    proxies: Proxies,
+}
+
+impl AsyncHttpTransport {
+    pub fn new(builder: &ClientBuilder) -> Result<AsyncHttpTransport> {
+        let mut connector = TimeoutConnector::new(AsyncHttpTransport::connector(builder)?);
+        connector.set_connect_timeout(builder.connect_timeout);
+        connector.set_read_timeout(builder.read_timeout);
+        connector.set_write_timeout(builder.write_timeout);
+        let mut client = HyperClient::builder(TokioExecutor::new());
+        client
+            .pool_timer(TokioTimer::new())
+            .pool_max_idle_per_host(builder.pool_max_idle)
+            .pool_idle_timeout(builder.pool_idle_timeout);
+        #[cfg(feature = "http2")]
+        client.http2_only(builder.http2_prior_knowledge);
+        let client = client.build(connector);
+        Ok(AsyncHttpTransport {
+            client,
+            proxies: builder.proxies()?,
+        })
+    }
+
+    #[cfg(feature = "tls")]
+    fn connector(builder: &ClientBuilder) -> Result<Connector> {
+        let mut connector = ProxyConnector::new(
+            builder.proxies()?,
+            builder.resolver(),
+            builder.local_address,
+        );
+        connector.enforce_http(false);
+        let https = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config((*builder.tls_config()?).clone())
+            .https_or_http();
+        // Only the protocols enabled here are offered through ALPN.
+        #[cfg(feature = "http2")]
+        {
+            if builder.http2_prior_knowledge {
+                return Ok(https.enable_http2().wrap_connector(connector));
+            }
+            Ok(https.enable_all_versions().wrap_connector(connector))
+        }
+        #[cfg(not(feature = "http2"))]
+        Ok(https.enable_http1().wrap_connector(connector))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn connector(builder: &ClientBuilder) -> Result<Connector> {
+        Ok(ProxyConnector::new(
+            builder.proxies()?,
+            builder.resolver(),
+            builder.local_address,
+        ))
+    }
+
+    async fn exchange(&self, call: &Request) -> Result<Reply> {
+        let uri: Uri = call
+            .url()
+            .as_str()
+            .parse()
+            .chain_err(|| "Failed to convert the URL for hyper.")?;
+        let mut request = HyperRequest::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Full::new(Bytes::from(call.body().to_vec())))
+            .chain_err(|| "Failed to build the HTTP request.")?;
+        add_headers(request.headers_mut(), call.headers())?;
+        if call.url().scheme() == "http" {
+            let proxy = self
+                .proxies
+                .find("http", call.url().host_str().unwrap_or(""))
+                .filter(|proxy| proxy.forwards_http());
+            if let Some(authorization) = proxy.and_then(|proxy| proxy.authorization()) {
+                let value = HeaderValue::from_str(&authorization)
+                    .chain_err(|| "Invalid proxy credentials.")?;
+                request.headers_mut().insert(PROXY_AUTHORIZATION, value);
+            }
+        }
+
+        let response = timer::first_byte(call.first_byte_timeout(), async {
+            self.client.request(request).await.map_err(|err| {
+                // Nothing has been sent when connecting fails, including through a proxy.
+                let kind = if err.is_connect() {
+                    ErrorKind::Connect("Failed to connect to the server.".into())
+                } else {
+                    ErrorKind::Transport("Failed to run the HTTP request within hyper.".into())
+                };
+                Error::with_chain(err, kind)
+            })
+        })
+        .await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_owned(), value)
+            })
+            .collect::<Vec<_>>();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .chain_err(|| ErrorKind::Transport("Failed to read the HTTP response body.".into()))?
+            .to_bytes();
+        let reply = Reply::new(status, body.to_vec());
+        Ok(headers
+            .into_iter()
+            .fold(reply, |reply, (name, value)| reply.with_header(name, value)))
+    }
+}
+
+impl AsyncTransport for AsyncHttpTransport {
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>> {
+        Box::pin(self.exchange(request))
+    }
+}
+
+fn add_headers(map: &mut HeaderMap, headers: &[(String, String)]) -> Result<()> {
+    for (name, value) in headers {
+        map.insert(
+            HeaderName::from_bytes(name.as_bytes())
+                .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+            HeaderValue::from_str(value).chain_err(|| format!("Invalid HTTP header: {}", name))?,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use super::Request;
+use crate::error::Result;
+
+/// Hook for authentication schemes that need more than a static header.
+///
+/// The authenticator runs right before every call is sent, after default and per-call headers
+/// have been applied, so it can sign the body or refresh short-lived tokens.
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, request: &mut Request) -> Result<()>;
+}
+
+impl<T> Authenticator for T
+where
+    T: Fn(&mut Request) -> Result<()> + Send + Sync,
+{
+    fn authenticate(&self, request: &mut Request) -> Result<()> {
+        self(request)
+    }
+}
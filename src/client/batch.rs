@@ -0,0 +1,137 @@
+use super::{AsyncClient, BoxFuture, CallOptions};
+use crate::error::Result;
+use crate::xmlfmt::{Params, Response};
+use crate::Url;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Queues calls to a server, to be made concurrently by an `AsyncClient` once run.
+///
+/// At most `concurrency` calls are in flight at a time, which also bounds the HTTP/1.1
+/// connections opened for them, and queued calls start as earlier ones complete. Unlike with a
+/// multicall, every call is a request of its own, so the server needs no support for batches,
+/// and each call is retried and times out on its own.
+pub struct BatchExecutor {
+    client: AsyncClient,
+    url: Url,
+    options: CallOptions,
+    concurrency: usize,
+    queue: VecDeque<(usize, String, Params)>,
+}
+
+impl BatchExecutor {
+    pub fn new(client: AsyncClient, url: Url) -> BatchExecutor {
+        BatchExecutor {
+            client,
+            url,
+            options: CallOptions::default(),
+            concurrency: 4,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Sets how many calls may be in flight at once, which is 4 by default.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the options of every call in the batch.
+    pub fn options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Queues the call, returning the index its result is reported with, which counts up from
+    /// zero in the order calls are queued.
+    pub fn push<Tkey>(&mut self, name: Tkey, params: Params) -> usize
+    where
+        Tkey: Into<String>,
+    {
+        let index = self.queue.len();
+        self.queue.push_back((index, name.into(), params));
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Starts making the calls, returning a stream of their results in the order they complete.
+    pub fn run(self) -> BatchStream {
+        BatchStream {
+            executor: self,
+            running: Vec::new(),
+        }
+    }
+
+    fn start(&self, index: usize, name: String, params: Params) -> Running {
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let options = self.options.clone();
+        Box::pin(async move {
+            let result = client
+                .call_value_with_options(&url, name, params, &options)
+                .await;
+            (index, result)
+        })
+    }
+}
+
+type Running = BoxFuture<'static, (usize, Result<Response>)>;
+
+/// The results of the calls of a `BatchExecutor`, each with the index of its call.
+///
+/// The calls are driven by polling the stream, on the task doing so, so they make no progress
+/// while it is not polled, and dropping the stream abandons the calls still queued or in flight.
+pub struct BatchStream {
+    executor: BatchExecutor,
+    running: Vec<Running>,
+}
+
+impl BatchStream {
+    /// Waits for the next call to complete, or returns `None` once all have.
+    pub async fn next(&mut self) -> Option<(usize, Result<Response>)> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl Stream for BatchStream {
+    type Item = (usize, Result<Response>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while this.running.len() < this.executor.concurrency {
+            match this.executor.queue.pop_front() {
+                Some((index, name, params)) => {
+                    let call = this.executor.start(index, name, params);
+                    this.running.push(call);
+                }
+                None => break,
+            }
+        }
+        for position in 0..this.running.len() {
+            if let Poll::Ready(result) = this.running[position].as_mut().poll(cx) {
+                drop(this.running.swap_remove(position));
+                return Poll::Ready(Some(result));
+            }
+        }
+        if this.running.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.running.len() + self.executor.queue.len();
+        (remaining, Some(remaining))
+    }
+}
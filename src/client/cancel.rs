@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Abandons the async calls it is passed to with `CallOptions::cancel_with`.
+///
+/// Cancelling fails the calls with `ErrorKind::Cancelled` and drops their in-flight HTTP
+/// exchange, which closes its connection instead of returning it to the pool. Tokens are cheap
+/// to clone, with all clones cancelling together, and one token can serve any number of calls.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<Mutex<State>>,
+}
+
+/// The wakers of the futures waiting on a token are kept by key, so that a future can replace
+/// its own on every poll and remove it when dropped.
+#[derive(Default)]
+struct State {
+    cancelled: bool,
+    next_key: u64,
+    waiters: HashMap<u64, Waker>,
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels all calls using the token, including ones started later.
+    pub fn cancel(&self) {
+        let waiters = {
+            let mut state = self.lock();
+            state.cancelled = true;
+            std::mem::take(&mut state.waiters)
+        };
+        for (_, waker) in waiters {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.lock().cancelled
+    }
+
+    /// Completes once the token is cancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + '_ {
+        let key = {
+            let mut state = self.lock();
+            state.next_key += 1;
+            state.next_key
+        };
+        Cancelled { token: self, key }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state is consistent whenever the lock is released, so a poisoned one is still
+        // safe to use.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    key: u64,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut state = self.token.lock();
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+        state.waiters.insert(self.key, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        self.token.lock().waiters.remove(&self.key);
+    }
+}
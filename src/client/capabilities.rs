@@ -0,0 +1,90 @@
+use crate::error::Result;
+use crate::xmlfmt::value::take_member;
+use crate::xmlfmt::{Extensions, Params, Value};
+use std::collections::HashMap;
+
+/// The specifications and extensions a server implements, as returned by
+/// `system.getCapabilities`.
+///
+/// Capabilities are keyed by the names servers give them, such as `xmlrpc`, `introspection`,
+/// `system.multicall`, `faults_interop` or `nil`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    entries: HashMap<String, Capability>,
+}
+
+/// The specification behind a capability, identified by its URL and version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capability {
+    pub spec_url: String,
+    pub spec_version: i32,
+}
+
+impl Capabilities {
+    /// Reads the single struct of a `system.getCapabilities` response.
+    pub(super) fn from_params(mut params: Params) -> Result<Capabilities> {
+        let entries = match (params.pop(), params.is_empty()) {
+            (Some(Value::Struct(entries)), true) => entries,
+            _ => bail!("Invalid system.getCapabilities response."),
+        };
+        let entries = entries
+            .into_iter()
+            .map(|(name, entry)| {
+                let capability = Capability::from_value(entry).ok_or_else(|| {
+                    format!(
+                        "Invalid capability in system.getCapabilities response: {}",
+                        name
+                    )
+                })?;
+                Ok((name, capability))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Capabilities { entries })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Capability> {
+        self.entries.get(name)
+    }
+
+    pub fn supports(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// The extensions of those the server implements, for `Client::set_extensions`.
+    pub fn extensions(&self) -> Extensions {
+        Extensions {
+            nil: self.supports("nil"),
+            i8: self.supports("i8"),
+            strict: false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Capability)> {
+        self.entries
+            .iter()
+            .map(|(name, capability)| (name.as_str(), capability))
+    }
+}
+
+impl Capability {
+    /// Some servers send the version as a string, which is accepted as long as it is numeric.
+    fn from_value(value: Value) -> Option<Capability> {
+        let mut entry = match value {
+            Value::Struct(entry) => entry,
+            _ => return None,
+        };
+        let spec_url = match take_member(&mut entry, "specUrl") {
+            Some(Value::String(url)) => url,
+            _ => return None,
+        };
+        let spec_version = match take_member(&mut entry, "specVersion") {
+            Some(Value::Int(version)) => version,
+            Some(Value::String(version)) => version.trim().parse().ok()?,
+            _ => return None,
+        };
+        Some(Capability {
+            spec_url,
+            spec_version,
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use super::{Reply, Request};
+use std::sync::Mutex;
+
+/// The HTTP exchange of a call, as kept by clients with `ClientBuilder::capture_wire` enabled.
+///
+/// The request is the one handed to the transport, which adds its own framing headers such as
+/// `Content-Length`, and the reply is the one it returned, before the body is decompressed.
+#[derive(Clone, Debug)]
+pub struct Exchange {
+    request: Request,
+    reply: Option<Reply>,
+}
+
+impl Exchange {
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// The reply of the server, or `None` if the call failed before one was received.
+    pub fn reply(&self) -> Option<&Reply> {
+        self.reply.as_ref()
+    }
+}
+
+/// Holds the most recent exchange of a client and its clones.
+#[derive(Debug, Default)]
+pub struct WireCapture {
+    last: Mutex<Option<Exchange>>,
+}
+
+impl WireCapture {
+    /// Records a request about to be sent, replacing the previous exchange.
+    pub fn request(&self, request: &Request) {
+        self.store(Exchange {
+            request: request.clone(),
+            reply: None,
+        });
+    }
+
+    /// Records the reply received to the request.
+    pub fn reply(&self, request: &Request, reply: &Reply) {
+        self.store(Exchange {
+            request: request.clone(),
+            reply: Some(reply.clone()),
+        });
+    }
+
+    pub fn last(&self) -> Option<Exchange> {
+        self.last
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    fn store(&self, exchange: Exchange) {
+        *self.last.lock().unwrap_or_else(|err| err.into_inner()) = Some(exchange);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::{Result, ResultExt};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// The `Accept-Encoding` value sent when response decompression is enabled.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+pub fn gzip(body: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(body)
+        .and_then(|_| encoder.finish())
+        .chain_err(|| "Failed to compress the HTTP request body.")
+}
+
+/// Undoes the codings listed in a `Content-Encoding` header, last applied first.
+pub fn decode(encoding: &str, mut body: Vec<u8>) -> Result<Vec<u8>> {
+    for coding in encoding.rsplit(',').map(str::trim) {
+        body = match coding.to_ascii_lowercase().as_str() {
+            "" | "identity" => body,
+            "gzip" | "x-gzip" => read_all(GzDecoder::new(body.as_slice()))?,
+            // Some servers send raw deflate streams instead of the zlib format the spec asks for.
+            "deflate" => read_all(ZlibDecoder::new(body.as_slice()))
+                .or_else(|_| read_all(DeflateDecoder::new(body.as_slice())))?,
+            other => bail!("Unsupported content encoding in HTTP response: {}", other),
+        };
+    }
+    Ok(body)
+}
+
+fn read_all<R: Read>(mut decoder: R) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    decoder
+        .read_to_end(&mut content)
+        .chain_err(|| "Failed to decompress the HTTP response body.")?;
+    Ok(content)
+}
@@ -0,0 +1,287 @@
+use super::dns::Resolver;
+use super::proxy::{Proxies, ProxyKind, ProxyServer};
+use hyper::net::{HttpStream, NetworkConnector, NetworkStream};
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "socks")]
+use super::socks;
+#[cfg(feature = "tls")]
+use super::tls::{self, TlsStream};
+#[cfg(feature = "tls")]
+use rustls::ClientConfig;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Runs `connect` with the connections it opens given up on at the deadline, as the pool of
+/// hyper's client has no way of passing one through to the connector.
+pub(super) fn with_deadline<T, F: FnOnce() -> T>(deadline: Option<Instant>, connect: F) -> T {
+    let previous = DEADLINE.with(|cell| cell.replace(deadline));
+    let result = connect();
+    DEADLINE.with(|cell| cell.set(previous));
+    result
+}
+
+/// Establishes the connections used by `Client`, bounding the time spent connecting.
+///
+/// Plain HTTP connections to hosts behind an HTTP proxy go to the proxy itself, while all other
+/// proxied connections are relayed through it, with `CONNECT` or SOCKS5.
+#[derive(Clone, Debug, Default)]
+pub struct Connector {
+    pub connect_timeout: Option<Duration>,
+    pub proxies: Proxies,
+    pub resolver: Resolver,
+    pub local_address: Option<IpAddr>,
+    #[cfg(feature = "tls")]
+    pub tls: Option<Arc<ClientConfig>>,
+}
+
+impl NetworkConnector for Connector {
+    type Stream = Stream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Stream> {
+        match scheme {
+            "http" => Ok(Stream::Http(HttpStream(self.open(host, port, scheme)?))),
+            #[cfg(feature = "tls")]
+            "https" => match self.tls {
+                Some(ref config) => {
+                    let stream = self.open(host, port, scheme)?;
+                    tls::connect(config, stream, host).map(|stream| Stream::Https(Box::new(stream)))
+                }
+                None => Err(invalid_scheme()),
+            },
+            _ => Err(invalid_scheme()),
+        }
+    }
+}
+
+impl Connector {
+    /// Opens a TCP connection to the host, or to the proxy configured for it.
+    pub(super) fn open(&self, host: &str, port: u16, scheme: &str) -> io::Result<TcpStream> {
+        let proxy = match self.proxies.find(scheme, host) {
+            Some(proxy) => proxy,
+            None => return self.connect_tcp(host, port),
+        };
+        let mut stream = self.connect_tcp(&proxy.host, proxy.port)?;
+        let timeout = self.timeout()?;
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        match proxy.kind {
+            ProxyKind::Http if scheme == "http" => {}
+            ProxyKind::Http => tunnel(&mut stream, proxy, host, port)?,
+            #[cfg(feature = "socks")]
+            ProxyKind::Socks5 { remote_dns } => socks::connect(
+                &mut stream,
+                host,
+                port,
+                remote_dns,
+                &self.resolver,
+                proxy.credentials.as_ref(),
+            )?,
+        }
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(None)?;
+        Ok(stream)
+    }
+
+    /// Connects to the first reachable address of the host, from the local address if one of
+    /// the same IP version is set.
+    fn connect_tcp(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in self.resolver.resolve(host, port)? {
+            let local = self
+                .local_address
+                .filter(|local| local.is_ipv4() == addr.is_ipv4());
+            let stream = match (local, self.timeout()?) {
+                #[cfg(not(target_arch = "wasm32"))]
+                (Some(local), timeout) => connect_from(local, &addr, timeout),
+                (_, Some(timeout)) => TcpStream::connect_timeout(&addr, timeout),
+                (_, None) => TcpStream::connect(addr),
+            };
+            match stream {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Could not resolve to any addresses",
+            )
+        }))
+    }
+
+    /// The connect timeout, narrowed to what is left until the deadline of the call.
+    fn timeout(&self) -> io::Result<Option<Duration>> {
+        timeout_until(self.connect_timeout, DEADLINE.with(Cell::get))
+    }
+}
+
+/// Narrows a socket timeout so that it does not outlast the call's deadline.
+pub(super) fn timeout_until(
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+) -> io::Result<Option<Duration>> {
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return Ok(timeout),
+    };
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Call deadline exceeded",
+        ));
+    }
+    let remaining = deadline - now;
+    Ok(Some(
+        timeout.map_or(remaining, |timeout| timeout.min(remaining)),
+    ))
+}
+
+/// Binds the socket to the local address before connecting, which the standard library cannot.
+#[cfg(not(target_arch = "wasm32"))]
+fn connect_from(
+    local: IpAddr,
+    addr: &SocketAddr,
+    timeout: Option<Duration>,
+) -> io::Result<TcpStream> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(
+        Domain::for_address(*addr),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    socket.bind(&SocketAddr::new(local, 0).into())?;
+    match timeout {
+        Some(timeout) => socket.connect_timeout(&(*addr).into(), timeout)?,
+        None => socket.connect(&(*addr).into())?,
+    }
+    Ok(socket.into())
+}
+
+/// Asks an HTTP proxy to open a raw TCP tunnel to the host, to run TLS through.
+fn tunnel(stream: &mut TcpStream, proxy: &ProxyServer, host: &str, port: u16) -> io::Result<()> {
+    let mut request = format!("CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n", host, port);
+    if let Some(authorization) = proxy.authorization() {
+        request.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // Read byte by byte, so that nothing the server sends after the handshake is consumed.
+    let mut response = Vec::new();
+    let mut byte = [0];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 8192 || stream.read(&mut byte)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid response from proxy to CONNECT request",
+            ));
+        }
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or("");
+    if status
+        .split(' ')
+        .nth(1)
+        .is_none_or(|code| !code.starts_with('2'))
+    {
+        return Err(io::Error::other(format!(
+            "Proxy refused to open tunnel: {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+fn invalid_scheme() -> hyper::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "Invalid scheme for Http").into()
+}
+
+/// Any of the connection kinds a `Connector` can produce.
+#[derive(Debug)]
+pub enum Stream {
+    Http(HttpStream),
+    #[cfg(feature = "tls")]
+    Https(Box<TlsStream>),
+}
+
+impl Stream {
+    /// Checks an idle connection for having been closed by the server.
+    ///
+    /// Any data waiting on an idle connection also makes it unusable for a new request.
+    pub fn is_closed(&self) -> bool {
+        let stream = match *self {
+            Stream::Http(ref stream) => &stream.0,
+            #[cfg(feature = "tls")]
+            Stream::Https(ref stream) => stream.tcp(),
+        };
+        if stream.set_nonblocking(true).is_err() {
+            return true;
+        }
+        let closed = match stream.peek(&mut [0]) {
+            Err(ref err) => err.kind() != io::ErrorKind::WouldBlock,
+            Ok(_) => true,
+        };
+        stream.set_nonblocking(false).is_err() || closed
+    }
+
+    fn get_ref(&self) -> &dyn NetworkStream {
+        match *self {
+            Stream::Http(ref stream) => stream,
+            #[cfg(feature = "tls")]
+            Stream::Https(ref stream) => &**stream,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn NetworkStream {
+        match *self {
+            Stream::Http(ref mut stream) => stream,
+            #[cfg(feature = "tls")]
+            Stream::Https(ref mut stream) => &mut **stream,
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.get_mut().read(buf)
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+impl NetworkStream for Stream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.get_mut().peer_addr()
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.get_mut().close(how)
+    }
+}
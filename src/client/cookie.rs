@@ -0,0 +1,269 @@
+use crate::Url;
+use hyper::header::HttpDate;
+use std::cmp::Reverse;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Session cookies received from servers, replayed on later calls to matching URLs.
+///
+/// Only the parts of RFC 6265 relevant for talking to a handful of endpoints are implemented:
+/// domain and path scoping, `Secure`, and expiry through `Max-Age` or `Expires`.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Mutex<Vec<Cookie>>,
+}
+
+#[derive(Debug)]
+struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires: Option<SystemTime>,
+}
+
+impl CookieJar {
+    /// Records the cookies of the `Set-Cookie` header values received from `url`.
+    pub fn store<'a, I>(&self, url: &Url, headers: I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return,
+        };
+        let mut cookies = self.cookies.lock().unwrap_or_else(|err| err.into_inner());
+        for header in headers {
+            let cookie = match Cookie::parse(header, &host, url.path()) {
+                Some(cookie) => cookie,
+                None => continue,
+            };
+            cookies.retain(|old| {
+                old.name != cookie.name || old.domain != cookie.domain || old.path != cookie.path
+            });
+            if !cookie.is_expired(SystemTime::now()) {
+                cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Returns the `Cookie` header value to send with a request to `url`, if any cookie applies.
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let secure = url.scheme() == "https";
+        let now = SystemTime::now();
+        let mut cookies = self.cookies.lock().unwrap_or_else(|err| err.into_inner());
+        cookies.retain(|cookie| !cookie.is_expired(now));
+
+        let mut matching = cookies
+            .iter()
+            .filter(|cookie| cookie.matches(&host, url.path(), secure))
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|cookie| Reverse(cookie.path.len()));
+        let pairs = matching
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>();
+        Some(pairs.join("; "))
+    }
+}
+
+impl Cookie {
+    fn parse(header: &str, host: &str, request_path: &str) -> Option<Cookie> {
+        let mut parts = header.split(';');
+        let (name, value) = split_pair(parts.next()?)?;
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            domain: host.to_owned(),
+            host_only: true,
+            path: default_path(request_path),
+            secure: false,
+            expires: None,
+        };
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) = split_pair(attribute).unwrap_or((attribute.trim(), ""));
+            match key.to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain_matches(host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_owned(),
+                "secure" => cookie.secure = true,
+                "max-age" => {
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        max_age = Some(seconds);
+                    }
+                }
+                "expires" => {
+                    if let Ok(date) = value.parse::<HttpDate>() {
+                        let seconds = date.0.to_timespec().sec;
+                        cookie.expires = Some(if seconds > 0 {
+                            UNIX_EPOCH + Duration::from_secs(seconds as u64)
+                        } else {
+                            UNIX_EPOCH
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(if seconds > 0 {
+                SystemTime::now() + Duration::from_secs(seconds as u64)
+            } else {
+                UNIX_EPOCH
+            });
+        }
+        Some(cookie)
+    }
+
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(host, &self.domain)
+        };
+        domain && path_matches(path, &self.path) && (secure || !self.secure)
+    }
+}
+
+fn split_pair(pair: &str) -> Option<(&str, &str)> {
+    let index = pair.find('=')?;
+    Some((pair[..index].trim(), pair[index + 1..].trim()))
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+    host.ends_with(domain)
+        && host[..host.len() - domain.len()].ends_with('.')
+        && host.parse::<IpAddr>().is_err()
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the request path, which scopes cookies that come without a `Path`.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => path[..index].to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        url.parse().unwrap()
+    }
+
+    #[test]
+    fn matches_domains() {
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(domain_matches("api.example.com", "example.com"));
+        assert!(!domain_matches("badexample.com", "example.com"));
+        assert!(!domain_matches("example.com", "api.example.com"));
+        assert!(!domain_matches("10.0.0.1", "0.0.1"));
+    }
+
+    #[test]
+    fn matches_paths() {
+        assert!(path_matches("/rpc", "/rpc"));
+        assert!(path_matches("/rpc/v2", "/rpc"));
+        assert!(path_matches("/rpc/v2", "/rpc/"));
+        assert!(path_matches("/anything", "/"));
+        assert!(!path_matches("/rpcx", "/rpc"));
+        assert!(!path_matches("/", "/rpc"));
+        assert_eq!(default_path("/"), "/");
+        assert_eq!(default_path("/rpc"), "/");
+        assert_eq!(default_path("/api/rpc"), "/api");
+    }
+
+    #[test]
+    fn scopes_host_only_cookies() {
+        let jar = CookieJar::default();
+        jar.store(&url("http://example.com/api/rpc"), vec!["id=1"]);
+        assert_eq!(
+            jar.header(&url("http://example.com/api/other")),
+            Some("id=1".into())
+        );
+        assert_eq!(jar.header(&url("http://example.com/rpc")), None);
+        assert_eq!(jar.header(&url("http://api.example.com/api/rpc")), None);
+    }
+
+    #[test]
+    fn scopes_domain_cookies() {
+        let jar = CookieJar::default();
+        jar.store(
+            &url("http://api.example.com/rpc"),
+            vec![
+                "id=1; Domain=.example.com; Path=/",
+                "other=2; Domain=elsewhere.com",
+            ],
+        );
+        assert_eq!(
+            jar.header(&url("http://www.example.com/")),
+            Some("id=1".into())
+        );
+        assert_eq!(jar.header(&url("http://elsewhere.com/")), None);
+    }
+
+    #[test]
+    fn orders_longer_paths_first() {
+        let jar = CookieJar::default();
+        let target = url("http://example.com/api/rpc");
+        jar.store(&target, vec!["short=1; Path=/", "long=2; Path=/api"]);
+        assert_eq!(jar.header(&target), Some("long=2; short=1".into()));
+    }
+
+    #[test]
+    fn keeps_secure_cookies_to_https() {
+        let jar = CookieJar::default();
+        jar.store(&url("https://example.com/"), vec!["id=1; Secure"]);
+        assert_eq!(jar.header(&url("http://example.com/")), None);
+        assert_eq!(
+            jar.header(&url("https://example.com/")),
+            Some("id=1".into())
+        );
+    }
+
+    #[test]
+    fn replaces_and_expires_cookies() {
+        let jar = CookieJar::default();
+        let target = url("http://example.com/");
+        jar.store(&target, vec!["id=1", "id=2"]);
+        assert_eq!(jar.header(&target), Some("id=2".into()));
+        jar.store(&target, vec!["id=3; Max-Age=0"]);
+        assert_eq!(jar.header(&target), None);
+        jar.store(&target, vec!["id=4; Expires=Thu, 01 Jan 1970 00:00:00 GMT"]);
+        assert_eq!(jar.header(&target), None);
+        jar.store(&target, vec!["id=5; Max-Age=60"]);
+        assert_eq!(jar.header(&target), Some("id=5".into()));
+    }
+}
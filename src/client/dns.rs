@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+
+/// Looks up the addresses of hosts for a client, in place of the system resolver.
+///
+/// Lookups happen while connecting, on a blocking thread for async clients, so implementations
+/// may block.
+pub trait Resolve: Send + Sync {
+    /// Returns the addresses of the host, to be tried in order.
+    fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+/// The name resolution of a client, preferring the addresses fixed on the builder.
+#[derive(Clone, Default)]
+pub struct Resolver {
+    overrides: HashMap<String, Vec<IpAddr>>,
+    custom: Option<Arc<dyn Resolve>>,
+}
+
+impl fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resolver")
+            .field("overrides", &self.overrides)
+            .field("custom", &self.custom.is_some())
+            .finish()
+    }
+}
+
+impl Resolver {
+    pub fn new(
+        overrides: HashMap<String, Vec<IpAddr>>,
+        custom: Option<Arc<dyn Resolve>>,
+    ) -> Resolver {
+        Resolver { overrides, custom }
+    }
+
+    /// Returns the socket addresses to connect to for the host and port.
+    pub fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addresses = match self.overrides.get(&host.to_ascii_lowercase()) {
+            Some(addresses) => addresses.clone(),
+            None => match self.custom {
+                Some(ref custom) if host.parse::<IpAddr>().is_err() => custom.resolve(host)?,
+                _ => return (host, port).to_socket_addrs().map(Iterator::collect),
+            },
+        };
+        Ok(addresses
+            .into_iter()
+            .map(|address| SocketAddr::new(address, port))
+            .collect())
+    }
+}
@@ -0,0 +1,109 @@
+use super::timer;
+use super::{AsyncTransport, BoxFuture, Reply, Request};
+use crate::error::{Error, ErrorKind, Result};
+use js_sys::{Promise, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortController, Headers, RequestInit, Response};
+
+#[wasm_bindgen]
+extern "C" {
+    // The global `fetch`, present in windows as well as workers.
+    #[wasm_bindgen(js_name = fetch)]
+    fn fetch_with_request(input: &web_sys::Request) -> Promise;
+}
+
+/// Carries the calls of an `AsyncClient` with the `fetch` API of browsers and other JavaScript
+/// hosts, and is its default transport on `wasm32` targets.
+///
+/// The host controls connections, proxies, TLS, cookies and redirects, so the corresponding
+/// builder settings have no effect, and it may refuse to send some headers, such as `User-Agent`
+/// and `Accept-Encoding`, or calls to other origins that do not allow them through CORS.
+/// Responses are decompressed by the host.
+#[derive(Clone, Debug, Default)]
+pub struct FetchTransport {
+    _private: (),
+}
+
+impl FetchTransport {
+    pub fn new() -> FetchTransport {
+        FetchTransport::default()
+    }
+}
+
+impl AsyncTransport for FetchTransport {
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>> {
+        Box::pin(exchange(request))
+    }
+}
+
+async fn exchange(call: &Request) -> Result<Reply> {
+    let headers = Headers::new().map_err(|err| js_error("Failed to create HTTP headers", err))?;
+    for (name, value) in call.headers() {
+        headers
+            .set(name, value)
+            .map_err(|err| js_error(&format!("Invalid HTTP header: {}", name), err))?;
+    }
+
+    // Aborting once the future is done or dropped, as when the call times out, cancels a
+    // request still in flight.
+    let controller =
+        AbortController::new().map_err(|err| js_error("Failed to create an abort signal", err))?;
+    let _abort = AbortOnDrop(controller.clone());
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_headers(&headers);
+    init.set_body(&Uint8Array::from(call.body()));
+    init.set_signal(Some(&controller.signal()));
+    let request = web_sys::Request::new_with_str_and_init(call.url().as_str(), &init)
+        .map_err(|err| js_error("Failed to build the HTTP request", err))?;
+
+    let response: Response = timer::first_byte(call.first_byte_timeout(), async {
+        JsFuture::from(fetch_with_request(&request))
+            .await
+            .and_then(|response| response.dyn_into())
+            .map_err(|err| js_error("Failed to run the HTTP request", err))
+    })
+    .await?;
+    let body = response
+        .array_buffer()
+        .map(JsFuture::from)
+        .map_err(|err| js_error("Failed to read the HTTP response body", err))?
+        .await
+        .map_err(|err| js_error("Failed to read the HTTP response body", err))?;
+
+    let mut reply = Reply::new(response.status(), Uint8Array::new(&body).to_vec());
+    let entries = js_sys::try_iter(&response.headers())
+        .map_err(|err| js_error("Failed to read the HTTP response headers", err))?
+        .ok_or("Failed to read the HTTP response headers.")?;
+    for entry in entries {
+        let entry =
+            entry.map_err(|err| js_error("Failed to read the HTTP response headers", err))?;
+        let entry = js_sys::Array::from(&entry);
+        let name = entry.get(0).as_string().unwrap_or_default();
+        // The body has already been decoded, so it must not be decompressed again.
+        if name.eq_ignore_ascii_case("content-encoding") {
+            continue;
+        }
+        reply = reply.with_header(name, entry.get(1).as_string().unwrap_or_default());
+    }
+    Ok(reply)
+}
+
+struct AbortOnDrop(AbortController);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// JavaScript errors cannot be chained, so their description is kept instead.
+fn js_error(context: &str, err: JsValue) -> Error {
+    let message = match err.dyn_ref::<js_sys::Error>() {
+        Some(err) => String::from(err.message()),
+        None => format!("{:?}", err),
+    };
+    ErrorKind::Transport(format!("{}: {}", context, message)).into()
+}
@@ -0,0 +1,208 @@
+use super::connector::{self, timeout_until, Connector};
+use super::proxy::Proxies;
+use super::{Reply, Request, Transport};
+use crate::error::{Error, ErrorKind, Result};
+use hyper::client::pool::{Pool, PooledStream};
+use hyper::client::Request as HyperRequest;
+use hyper::header::ContentLength;
+use hyper::http::h1::Http11Message;
+use hyper::http::HttpMessage;
+use hyper::method::Method;
+use hyper::net::{NetworkConnector, NetworkStream};
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+/// The built-in transport of `Client`, running on hyper's blocking client.
+pub struct HttpTransport<C: NetworkConnector = Connector> {
+    pub pool: Pool<C>,
+    pub read_timeout: Option<Duration>,
+    pub write_timeout: Option<Duration>,
+    pub proxies: Proxies,
+}
+
+impl<C, S> Transport for HttpTransport<C>
+where
+    C: NetworkConnector<Stream = S> + Send + Sync,
+    S: NetworkStream + Send,
+{
+    fn send(&self, request: &Request) -> Result<Reply> {
+        let url = request.url();
+        let host = url.host_str().ok_or("The URL has no host.")?;
+        let port = url.port_or_known_default().ok_or("The URL has no port.")?;
+        // The first byte timeout counts from the start, so it also covers connecting.
+        let head_deadline = match request.first_byte_timeout() {
+            Some(limit) => {
+                let limit = Instant::now() + limit;
+                Some(
+                    request
+                        .deadline()
+                        .map_or(limit, |deadline| deadline.min(limit)),
+                )
+            }
+            None => request.deadline(),
+        };
+        let stream = connector::with_deadline(head_deadline, || {
+            self.pool.connect(host, port, url.scheme())
+        })
+        .map_err(connect_error)?;
+        self.exchange(request, host, stream, head_deadline)
+            .map_err(transport_error)
+    }
+}
+
+impl<C, S> HttpTransport<C>
+where
+    C: NetworkConnector<Stream = S> + Send + Sync,
+    S: NetworkStream + Send,
+{
+    fn exchange(
+        &self,
+        call: &Request,
+        host: &str,
+        stream: PooledStream<S>,
+        head_deadline: Option<Instant>,
+    ) -> hyper::Result<Reply> {
+        let deadline = call.deadline();
+        let url = call.url();
+
+        // Plain HTTP requests are forwarded by the proxy, so they carry the full URL.
+        let proxy = match url.scheme() {
+            "http" => self
+                .proxies
+                .find("http", host)
+                .filter(|proxy| proxy.forwards_http()),
+            _ => None,
+        };
+        let mut message = Http11Message::with_stream(Box::new(stream));
+        message.set_proxied(proxy.is_some());
+        let mut request = HyperRequest::with_message(Method::Post, url.clone(), Box::new(message))?;
+        if let Some(authorization) = proxy.and_then(|proxy| proxy.authorization()) {
+            request
+                .headers_mut()
+                .set_raw("Proxy-Authorization", vec![authorization.into_bytes()]);
+        }
+        request
+            .headers_mut()
+            .set(ContentLength(call.body().len() as u64));
+        for (name, value) in call.headers() {
+            request
+                .headers_mut()
+                .set_raw(name.clone(), vec![value.clone().into_bytes()]);
+        }
+        request.set_write_timeout(timeout_until(self.write_timeout, head_deadline)?)?;
+        request.set_read_timeout(timeout_until(self.read_timeout, head_deadline)?)?;
+
+        let mut request = request.start()?;
+        request.write_all(call.body())?;
+        let mut response = request.send()?;
+
+        let mut content = Vec::new();
+        let mut buffer = [0; 8192];
+        loop {
+            response
+                .get_ref()
+                .set_read_timeout(timeout_until(self.read_timeout, deadline)?)?;
+            match response.read(&mut buffer)? {
+                0 => break,
+                n => content.extend_from_slice(&buffer[..n]),
+            }
+        }
+
+        let mut reply = Reply::new(response.status.to_u16(), content);
+        for name in response
+            .headers
+            .iter()
+            .map(|header| header.name().to_owned())
+        {
+            for value in response.headers.get_raw(&name).unwrap_or(&[]) {
+                reply = reply.with_header(name.as_str(), String::from_utf8_lossy(value));
+            }
+        }
+        Ok(reply)
+    }
+}
+
+/// Nothing has been sent while connecting, including through a proxy, so all failures of it,
+/// timeouts included, are of kind `Connect`.
+fn connect_error(err: hyper::Error) -> Error {
+    let details = "Failed to connect to the server.".to_owned();
+    Error::with_chain(err, ErrorKind::Connect(details))
+}
+
+fn transport_error(err: hyper::Error) -> Error {
+    if let hyper::Error::Io(ref err) = err {
+        if let io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock = err.kind() {
+            return ErrorKind::TimedOut(err.to_string()).into();
+        }
+    }
+    let details = "Failed to run the HTTP request within hyper.".to_owned();
+    Error::with_chain(err, ErrorKind::Transport(details))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ClientBuilder;
+    use super::*;
+    use crate::server::{Rejection, Rejections, Server};
+    use crate::xmlfmt::Value;
+    use socket2::{Domain, Socket, Type};
+    use std::net::{SocketAddr, TcpStream};
+
+    #[test]
+    fn bounds_connecting_by_the_first_byte_timeout() {
+        let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        socket.bind(&addr.into()).unwrap();
+        socket.listen(0).unwrap();
+        let addr = socket.local_addr().unwrap().as_socket().unwrap();
+        // Fill the backlog of the server that never accepts, so that further connections hang.
+        let _queued = (0..8)
+            .filter_map(|_| TcpStream::connect_timeout(&addr, Duration::from_millis(100)).ok())
+            .collect::<Vec<_>>();
+
+        let client = ClientBuilder::new()
+            .first_byte_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let url = format!("http://{}/", addr).parse().unwrap();
+        let start = Instant::now();
+        let err = client.call_value(&url, "echo", Vec::new()).unwrap_err();
+        assert!(matches!(*err.kind(), ErrorKind::Connect(_)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn sends_calls_as_xml() {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        server.set_rejections(Rejections::new().wrong_content_type(Rejection::new(415)));
+        let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let url = format!("http://{}/", bound.local_addr()).parse().unwrap();
+        std::thread::spawn(move || bound.run());
+
+        let client = ClientBuilder::new().build().unwrap();
+        let params = vec![Value::Int(4)];
+        let response = client.call_value(&url, "echo", params.clone()).unwrap();
+        assert_eq!(response, Ok(params));
+    }
+
+    #[test]
+    fn sends_values_as_themselves() {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let url = format!("http://{}/", bound.local_addr()).parse().unwrap();
+        std::thread::spawn(move || bound.run());
+
+        let client = ClientBuilder::new().capture_wire(true).build().unwrap();
+        let response: Value = client.call(&url, "echo", Value::Int(3)).unwrap().unwrap();
+        assert_eq!(response, Value::Int(3));
+        let exchange = client.last_exchange().unwrap();
+        let body = String::from_utf8_lossy(exchange.request().body()).into_owned();
+        assert!(
+            body.contains("<param><value><i4>3</i4></value></param>"),
+            "{}",
+            body
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use super::timer;
+use super::{AsyncTransport, BoxFuture, Reply, Request};
+use crate::error::{Error, ErrorKind, Result, ResultExt};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper1::body::Body;
+use hyper1::header::{HeaderName, HeaderValue};
+use hyper1::{Method, Request as HyperRequest, Response as HyperResponse, Uri};
+use std::future::poll_fn;
+use tower_service::Service;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Carries the calls of an `AsyncClient` over any hyper based `tower` service.
+///
+/// The service can be a `hyper_util` client with its own executor and connector, a stack of
+/// `tower` middleware around one, or a wrapper of an HTTP/2 connection. It is cloned for every
+/// call, as is usual for services, so it should be cheap to clone.
+#[derive(Clone, Debug)]
+pub struct HyperTransport<S> {
+    service: S,
+}
+
+impl<S> HyperTransport<S> {
+    pub fn new(service: S) -> HyperTransport<S> {
+        HyperTransport { service }
+    }
+}
+
+impl<S, B> HyperTransport<S>
+where
+    S: Service<HyperRequest<Full<Bytes>>, Response = HyperResponse<B>> + Clone,
+    S::Error: Into<BoxError>,
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    async fn exchange(&self, call: &Request) -> Result<Reply> {
+        let uri: Uri = call
+            .url()
+            .as_str()
+            .parse()
+            .chain_err(|| "Failed to convert the URL for hyper.")?;
+        let mut request = HyperRequest::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Full::new(Bytes::from(call.body().to_vec())))
+            .chain_err(|| "Failed to build the HTTP request.")?;
+        for (name, value) in call.headers() {
+            request.headers_mut().insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+                HeaderValue::from_str(value)
+                    .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+            );
+        }
+
+        let mut service = self.service.clone();
+        let response = timer::first_byte(call.first_byte_timeout(), async {
+            poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(|err| transport_error("Failed to run the HTTP request", err.into()))?;
+            service
+                .call(request)
+                .await
+                .map_err(|err| transport_error("Failed to run the HTTP request", err.into()))
+        })
+        .await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_owned(), value)
+            })
+            .collect::<Vec<_>>();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|err| transport_error("Failed to read the HTTP response body", err.into()))?
+            .to_bytes();
+        let reply = Reply::new(status, body.to_vec());
+        Ok(headers
+            .into_iter()
+            .fold(reply, |reply, (name, value)| reply.with_header(name, value)))
+    }
+}
+
+impl<S, B> AsyncTransport for HyperTransport<S>
+where
+    S: Service<HyperRequest<Full<Bytes>>, Response = HyperResponse<B>> + Clone + Send + Sync,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>> {
+        Box::pin(self.exchange(request))
+    }
+}
+
+/// Boxed errors cannot be chained, so their message is kept instead.
+fn transport_error(context: &str, err: BoxError) -> Error {
+    // The clients of hyper-util tell failures to connect, before anything is sent, apart.
+    let connect = err
+        .downcast_ref::<hyper_util::client::legacy::Error>()
+        .is_some_and(|err| err.is_connect());
+    if connect {
+        return ErrorKind::Connect(format!("Failed to connect to the server: {}", err)).into();
+    }
+    ErrorKind::Transport(format!("{}: {}", context, err)).into()
+}
@@ -0,0 +1,48 @@
+use super::Reply;
+use std::time::Duration;
+
+/// The HTTP details of the reply to a call, returned along with its response by the
+/// `_with_meta` methods of the clients.
+#[derive(Clone, Debug)]
+pub struct ResponseMeta {
+    status: u16,
+    headers: Vec<(String, String)>,
+    elapsed: Duration,
+}
+
+impl ResponseMeta {
+    pub(super) fn new(reply: &Reply) -> ResponseMeta {
+        ResponseMeta {
+            status: reply.status(),
+            headers: reply.headers().to_vec(),
+            elapsed: Duration::default(),
+        }
+    }
+
+    pub(super) fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = elapsed;
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the first value of the header, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The time the call took, from before the first request was sent until the response was
+    /// parsed, including any retries and redirects.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
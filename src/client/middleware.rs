@@ -0,0 +1,19 @@
+use super::{Reply, Request};
+use crate::error::Result;
+
+/// Hooks into every HTTP exchange of a client, for logging, signing, metrics or header
+/// injection.
+///
+/// Middleware sees each attempt of a retried call separately. `on_request` runs in the order
+/// the middleware was added, after authentication, so it sees the request exactly as it is sent.
+/// `on_response` runs in reverse order, before the reply is interpreted, and an error from
+/// either hook fails the attempt.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_response(&self, _request: &Request, _reply: &Reply) -> Result<()> {
+        Ok(())
+    }
+}
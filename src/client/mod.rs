@@ -0,0 +1,462 @@
+use self::observer::Outcome;
+use self::request::Config;
+use super::error::Result;
+use super::xmlfmt::{
+    from_params, into_params_with, Call, Extensions, Fault, Params, ParseOptions, Response, Value,
+};
+use crate::Url;
+use serde::{Deserialize, Serialize};
+use std;
+use std::io::Write;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(all(
+    feature = "async",
+    not(feature = "async-std"),
+    not(target_arch = "wasm32")
+))]
+mod async_connector;
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+mod async_io_transport;
+#[cfg(all(
+    feature = "async",
+    not(feature = "async-std"),
+    not(target_arch = "wasm32")
+))]
+mod async_transport;
+mod auth;
+#[cfg(feature = "async")]
+mod batch;
+mod builder;
+#[cfg(feature = "async")]
+mod cancel;
+mod capabilities;
+mod capture;
+mod compression;
+mod connector;
+mod cookie;
+mod dns;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+mod fetch_transport;
+mod http_transport;
+#[cfg(all(feature = "transport-hyper", not(target_arch = "wasm32")))]
+mod hyper_transport;
+mod meta;
+mod middleware;
+mod multicall;
+mod observer;
+mod proxy;
+mod redirect;
+mod request;
+#[cfg(all(feature = "transport-reqwest", not(target_arch = "wasm32")))]
+mod reqwest_transport;
+mod retry;
+#[cfg(feature = "socks")]
+mod socks;
+#[cfg(feature = "async")]
+mod timer;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+#[cfg(unix)]
+mod unix;
+#[cfg(all(feature = "transport-ureq", not(target_arch = "wasm32")))]
+mod ureq_transport;
+
+#[cfg(feature = "async")]
+pub use self::async_client::AsyncClient;
+pub use self::auth::Authenticator;
+#[cfg(feature = "async")]
+pub use self::batch::{BatchExecutor, BatchStream};
+pub use self::builder::{CallOptions, ClientBuilder, DEFAULT_USER_AGENT};
+#[cfg(feature = "async")]
+pub use self::cancel::CancellationToken;
+pub use self::capabilities::{Capabilities, Capability};
+pub use self::capture::Exchange;
+pub use self::dns::Resolve;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+pub use self::fetch_transport::FetchTransport;
+#[cfg(all(feature = "transport-hyper", not(target_arch = "wasm32")))]
+pub use self::hyper_transport::HyperTransport;
+pub use self::meta::ResponseMeta;
+pub use self::middleware::Middleware;
+pub use self::observer::{CallObserver, CallOutcome};
+pub use self::redirect::RedirectPolicy;
+pub use self::request::Request;
+#[cfg(all(feature = "transport-reqwest", not(target_arch = "wasm32")))]
+pub use self::reqwest_transport::{BlockingReqwestTransport, ReqwestTransport};
+pub use self::retry::{is_transient, RetryPolicy};
+#[cfg(feature = "tls")]
+pub use self::tls::Identity;
+#[cfg(feature = "async")]
+pub use self::transport::{AsyncTransport, BoxFuture};
+pub use self::transport::{Reply, Transport};
+#[cfg(all(feature = "transport-ureq", not(target_arch = "wasm32")))]
+pub use self::ureq_transport::UreqTransport;
+
+lazy_static! {
+    /// Backs the free functions, so that consecutive calls share kept-alive connections.
+    static ref DEFAULT_CLIENT: std::result::Result<Client, String> =
+        Client::new().map_err(|err| err.to_string());
+}
+
+fn default_client() -> Result<&'static Client> {
+    DEFAULT_CLIENT.as_ref().map_err(|err| err.as_str().into())
+}
+
+pub fn call_value<Tkey>(uri: &Url, name: Tkey, params: Params) -> Result<Response>
+where
+    Tkey: Into<String>,
+{
+    default_client()?.call_value(uri, name, params)
+}
+
+pub fn call<'a, Tkey, Treq, Tres>(
+    uri: &Url,
+    name: Tkey,
+    req: Treq,
+) -> Result<std::result::Result<Tres, Fault>>
+where
+    Tkey: Into<String>,
+    Treq: Serialize,
+    Tres: Deserialize<'a>,
+{
+    default_client()?.call(uri, name, req)
+}
+
+/// Blocking XML-RPC client.
+///
+/// Connections are kept alive and reused between calls. The client is cheap to clone, with clones
+/// sharing connections and cookies, and calls can be made from several threads.
+#[derive(Clone)]
+pub struct Client {
+    transport: Arc<dyn Transport>,
+    config: Config,
+}
+
+impl Client {
+    pub fn new() -> Result<Client> {
+        ClientBuilder::new().build()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Creates a client that sends all calls over the Unix domain socket at `path`.
+    ///
+    /// See `ClientBuilder::unix_socket` for how call URLs are used.
+    #[cfg(unix)]
+    pub fn connect_uds<P: Into<std::path::PathBuf>>(path: P) -> Result<Client> {
+        ClientBuilder::new().unix_socket(path).build()
+    }
+
+    /// Encodes the parameters of typed calls with the extensions, as those the server tells it
+    /// implements, in `capabilities(..)?.extensions()`.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.config.extensions = extensions;
+    }
+
+    /// Reads responses with the options, tolerating the deviations from the specification
+    /// they allow, or none with `ParseOptions::strict`.
+    pub fn set_parse_options(&mut self, options: ParseOptions) {
+        self.config.parse_options = options;
+    }
+
+    /// The URL set with `ClientBuilder::url`, which calls made with `invoke` go to.
+    pub fn url(&self) -> Option<&Url> {
+        self.config.url.as_ref()
+    }
+
+    /// The last HTTP exchange made by the client or its clones, if it was built with
+    /// `ClientBuilder::capture_wire`.
+    pub fn last_exchange(&self) -> Option<Exchange> {
+        self.config.last_exchange()
+    }
+
+    /// Makes the call to the client's URL, like `call_value` does to a given one.
+    pub fn invoke_value<Tkey>(&self, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.call_value(self.config.url()?, name, params)
+    }
+
+    /// Makes the call to the client's URL, like `call` does to a given one.
+    pub fn invoke<'a, Tkey, Treq, Tres>(
+        &self,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        self.call(self.config.url()?, name, req)
+    }
+
+    /// Makes the call with the given values as parameters, returning the values of the result.
+    ///
+    /// The values are written out directly, without going through serde, which suits callers
+    /// building them dynamically, such as proxies and gateways.
+    pub fn call_value<Tkey>(&self, uri: &Url, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.call_value_with_options(uri, name, params, &CallOptions::default())
+    }
+
+    pub fn call_value_with_options<Tkey>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        self.exchange(uri, name.into(), params, options, |reply| {
+            self.config.response(reply)
+        })
+    }
+
+    /// Makes the call like `call_value_with_options`, also returning the status, headers and
+    /// duration of the HTTP reply, as needed to read rate limits or the version of the server.
+    pub fn call_value_with_meta<Tkey>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+    ) -> Result<(Response, ResponseMeta)>
+    where
+        Tkey: Into<String>,
+    {
+        let start = Instant::now();
+        let (response, meta) = self.exchange(uri, name.into(), params, options, |reply| {
+            let meta = ResponseMeta::new(&reply);
+            self.config.response(reply).map(|response| (response, meta))
+        })?;
+        Ok((response, meta.with_elapsed(start.elapsed())))
+    }
+
+    /// Makes the call like `call_value`, but decodes all base64 values of the response into
+    /// `sink` in document order, instead of holding them in the returned values, which are left
+    /// empty.
+    ///
+    /// This suits downloads of large files. If parsing the response fails, part of the data may
+    /// already have been written.
+    pub fn call_value_base64_into<Tkey, W>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        sink: &mut W,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+        W: Write,
+    {
+        self.call_value_base64_into_with_options(uri, name, params, &CallOptions::default(), sink)
+    }
+
+    pub fn call_value_base64_into_with_options<Tkey, W>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        params: Params,
+        options: &CallOptions,
+        sink: &mut W,
+    ) -> Result<Response>
+    where
+        Tkey: Into<String>,
+        W: Write,
+    {
+        self.exchange(uri, name.into(), params, options, |reply| {
+            self.config.response_base64_into(reply, sink)
+        })
+    }
+
+    /// Sends the call, retrying and following redirects according to the client's policies, and
+    /// interprets the final reply.
+    fn exchange<T, F>(
+        &self,
+        uri: &Url,
+        name: String,
+        params: Params,
+        options: &CallOptions,
+        mut interpret: F,
+    ) -> Result<T>
+    where
+        T: Outcome,
+        F: FnMut(Reply) -> Result<T>,
+    {
+        use super::xmlfmt::value::ToXml;
+        let call = Call { name, params };
+        let body_str = call.to_xml();
+
+        let started = Instant::now();
+        let result = self.attempts(&call.name, uri, &body_str, options, &mut interpret);
+        self.config.report(&call.name, started.elapsed(), &result);
+        result
+    }
+
+    fn attempts<T, F>(
+        &self,
+        method: &str,
+        uri: &Url,
+        body: &str,
+        options: &CallOptions,
+        interpret: &mut F,
+    ) -> Result<T>
+    where
+        F: FnMut(Reply) -> Result<T>,
+    {
+        let deadline = self
+            .config
+            .deadline(options)
+            .map(|deadline| Instant::now() + deadline);
+        let mut attempt = 1;
+        loop {
+            let err = match self.attempt(uri, body, options, deadline, interpret) {
+                Err(err) => err,
+                result => return result,
+            };
+            let backoff = match self.config.retry.backoff_after(attempt, method, &err) {
+                Some(backoff) => backoff,
+                None => return Err(err),
+            };
+            if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                return Err(err);
+            }
+            thread::sleep(backoff);
+            attempt += 1;
+        }
+    }
+
+    fn attempt<T, F>(
+        &self,
+        uri: &Url,
+        body: &str,
+        options: &CallOptions,
+        deadline: Option<Instant>,
+        interpret: &mut F,
+    ) -> Result<T>
+    where
+        F: FnMut(Reply) -> Result<T>,
+    {
+        let mut url = uri.clone();
+        let mut hops = 0;
+        loop {
+            let mut request = self.config.request(uri, &url, body, options)?;
+            request.set_deadline(deadline);
+            let reply = self.transport.send(&request)?;
+            self.config.observe(&request, &reply)?;
+            match self.config.redirect.target(&request, &reply, hops)? {
+                Some(target) => url = target,
+                None => return interpret(reply),
+            }
+            hops += 1;
+        }
+    }
+
+    /// Sends the calls in a single `system.multicall` request, returning their results in the
+    /// same order.
+    ///
+    /// A fault of the multicall itself, as from servers not supporting it, is returned as the
+    /// outer fault.
+    pub fn multicall(
+        &self,
+        uri: &Url,
+        calls: &[Call],
+    ) -> Result<std::result::Result<Vec<std::result::Result<Value, Fault>>, Fault>> {
+        match self.call_value(uri, "system.multicall", multicall::pack(calls))? {
+            Ok(params) => multicall::unpack(params, calls.len()).map(Ok),
+            Err(fault) => Ok(Err(fault)),
+        }
+    }
+
+    /// Asks the server which specifications and extensions it implements, with
+    /// `system.getCapabilities`.
+    ///
+    /// Servers that do not support this respond with a fault, which is returned as the outer one.
+    pub fn capabilities(&self, uri: &Url) -> Result<std::result::Result<Capabilities, Fault>> {
+        match self.call_value(uri, "system.getCapabilities", Vec::new())? {
+            Ok(params) => Capabilities::from_params(params).map(Ok),
+            Err(fault) => Ok(Err(fault)),
+        }
+    }
+
+    /// Makes the call with parameters serialized from `req`, deserializing the result into `Tres`.
+    ///
+    /// A fault is returned as the inner error, and `?` converts it into an error of kind
+    /// `ErrorKind::Fault`, so `client.call(..)??` yields the result or an error either way.
+    pub fn call<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        self.call_with_options(uri, name, req, &CallOptions::default())
+    }
+
+    pub fn call_with_options<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+        options: &CallOptions,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        match self.call_value_with_options(
+            uri,
+            name,
+            into_params_with(&req, self.config.extensions)?,
+            options,
+        ) {
+            Ok(Ok(v)) => from_params(v).map(Ok).map_err(Into::into),
+            Ok(Err(v)) => Ok(Err(v)),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Makes the call like `call_with_options`, also returning the HTTP details of the reply like
+    /// `call_value_with_meta`.
+    pub fn call_with_meta<'a, Tkey, Treq, Tres>(
+        &self,
+        uri: &Url,
+        name: Tkey,
+        req: Treq,
+        options: &CallOptions,
+    ) -> Result<(std::result::Result<Tres, Fault>, ResponseMeta)>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'a>,
+    {
+        match self.call_value_with_meta(
+            uri,
+            name,
+            into_params_with(&req, self.config.extensions)?,
+            options,
+        )? {
+            (Ok(v), meta) => Ok((Ok(from_params(v)?), meta)),
+            (Err(fault), meta) => Ok((Err(fault), meta)),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::error::Result;
+use crate::xmlfmt::{Call, Fault, Members, Params, Value};
+
+/// Packs the calls into the single parameter of a `system.multicall` call.
+pub fn pack(calls: &[Call]) -> Params {
+    let calls = calls
+        .iter()
+        .map(|call| {
+            let mut entry = Members::new();
+            entry.insert("methodName".to_owned(), Value::String(call.name.clone()));
+            entry.insert("params".to_owned(), Value::Array(call.params.clone()));
+            Value::Struct(entry)
+        })
+        .collect();
+    vec![Value::Array(calls)]
+}
+
+/// Unpacks the results of a `system.multicall` call, which come as one-element arrays for
+/// successful calls and as fault structs for failed ones.
+pub fn unpack(mut params: Params, count: usize) -> Result<Vec<std::result::Result<Value, Fault>>> {
+    let results = match (params.pop(), params.is_empty()) {
+        (Some(Value::Array(results)), true) if results.len() == count => results,
+        _ => bail!("Invalid system.multicall response."),
+    };
+    results
+        .into_iter()
+        .map(|result| match result {
+            Value::Array(mut values) if values.len() == 1 => Ok(Ok(values.remove(0))),
+            Value::Struct(fault) => Ok(Err(Fault::from_value(Value::Struct(fault))?)),
+            _ => bail!("Invalid result in system.multicall response."),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fault(code: i32, message: &str) -> Value {
+        let mut fault = Members::new();
+        fault.insert("faultCode".to_owned(), Value::Int(code));
+        fault.insert("faultString".to_owned(), Value::String(message.into()));
+        Value::Struct(fault)
+    }
+
+    #[test]
+    fn packs_calls_as_structs() {
+        let calls = vec![
+            Call {
+                name: "add".into(),
+                params: vec![Value::Int(1), Value::Int(2)],
+            },
+            Call {
+                name: "ping".into(),
+                params: vec![],
+            },
+        ];
+        let params = pack(&calls);
+        assert_eq!(params.len(), 1);
+        let entries = match params[0] {
+            Value::Array(ref entries) => entries,
+            _ => panic!("expected an array"),
+        };
+        assert_eq!(entries.len(), 2);
+        match entries[0] {
+            Value::Struct(ref entry) => {
+                assert_eq!(entry["methodName"], Value::String("add".into()));
+                assert_eq!(
+                    entry["params"],
+                    Value::Array(vec![Value::Int(1), Value::Int(2)])
+                );
+            }
+            _ => panic!("expected a struct"),
+        }
+    }
+
+    #[test]
+    fn unpacks_results_mixed_with_faults() {
+        let results = unpack(
+            vec![Value::Array(vec![
+                Value::Array(vec![Value::Int(3)]),
+                fault(4, "Too many parameters."),
+                Value::Array(vec![Value::String("pong".into())]),
+            ])],
+            3,
+        )
+        .unwrap();
+        assert_eq!(results[0], Ok(Value::Int(3)));
+        assert_eq!(results[1], Err(Fault::new(4, "Too many parameters.")));
+        assert_eq!(results[2], Ok(Value::String("pong".into())));
+    }
+
+    #[test]
+    fn rejects_malformed_responses() {
+        let results = || Value::Array(vec![Value::Array(vec![Value::Int(3)])]);
+        assert!(unpack(vec![results()], 2).is_err());
+        assert!(unpack(vec![Value::Int(3)], 1).is_err());
+        assert!(unpack(vec![results(), results()], 1).is_err());
+        let empty = Value::Array(vec![Value::Array(vec![])]);
+        assert!(unpack(vec![empty], 1).is_err());
+        let bare = Value::Array(vec![Value::Int(3)]);
+        assert!(unpack(vec![bare], 1).is_err());
+        let partial = Value::Array(vec![fault(4, "x"), Value::Struct(Members::new())]);
+        assert!(unpack(vec![partial], 2).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::error::{Error, Result};
+use crate::xmlfmt::{Fault, Response};
+use std::time::Duration;
+
+/// Receives the method name, duration and outcome of every call a client makes, to feed
+/// metrics such as latency histograms without wrapping each call site.
+///
+/// The observer runs once per call, after any retries and redirects, with the time the call took
+/// in total. Async calls whose future is dropped before completing are not reported.
+pub trait CallObserver: Send + Sync {
+    fn on_call(&self, method: &str, elapsed: Duration, outcome: CallOutcome);
+}
+
+impl<T> CallObserver for T
+where
+    T: Fn(&str, Duration, CallOutcome) + Send + Sync,
+{
+    fn on_call(&self, method: &str, elapsed: Duration, outcome: CallOutcome) {
+        self(method, elapsed, outcome)
+    }
+}
+
+/// How a call ended, as reported to a `CallObserver`.
+#[derive(Clone, Copy, Debug)]
+pub enum CallOutcome<'a> {
+    /// The server returned a result.
+    Success,
+    /// The server returned a fault.
+    Fault(&'a Fault),
+    /// The call failed without a response, such as through a transport error or a timeout.
+    Error(&'a Error),
+}
+
+impl CallOutcome<'_> {
+    pub fn is_success(&self) -> bool {
+        matches!(*self, CallOutcome::Success)
+    }
+}
+
+/// The values calls result in, which may carry a fault.
+pub(super) trait Outcome {
+    fn fault(&self) -> Option<&Fault>;
+}
+
+impl Outcome for Response {
+    fn fault(&self) -> Option<&Fault> {
+        self.as_ref().err()
+    }
+}
+
+impl<T> Outcome for (Response, T) {
+    fn fault(&self) -> Option<&Fault> {
+        self.0.fault()
+    }
+}
+
+pub(super) fn outcome<T: Outcome>(result: &Result<T>) -> CallOutcome<'_> {
+    match *result {
+        Ok(ref value) => match value.fault() {
+            Some(fault) => CallOutcome::Fault(fault),
+            None => CallOutcome::Success,
+        },
+        Err(ref err) => CallOutcome::Error(err),
+    }
+}
@@ -0,0 +1,173 @@
+use super::{Reply, Request};
+use crate::error::Result;
+use crate::Url;
+use std::fmt;
+use std::sync::Arc;
+
+type Inspect = dyn Fn(&Url, &Url) -> bool + Send + Sync;
+
+/// Decides whether calls follow redirects with a 301, 302, 303, 307 or 308 status.
+///
+/// Redirected calls are sent again with the same body, as XML-RPC calls are always `POST`s. The
+/// `Authorization` header set on the builder or the call is dropped once a redirect leaves the
+/// scheme, host and port of the original URL, while authenticators and middleware still run.
+#[derive(Clone)]
+pub struct RedirectPolicy {
+    max_hops: usize,
+    inspect: Option<Arc<Inspect>>,
+}
+
+impl Default for RedirectPolicy {
+    /// Follows no redirects, which is the behaviour of clients without a redirect policy.
+    fn default() -> RedirectPolicy {
+        RedirectPolicy::none()
+    }
+}
+
+impl fmt::Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RedirectPolicy")
+            .field("max_hops", &self.max_hops)
+            .finish()
+    }
+}
+
+impl RedirectPolicy {
+    /// Treats redirects like any other unsuccessful HTTP status.
+    pub fn none() -> RedirectPolicy {
+        RedirectPolicy::limited(0)
+    }
+
+    /// Follows up to `max_hops` redirects per call, failing the call if there are more.
+    pub fn limited(max_hops: usize) -> RedirectPolicy {
+        RedirectPolicy {
+            max_hops,
+            inspect: None,
+        }
+    }
+
+    /// Calls `inspect` with the current and the target URL before following a redirect. If it
+    /// returns `false`, the redirect is not followed and fails the call with its status.
+    pub fn inspect<F>(mut self, inspect: F) -> Self
+    where
+        F: Fn(&Url, &Url) -> bool + Send + Sync + 'static,
+    {
+        self.inspect = Some(Arc::new(inspect));
+        self
+    }
+
+    /// Returns the URL to send the call to next, if the reply redirects it.
+    pub(super) fn target(
+        &self,
+        request: &Request,
+        reply: &Reply,
+        hops: usize,
+    ) -> Result<Option<Url>> {
+        if self.max_hops == 0 {
+            return Ok(None);
+        }
+        match reply.status() {
+            301 | 302 | 303 | 307 | 308 => {}
+            _ => return Ok(None),
+        }
+        let location = match reply.header_values("Location").next() {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        let target = match request.url().join(location) {
+            Ok(target) => target,
+            Err(_) => bail!("Invalid redirect location: {}", location),
+        };
+        if let Some(ref inspect) = self.inspect {
+            if !inspect(request.url(), &target) {
+                return Ok(None);
+            }
+        }
+        if hops >= self.max_hops {
+            bail!("Too many redirects, the last one to {}", target);
+        }
+        Ok(Some(target))
+    }
+}
+
+/// Whether a redirect to `target` stays with the server the call was originally sent to.
+pub(super) fn same_origin(original: &Url, target: &Url) -> bool {
+    original.scheme() == target.scheme()
+        && original.host_str() == target.host_str()
+        && original.port_or_known_default() == target.port_or_known_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CallOptions, ClientBuilder};
+    use super::*;
+
+    fn request() -> Request {
+        Request::new("http://example.com/rpc".parse().unwrap(), Vec::new())
+    }
+
+    fn redirect(status: u16, location: &str) -> Reply {
+        Reply::new(status, Vec::new()).with_header("Location", location)
+    }
+
+    #[test]
+    fn follows_redirect_statuses() {
+        let policy = RedirectPolicy::limited(1);
+        for &status in &[301, 302, 303, 307, 308] {
+            let target = policy.target(&request(), &redirect(status, "/v2"), 0);
+            assert_eq!(target.unwrap().unwrap().as_str(), "http://example.com/v2");
+        }
+        let target = policy.target(&request(), &redirect(200, "/v2"), 0);
+        assert!(target.unwrap().is_none());
+        let target = policy.target(&request(), &Reply::new(302, Vec::new()), 0);
+        assert!(target.unwrap().is_none());
+        let target = RedirectPolicy::none().target(&request(), &redirect(302, "/v2"), 0);
+        assert!(target.unwrap().is_none());
+    }
+
+    #[test]
+    fn limits_hops() {
+        let policy = RedirectPolicy::limited(2);
+        let reply = redirect(302, "https://other.com/rpc");
+        assert!(policy.target(&request(), &reply, 1).unwrap().is_some());
+        assert!(policy.target(&request(), &reply, 2).is_err());
+    }
+
+    #[test]
+    fn lets_inspect_veto_redirects() {
+        let policy = RedirectPolicy::limited(1).inspect(|_, target| target.scheme() == "https");
+        let target = policy.target(&request(), &redirect(301, "http://other.com/"), 0);
+        assert!(target.unwrap().is_none());
+        let target = policy.target(&request(), &redirect(301, "https://other.com/"), 0);
+        assert!(target.unwrap().is_some());
+    }
+
+    #[test]
+    fn compares_origins() {
+        let url = |url: &str| url.parse::<Url>().unwrap();
+        let origin = url("http://example.com/rpc");
+        assert!(same_origin(&origin, &url("http://example.com:80/v2")));
+        assert!(!same_origin(&origin, &url("https://example.com/rpc")));
+        assert!(!same_origin(&origin, &url("http://example.com:8080/rpc")));
+        assert!(!same_origin(&origin, &url("http://api.example.com/rpc")));
+    }
+
+    #[test]
+    fn drops_authorization_across_origins() {
+        let config = ClientBuilder::new()
+            .header("Authorization", "Bearer secret")
+            .config()
+            .unwrap();
+        let origin = "http://example.com/rpc".parse().unwrap();
+        let options = CallOptions::new();
+        let same = "http://example.com/v2".parse().unwrap();
+        let request = config.request(&origin, &same, "<call/>", &options).unwrap();
+        assert_eq!(request.header("Authorization"), Some("Bearer secret"));
+        let other = "http://other.com/rpc".parse().unwrap();
+        let request = config
+            .request(&origin, &other, "<call/>", &options)
+            .unwrap();
+        assert_eq!(request.header("Authorization"), None);
+        assert_eq!(request.body(), b"<call/>");
+    }
+}
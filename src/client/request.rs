@@ -0,0 +1,241 @@
+use super::auth::Authenticator;
+use super::capture::{Exchange, WireCapture};
+use super::compression;
+use super::cookie::CookieJar;
+use super::middleware::Middleware;
+use super::observer::{self, CallObserver, Outcome};
+use super::redirect::{self, RedirectPolicy};
+use super::retry::RetryPolicy;
+use super::{CallOptions, Reply};
+use crate::error::{ErrorKind, Result};
+use crate::xmlfmt::{parse, Encoding, Extensions, ParseOptions, Response};
+use crate::Url;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// An XML-RPC call as it is about to be sent over HTTP.
+///
+/// It carries every header but the framing ones, like `Content-Length`, which the transport
+/// adds when writing the request. Calls are sent as `text/xml` unless a `Content-Type` is set.
+#[derive(Clone, Debug)]
+pub struct Request {
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    deadline: Option<Instant>,
+    first_byte_timeout: Option<Duration>,
+}
+
+impl Request {
+    pub fn new(url: Url, body: Vec<u8>) -> Request {
+        Request {
+            url,
+            headers: Vec::new(),
+            body,
+            deadline: None,
+            first_byte_timeout: None,
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// The point in time by which the call has to be answered, if it has a deadline.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// The time the server may take to start responding, counted from when the transport starts
+    /// sending the request, including any time spent connecting.
+    pub fn first_byte_timeout(&self) -> Option<Duration> {
+        self.first_byte_timeout
+    }
+
+    pub fn set_first_byte_timeout(&mut self, timeout: Option<Duration>) {
+        self.first_byte_timeout = timeout;
+    }
+
+    /// Returns the value of the header, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|&(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the header, replacing any previous value of the same name.
+    pub fn set_header<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        self.remove_header(&name);
+        self.headers.push((name, value.into()));
+    }
+
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+    }
+}
+
+/// Client-wide behaviour shared by the blocking and the async clients.
+#[derive(Clone)]
+pub(super) struct Config {
+    pub url: Option<Url>,
+    pub timeout: Option<Duration>,
+    pub first_byte_timeout: Option<Duration>,
+    pub headers: Vec<(String, String)>,
+    pub authenticator: Option<Arc<dyn Authenticator>>,
+    pub middleware: Vec<Arc<dyn Middleware>>,
+    pub observer: Option<Arc<dyn CallObserver>>,
+    pub cookies: Option<Arc<CookieJar>>,
+    pub capture: Option<Arc<WireCapture>>,
+    pub decompress: bool,
+    pub compress_threshold: Option<usize>,
+    pub encoding: Encoding,
+    pub extensions: Extensions,
+    pub parse_options: ParseOptions,
+    pub retry: RetryPolicy,
+    pub redirect: RedirectPolicy,
+}
+
+impl Config {
+    /// The URL of calls made without one.
+    pub fn url(&self) -> Result<&Url> {
+        match self.url {
+            Some(ref url) => Ok(url),
+            None => bail!("No URL was set on the client builder."),
+        }
+    }
+
+    /// The time the call may take in total, if limited.
+    pub fn deadline(&self, options: &CallOptions) -> Option<Duration> {
+        options.deadline.or(self.timeout)
+    }
+
+    /// Assembles the HTTP request for a call, applying client defaults and per-call overrides.
+    ///
+    /// `origin` is the URL the call was made with, which differs from `url` after redirects.
+    pub fn request(
+        &self,
+        origin: &Url,
+        url: &Url,
+        body: &str,
+        options: &CallOptions,
+    ) -> Result<Request> {
+        let mut request = Request::new(url.clone(), self.encoding.encode(body));
+        request.set_first_byte_timeout(self.first_byte_timeout);
+        for (name, value) in self.headers.iter().chain(&options.headers) {
+            request.set_header(name.as_str(), value.as_str());
+        }
+        // The charset of a text type overrides the XML declaration, so it has to match.
+        if request.header("Content-Type").is_none() {
+            let content_type = match self.encoding {
+                Encoding::Utf8 => "text/xml".to_owned(),
+                ref encoding => format!("text/xml; charset={}", encoding.name()),
+            };
+            request.set_header("Content-Type", content_type);
+        }
+        if !redirect::same_origin(origin, url) {
+            request.remove_header("Authorization");
+        }
+        if let Some(cookies) = self.cookies.as_ref().and_then(|jar| jar.header(url)) {
+            let cookies = match request.header("Cookie") {
+                Some(existing) => format!("{}; {}", existing, cookies),
+                None => cookies,
+            };
+            request.set_header("Cookie", cookies);
+        }
+        if self.decompress && request.header("Accept-Encoding").is_none() {
+            request.set_header("Accept-Encoding", compression::ACCEPT_ENCODING);
+        }
+        if let Some(threshold) = self.compress_threshold {
+            if request.body.len() >= threshold && request.header("Content-Encoding").is_none() {
+                request.body = compression::gzip(&request.body)?;
+                request.set_header("Content-Encoding", "gzip");
+            }
+        }
+        if let Some(ref authenticator) = self.authenticator {
+            authenticator.authenticate(&mut request)?;
+        }
+        for middleware in &self.middleware {
+            middleware.on_request(&mut request)?;
+        }
+        super::builder::check_headers(request.headers())?;
+        if let Some(ref capture) = self.capture {
+            capture.request(&request);
+        }
+        Ok(request)
+    }
+
+    /// Runs the response middleware on the reply to a call, and takes note of the cookies set
+    /// by it, before the reply is followed as a redirect or interpreted.
+    pub fn observe(&self, request: &Request, reply: &Reply) -> Result<()> {
+        if let Some(ref capture) = self.capture {
+            capture.reply(request, reply);
+        }
+        for middleware in self.middleware.iter().rev() {
+            middleware.on_response(request, reply)?;
+        }
+        if let Some(ref jar) = self.cookies {
+            jar.store(request.url(), reply.header_values("Set-Cookie"));
+        }
+        Ok(())
+    }
+
+    /// Tells the observer, if any, how the call of the method went.
+    pub fn report<T: Outcome>(&self, method: &str, elapsed: Duration, result: &Result<T>) {
+        if let Some(ref observer) = self.observer {
+            observer.on_call(method, elapsed, observer::outcome(result));
+        }
+    }
+
+    pub fn last_exchange(&self) -> Option<Exchange> {
+        self.capture.as_ref().and_then(|capture| capture.last())
+    }
+
+    /// Interprets the reply to a call, parsing its decompressed body.
+    pub fn response(&self, reply: Reply) -> Result<Response> {
+        let body = self.reply_body(reply)?;
+        parse::response_with(body.as_slice(), self.parse_options).map_err(Into::into)
+    }
+
+    /// Parses the reply like `response`, decoding its base64 values into `sink`.
+    pub fn response_base64_into<W: Write>(&self, reply: Reply, sink: &mut W) -> Result<Response> {
+        let body = self.reply_body(reply)?;
+        parse::response_base64_into_with(body.as_slice(), sink, self.parse_options)
+            .map_err(Into::into)
+    }
+
+    /// Checks the status of the reply to a call, and returns its decompressed body.
+    fn reply_body(&self, reply: Reply) -> Result<Vec<u8>> {
+        if !(200..300).contains(&reply.status()) {
+            bail!(ErrorKind::HttpStatus(reply.status()));
+        }
+        let encoding = reply
+            .header_values("Content-Encoding")
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.decompress && !encoding.is_empty() {
+            compression::decode(&encoding, reply.into_body())
+        } else {
+            Ok(reply.into_body())
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::timer;
+use super::{AsyncTransport, BoxFuture, Reply, Request, Transport};
+use crate::error::{Error, ErrorKind, Result, ResultExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Instant;
+
+/// Carries the calls of an `AsyncClient` over a `reqwest::Client`.
+///
+/// This shares the connection pool, TLS and proxy settings of the reqwest client with the rest
+/// of the application.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+
+    async fn exchange(&self, call: &Request) -> Result<Reply> {
+        let request = self
+            .client
+            .post(call.url().as_str())
+            .headers(headers(call)?)
+            .body(call.body().to_vec());
+        let response = timer::first_byte(call.first_byte_timeout(), async {
+            request.send().await.map_err(transport_error)
+        })
+        .await?;
+        let reply = reply(response.status().as_u16(), response.headers());
+        let body = response.bytes().await.map_err(transport_error)?;
+        Ok(reply(body.to_vec()))
+    }
+}
+
+impl AsyncTransport for ReqwestTransport {
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>> {
+        Box::pin(self.exchange(request))
+    }
+}
+
+/// Carries the calls of a `Client` over a `reqwest::blocking::Client`.
+///
+/// Like the blocking reqwest client itself, this must not be used from within an async runtime.
+#[derive(Clone, Debug)]
+pub struct BlockingReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+impl BlockingReqwestTransport {
+    pub fn new(client: reqwest::blocking::Client) -> BlockingReqwestTransport {
+        BlockingReqwestTransport { client }
+    }
+}
+
+impl Transport for BlockingReqwestTransport {
+    fn send(&self, call: &Request) -> Result<Reply> {
+        let mut request = self
+            .client
+            .post(call.url().as_str())
+            .headers(headers(call)?)
+            .body(call.body().to_vec());
+        if let Some(deadline) = call.deadline() {
+            let now = Instant::now();
+            if now >= deadline {
+                bail!(ErrorKind::TimedOut("Call deadline exceeded".into()));
+            }
+            request = request.timeout(deadline - now);
+        }
+        let response = request.send().map_err(transport_error)?;
+        let reply = reply(response.status().as_u16(), response.headers());
+        let body = response.bytes().map_err(transport_error)?;
+        Ok(reply(body.to_vec()))
+    }
+}
+
+fn headers(call: &Request) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in call.headers() {
+        map.insert(
+            HeaderName::from_bytes(name.as_bytes())
+                .chain_err(|| format!("Invalid HTTP header: {}", name))?,
+            HeaderValue::from_str(value).chain_err(|| format!("Invalid HTTP header: {}", name))?,
+        );
+    }
+    Ok(map)
+}
+
+/// Captures the status and headers, which reqwest only lends out until the body is read.
+fn reply(status: u16, headers: &HeaderMap) -> impl FnOnce(Vec<u8>) -> Reply {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_owned(), value)
+        })
+        .collect::<Vec<_>>();
+    move |body| {
+        headers
+            .into_iter()
+            .fold(Reply::new(status, body), |reply, (name, value)| {
+                reply.with_header(name, value)
+            })
+    }
+}
+
+fn transport_error(err: reqwest::Error) -> Error {
+    if err.is_connect() {
+        let details = "Failed to connect to the server.".to_owned();
+        return Error::with_chain(err, ErrorKind::Connect(details));
+    }
+    if err.is_timeout() {
+        return ErrorKind::TimedOut(err.to_string()).into();
+    }
+    let details = "Failed to run the HTTP request within reqwest.".to_owned();
+    Error::with_chain(err, ErrorKind::Transport(details))
+}
@@ -0,0 +1,217 @@
+use crate::error::{Error, ErrorKind};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Decides whether and when failed calls are attempted again.
+///
+/// Only errors are retried; a fault returned by the server is a valid response. Since XML-RPC
+/// calls are not idempotent in general, a call that timed out or lost its connection may have
+/// been executed by the server already, so only enable retries for calls that can safely run
+/// twice, or name those with `idempotent`.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_if: Arc<dyn Fn(&Error) -> bool + Send + Sync>,
+    idempotent: Option<HashSet<String>>,
+}
+
+impl Default for RetryPolicy {
+    /// Makes a single attempt, which is the behaviour of clients without a retry policy.
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(1)
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("idempotent", &self.idempotent)
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// Makes up to `max_attempts` attempts in total, retrying errors found by `is_transient`.
+    ///
+    /// The first retry waits 100 milliseconds, doubling for every further retry up to 10 seconds,
+    /// with jitter applied.
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: true,
+            retry_if: Arc::new(is_transient),
+            idempotent: None,
+        }
+    }
+
+    /// Sets the wait before the first retry, and the longest wait between two attempts.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Sets the factor by which the wait grows with every retry.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Randomizes each wait between half and all of its length, so that clients failing at the
+    /// same time do not retry in lockstep. This is enabled by default.
+    pub fn jitter(mut self, enable: bool) -> Self {
+        self.jitter = enable;
+        self
+    }
+
+    /// Replaces the classification of which errors are worth retrying.
+    pub fn retry_if<F>(mut self, retry_if: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Arc::new(retry_if);
+        self
+    }
+
+    /// Only retries calls of the methods once the request may have reached the server, and calls
+    /// of other methods only for errors of kind `Connect`, which are raised before anything is
+    /// sent. Without this, all methods are retried alike.
+    ///
+    /// Calling this again adds further methods.
+    pub fn idempotent<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.idempotent
+            .get_or_insert_with(HashSet::new)
+            .extend(methods.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns the time to wait before the next attempt, if the failed one should be retried.
+    ///
+    /// `attempt` counts the attempts made so far, starting at 1.
+    pub(super) fn backoff_after(
+        &self,
+        attempt: u32,
+        method: &str,
+        err: &Error,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !(self.retry_if)(err) {
+            return None;
+        }
+        let unsent = matches!(*err.kind(), ErrorKind::Connect(_));
+        if !unsent
+            && self
+                .idempotent
+                .as_ref()
+                .is_some_and(|set| !set.contains(method))
+        {
+            return None;
+        }
+        let factor = self.multiplier.powi(attempt as i32 - 1);
+        let backoff = self.initial_backoff.as_secs_f64() * factor;
+        let backoff = Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()));
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            let fraction = 0.5 + (random % 1000) as f64 / 2000.0;
+            Some(backoff.mul_f64(fraction))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+/// The default retry classification: failures to connect, timeouts, transport failures and 5xx
+/// responses.
+pub fn is_transient(err: &Error) -> bool {
+    match *err.kind() {
+        ErrorKind::Connect(_) | ErrorKind::TimedOut(_) | ErrorKind::Transport(_) => true,
+        ErrorKind::HttpStatus(status) => (500..600).contains(&status),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed_out() -> Error {
+        ErrorKind::TimedOut("test".into()).into()
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(5).backoff(Duration::from_millis(100), Duration::from_millis(300))
+    }
+
+    #[test]
+    fn classifies_transient_errors() {
+        assert!(is_transient(&ErrorKind::Connect("test".into()).into()));
+        assert!(is_transient(&timed_out()));
+        assert!(is_transient(&ErrorKind::Transport("test".into()).into()));
+        assert!(is_transient(&ErrorKind::HttpStatus(503).into()));
+        assert!(!is_transient(&ErrorKind::HttpStatus(404).into()));
+        assert!(!is_transient(&"other".into()));
+    }
+
+    #[test]
+    fn grows_backoff_up_to_the_maximum() {
+        let policy = policy().jitter(false);
+        let waits = (1..5)
+            .map(|attempt| policy.backoff_after(attempt, "m", &timed_out()))
+            .collect::<Vec<_>>();
+        let millis = |ms| Some(Duration::from_millis(ms));
+        assert_eq!(
+            waits,
+            vec![millis(100), millis(200), millis(300), millis(300)]
+        );
+        assert_eq!(policy.backoff_after(5, "m", &timed_out()), None);
+    }
+
+    #[test]
+    fn keeps_jitter_within_half_the_backoff() {
+        let policy = policy();
+        for _ in 0..100 {
+            let wait = policy.backoff_after(2, "m", &timed_out()).unwrap();
+            assert!(wait >= Duration::from_millis(100));
+            assert!(wait <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn retries_only_idempotent_methods_once_sent() {
+        let policy = policy().idempotent(vec!["get"]);
+        assert!(policy.backoff_after(1, "get", &timed_out()).is_some());
+        assert!(policy.backoff_after(1, "set", &timed_out()).is_none());
+        let unsent = ErrorKind::Connect("test".into()).into();
+        assert!(policy.backoff_after(1, "set", &unsent).is_some());
+    }
+
+    #[test]
+    fn follows_custom_classification() {
+        let policy = policy().retry_if(|err| matches!(*err.kind(), ErrorKind::HttpStatus(_)));
+        assert!(policy.backoff_after(1, "m", &timed_out()).is_none());
+        let status = ErrorKind::HttpStatus(404).into();
+        assert!(policy.backoff_after(1, "m", &status).is_some());
+        assert!(RetryPolicy::default()
+            .backoff_after(1, "m", &timed_out())
+            .is_none());
+    }
+}
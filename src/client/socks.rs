@@ -0,0 +1,211 @@
+use super::dns::Resolver;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+/// Performs the client side of a SOCKS5 handshake (RFC 1928) asking the proxy for a connection
+/// to `host`.
+///
+/// Unless `remote_dns` is set, the host name is resolved locally and only its address is sent,
+/// which leaks the lookup to the local resolver.
+pub fn connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    remote_dns: bool,
+    resolver: &Resolver,
+    credentials: Option<&(String, String)>,
+) -> io::Result<()> {
+    let method = if credentials.is_some() { 0x02 } else { 0x00 };
+    stream.write_all(&[0x05, 0x01, method])?;
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    if reply[0] != 0x05 || reply[1] != method {
+        return Err(error(
+            "SOCKS5 proxy does not accept the authentication method",
+        ));
+    }
+
+    if let Some((username, password)) = credentials {
+        // Username/password authentication, RFC 1929.
+        if username.len() > 255 || password.len() > 255 {
+            return Err(error("SOCKS5 credentials are longer than 255 bytes"));
+        }
+        let mut request = vec![0x01, username.len() as u8];
+        request.extend_from_slice(username.as_bytes());
+        request.push(password.len() as u8);
+        request.extend_from_slice(password.as_bytes());
+        stream.write_all(&request)?;
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0x00 {
+            return Err(error("SOCKS5 proxy rejected the credentials"));
+        }
+    }
+
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let address = match host.parse::<IpAddr>() {
+        Ok(address) => Some(address),
+        Err(_) if remote_dns => None,
+        Err(_) => resolver.resolve(host, port)?.first().map(|addr| addr.ip()),
+    };
+    let mut request = vec![0x05, 0x01, 0x00];
+    match address {
+        Some(IpAddr::V4(address)) => {
+            request.push(0x01);
+            request.extend_from_slice(&address.octets());
+        }
+        Some(IpAddr::V6(address)) => {
+            request.push(0x04);
+            request.extend_from_slice(&address.octets());
+        }
+        None if host.len() > 255 => return Err(error("Host name is too long for SOCKS5")),
+        None => {
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0x00 {
+        return Err(error(match reply[1] {
+            0x02 => "SOCKS5 proxy refused the connection by its rules",
+            0x03 => "SOCKS5 proxy could not reach the network",
+            0x04 => "SOCKS5 proxy could not reach the host",
+            0x05 => "SOCKS5 proxy's connection was refused by the host",
+            0x06 => "SOCKS5 proxy's connection timed out",
+            _ => "SOCKS5 proxy failed to connect to the host",
+        }));
+    }
+    // Skip the address the proxy bound for the connection.
+    let length = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut length = [0];
+            stream.read_exact(&mut length)?;
+            usize::from(length[0])
+        }
+        _ => return Err(error("Invalid reply from SOCKS5 proxy")),
+    };
+    let mut bound = vec![0; length + 2];
+    stream.read_exact(&mut bound)
+}
+
+fn error(message: &str) -> io::Error {
+    io::Error::other(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// Runs a proxy for a single connection that, for each exchange, reads as many bytes as
+    /// given and answers with the reply, returning everything it read.
+    fn proxy(exchanges: Vec<(usize, Vec<u8>)>) -> (TcpStream, JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut read = Vec::new();
+            for (length, reply) in exchanges {
+                let mut request = vec![0; length];
+                if stream.read_exact(&mut request).is_err() {
+                    break;
+                }
+                read.extend_from_slice(&request);
+                stream.write_all(&reply).unwrap();
+            }
+            read
+        });
+        (TcpStream::connect(addr).unwrap(), handle)
+    }
+
+    fn credentials() -> (String, String) {
+        ("user".to_owned(), "secret".to_owned())
+    }
+
+    #[test]
+    fn authenticates_and_leaves_names_to_the_proxy() {
+        let (mut stream, proxy) = proxy(vec![
+            (3, vec![0x05, 0x02]),
+            (13, vec![0x01, 0x00]),
+            (18, b"\x05\x00\x00\x03\x04host\x00\x50".to_vec()),
+        ]);
+        let resolver = Resolver::default();
+        connect(
+            &mut stream,
+            "example.com",
+            80,
+            true,
+            &resolver,
+            Some(&credentials()),
+        )
+        .unwrap();
+        drop(stream);
+        assert_eq!(
+            proxy.join().unwrap(),
+            [
+                &b"\x05\x01\x02"[..],
+                b"\x01\x04user\x06secret",
+                b"\x05\x01\x00\x03\x0bexample.com\x00\x50",
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn sends_locally_resolved_addresses() {
+        let (mut stream, proxy) = proxy(vec![
+            (3, vec![0x05, 0x00]),
+            (10, vec![0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0]),
+        ]);
+        let mut overrides = HashMap::new();
+        overrides.insert("example.com".to_owned(), vec!["10.0.0.1".parse().unwrap()]);
+        let resolver = Resolver::new(overrides, None);
+        connect(&mut stream, "example.com", 8080, false, &resolver, None).unwrap();
+        drop(stream);
+        assert_eq!(
+            proxy.join().unwrap(),
+            [
+                &b"\x05\x01\x00"[..],
+                &[0x05, 0x01, 0x00, 0x01, 10, 0, 0, 1, 0x1f, 0x90],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn reports_refusals() {
+        let resolver = Resolver::default();
+        let fail = |exchanges, credentials: Option<&(String, String)>| {
+            let (mut stream, _) = proxy(exchanges);
+            connect(&mut stream, "[::1]", 80, false, &resolver, credentials)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            fail(vec![(3, vec![0x05, 0xff])], None),
+            "SOCKS5 proxy does not accept the authentication method"
+        );
+        assert_eq!(
+            fail(
+                vec![(3, vec![0x05, 0x02]), (13, vec![0x01, 0x01])],
+                Some(&credentials())
+            ),
+            "SOCKS5 proxy rejected the credentials"
+        );
+        assert_eq!(
+            fail(
+                vec![(3, vec![0x05, 0x00]), (22, vec![0x05, 0x05, 0x00, 0x01])],
+                None
+            ),
+            "SOCKS5 proxy's connection was refused by the host"
+        );
+    }
+}
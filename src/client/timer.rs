@@ -0,0 +1,90 @@
+use crate::error::{ErrorKind, Result};
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub async fn sleep(duration: Duration) {
+    async_io::Timer::after(duration).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    use std::convert::TryFrom;
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_name = setTimeout)]
+        fn set_timeout(handler: &js_sys::Function, millis: i32) -> JsValue;
+    }
+
+    let millis = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        set_timeout(&resolve, millis);
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Measures the time passed since it was started, with `Date.now()` on `wasm32`, where
+/// `Instant` is not available.
+pub struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start: f64,
+}
+
+impl Stopwatch {
+    pub fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            start: js_sys::Date::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.start).max(0.0) / 1000.0)
+    }
+}
+
+/// Runs the future to completion, unless the duration passes first.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut expired = pin!(sleep(duration));
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        expired.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+/// Runs the future receiving the head of a response, failing it once the first byte timeout of
+/// the request has passed.
+pub async fn first_byte<F, T>(limit: Option<Duration>, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match limit {
+        Some(limit) => timeout(limit, future).await.unwrap_or_else(|| {
+            Err(ErrorKind::TimedOut("No response within the first byte timeout".into()).into())
+        }),
+        None => future.await,
+    }
+}
@@ -0,0 +1,135 @@
+use super::Request;
+use crate::error::Result;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+
+/// Carries XML-RPC calls to the server for a `Client`.
+///
+/// The client prepares every request, applying default headers, cookies, compression and
+/// authentication, and interprets the reply, so a transport only has to exchange the bytes. An
+/// in-memory transport can serve as a test double, and other HTTP stacks can be plugged in
+/// through `ClientBuilder::transport`.
+pub trait Transport: Send + Sync {
+    /// Sends the request, giving up once its deadline, if any, has passed.
+    fn send(&self, request: &Request) -> Result<Reply>;
+}
+
+/// A boxed future, as returned by `AsyncTransport`.
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A boxed future, as returned by `AsyncTransport`.
+///
+/// JavaScript values cannot leave their thread, so the futures of `wasm32` targets are not
+/// `Send`.
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Non-blocking counterpart of `Transport`, carrying the calls of an `AsyncClient`.
+///
+/// The client enforces call deadlines by dropping the future.
+#[cfg(feature = "async")]
+pub trait AsyncTransport: Send + Sync {
+    fn send<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Reply>>;
+}
+
+/// The HTTP response to a call, as returned by a transport.
+#[derive(Clone, Debug)]
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn new(status: u16, body: Vec<u8>) -> Reply {
+        Reply {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Adds a header, keeping earlier ones of the same name, as needed for `Set-Cookie`.
+    pub fn with_header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns all values of the header, compared case-insensitively.
+    pub fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientBuilder;
+    use crate::xmlfmt::Encoding;
+    use std::sync::{Arc, Mutex};
+
+    /// Answers every call with an empty response, keeping the `Content-Type` headers it got.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Transport for Recorder {
+        fn send(&self, request: &Request) -> Result<Reply> {
+            let types = request
+                .headers()
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+                .map(|(_, value)| value.clone());
+            self.0.lock().unwrap().extend(types);
+            let body = "<methodResponse><params></params></methodResponse>";
+            Ok(Reply::new(200, body.into()))
+        }
+    }
+
+    fn content_types(builder: ClientBuilder) -> Vec<String> {
+        let recorder = Recorder::default();
+        let client = builder.transport(recorder.clone()).build().unwrap();
+        let url = "http://localhost/".parse().unwrap();
+        client.call_value(&url, "ping", vec![]).unwrap().unwrap();
+        let types = recorder.0.lock().unwrap();
+        types.clone()
+    }
+
+    #[test]
+    fn gives_transports_the_content_type() {
+        assert_eq!(content_types(ClientBuilder::new()), ["text/xml"]);
+        assert_eq!(
+            content_types(ClientBuilder::new().request_encoding(Encoding::Latin1)),
+            ["text/xml; charset=ISO-8859-1"]
+        );
+        assert_eq!(
+            content_types(ClientBuilder::new().header("content-type", "application/xml")),
+            ["application/xml"]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use hyper::net::{NetworkConnector, NetworkStream};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::os::unix::net;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Connects every call to a Unix domain socket, whatever the host of its URL.
+#[derive(Clone, Debug)]
+pub struct UnixConnector {
+    pub path: PathBuf,
+}
+
+impl NetworkConnector for UnixConnector {
+    type Stream = UnixStream;
+
+    fn connect(&self, _host: &str, _port: u16, scheme: &str) -> hyper::Result<UnixStream> {
+        if scheme != "http" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unix sockets only carry plain HTTP",
+            )
+            .into());
+        }
+        Ok(UnixStream(net::UnixStream::connect(&self.path)?))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnixStream(net::UnixStream);
+
+impl UnixStream {
+    /// Checks an idle connection for having been closed by the server, like `Stream::is_closed`.
+    ///
+    /// Unix sockets cannot be peeked at, but any byte read means the connection is discarded.
+    pub fn is_closed(&self) -> bool {
+        if self.0.set_nonblocking(true).is_err() {
+            return true;
+        }
+        let mut buffer = [0];
+        let closed = match (&self.0).read(&mut buffer) {
+            Err(ref err) => err.kind() != io::ErrorKind::WouldBlock,
+            Ok(_) => true,
+        };
+        self.0.set_nonblocking(false).is_err() || closed
+    }
+}
+
+impl Read for UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl NetworkStream for UnixStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "Unix sockets have no IP address",
+        ))
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+}
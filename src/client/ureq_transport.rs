@@ -0,0 +1,93 @@
+use super::{Reply, Request, Transport};
+use crate::error::{Error, ErrorKind, Result};
+use std::time::Instant;
+
+/// Carries the calls of a `Client` over a `ureq::Agent`, without pulling in hyper's connection
+/// handling or an async runtime.
+///
+/// ureq is built without its default features, so HTTPS and gzip support have to be enabled
+/// through its `rustls` and `gzip` features where needed.
+#[derive(Clone, Debug)]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+impl UreqTransport {
+    pub fn new(agent: ureq::Agent) -> UreqTransport {
+        UreqTransport { agent }
+    }
+}
+
+impl Default for UreqTransport {
+    fn default() -> UreqTransport {
+        UreqTransport::new(ureq::Agent::new_with_defaults())
+    }
+}
+
+impl Transport for UreqTransport {
+    fn send(&self, call: &Request) -> Result<Reply> {
+        let mut request = self.agent.post(call.url().as_str());
+        for (name, value) in call.headers() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let timeout = match call.deadline() {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    bail!(ErrorKind::TimedOut("Call deadline exceeded".into()));
+                }
+                Some(deadline - now)
+            }
+            None => None,
+        };
+        // Error statuses are left for the client to interpret, like those of other transports.
+        let response = request
+            .config()
+            .http_status_as_error(false)
+            .timeout_global(timeout)
+            .build()
+            .send(call.body())
+            .map_err(transport_error)?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_owned(), value)
+            })
+            .collect::<Vec<_>>();
+        let body = response
+            .into_body()
+            .with_config()
+            .limit(u64::MAX)
+            .read_to_vec()
+            .map_err(transport_error)?;
+        let reply = Reply::new(status, body);
+        Ok(headers
+            .into_iter()
+            .fold(reply, |reply, (name, value)| reply.with_header(name, value)))
+    }
+}
+
+fn transport_error(err: ureq::Error) -> Error {
+    use ureq::Timeout;
+    match err {
+        ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::Timeout(Timeout::Resolve)
+        | ureq::Error::Timeout(Timeout::Connect) => {
+            let details = "Failed to connect to the server.".to_owned();
+            return Error::with_chain(err, ErrorKind::Connect(details));
+        }
+        ureq::Error::Io(ref io) if io.kind() == std::io::ErrorKind::ConnectionRefused => {
+            let details = "Failed to connect to the server.".to_owned();
+            return Error::with_chain(err, ErrorKind::Connect(details));
+        }
+        ureq::Error::Timeout(_) => return ErrorKind::TimedOut(err.to_string()).into(),
+        _ => {}
+    }
+    let details = "Failed to run the HTTP request within ureq.".to_owned();
+    Error::with_chain(err, ErrorKind::Transport(details))
+}
@@ -1,5 +1,6 @@
 #![allow(unknown_lints, unused_doc_comments)]
 pub use super::xmlfmt::error::{Error as FmtError, ErrorKind as FmtErrorKind};
+use super::xmlfmt::Fault;
 
 error_chain! {
     links {
@@ -10,5 +11,37 @@ error_chain! {
             description("Failed to bind XML-RPC server to port")
             display("Failed to bind XML-RPC server to port: {}", details)
         }
+        TimedOut(details: String) {
+            description("XML-RPC call timed out")
+            display("XML-RPC call timed out: {}", details)
+        }
+        Cancelled {
+            description("XML-RPC call was cancelled")
+            display("XML-RPC call was cancelled")
+        }
+        Connect(details: String) {
+            description("Failed to connect to the server, without sending the request")
+            display("{}", details)
+        }
+        Transport(details: String) {
+            description("Failed to exchange the HTTP request with the server")
+            display("{}", details)
+        }
+        HttpStatus(status: u16) {
+            description("Server responded with an unsuccessful HTTP status")
+            display("Server responded with HTTP status {}", status)
+        }
+        // Boxed, as the members of a fault would make every error as large.
+        Fault(fault: Box<Fault>) {
+            description("Server responded with an XML-RPC fault")
+            display("{}", fault)
+        }
+    }
+}
+
+/// Lets `?` turn the fault of a call into an error, keeping its code and message.
+impl From<Fault> for Error {
+    fn from(fault: Fault) -> Error {
+        ErrorKind::Fault(Box::new(fault)).into()
     }
 }
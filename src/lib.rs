@@ -1,30 +1,103 @@
 #![recursion_limit = "1024"]
 
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+extern crate async_io;
 extern crate base64;
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+extern crate blocking;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate bytes;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 #[macro_use]
 extern crate error_chain;
+extern crate flate2;
 extern crate futures;
-#[macro_use]
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+extern crate futures_io;
+#[cfg(all(feature = "async-std", feature = "tls", not(target_arch = "wasm32")))]
+extern crate futures_rustls;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate http_body_util;
 extern crate hyper;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate hyper1;
+#[cfg(all(feature = "async", feature = "tls", not(target_arch = "wasm32")))]
+extern crate hyper_rustls;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate hyper_timeout;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate hyper_util;
+extern crate indexmap;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+extern crate js_sys;
 #[macro_use]
 extern crate lazy_static;
-extern crate regex;
+#[cfg(not(target_arch = "wasm32"))]
+extern crate log;
 #[macro_use]
 extern crate serde;
-#[cfg(test)]
 extern crate serde_bytes;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "tls")]
+extern crate p12_keystore;
+#[cfg(all(feature = "transport-reqwest", not(target_arch = "wasm32")))]
+pub extern crate reqwest;
+#[cfg(not(target_arch = "wasm32"))]
 pub extern crate rouille;
-extern crate serde_xml_rs;
+#[cfg(feature = "tls")]
+extern crate rustls;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+extern crate smol_hyper;
+#[cfg(not(target_arch = "wasm32"))]
+extern crate socket2;
+#[cfg(not(target_arch = "wasm32"))]
+extern crate tiny_http;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate tokio;
+#[cfg(all(feature = "async", feature = "tls", not(target_arch = "wasm32")))]
+extern crate tokio_rustls;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+extern crate tower_service;
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+extern crate tracing;
+#[cfg(all(feature = "transport-ureq", not(target_arch = "wasm32")))]
+pub extern crate ureq;
+extern crate url;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+extern crate wasm_bindgen;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+extern crate wasm_bindgen_futures;
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+extern crate web_sys;
+#[cfg(feature = "tls")]
+extern crate webpki_roots;
 extern crate xml;
 
 pub mod client;
 pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod server;
 mod xmlfmt;
 
+#[cfg(feature = "async")]
+pub use client::AsyncClient;
 pub use client::{call, call_value, Client};
 pub use hyper::Url;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub use server::AsyncServer;
+#[cfg(not(target_arch = "wasm32"))]
 pub use server::Server;
-pub use xmlfmt::{from_params, into_params, Call, Fault, Params, Response, Value};
+pub use xmlfmt::value;
+#[cfg(feature = "chrono")]
+pub use xmlfmt::DateTime;
+pub use xmlfmt::{
+    from_params, into_params, into_params_with, into_value, ArrayBuilder, Call, Encoding,
+    Extensions, Fault, Members, Params, ParseOptions, Patch, Response, StructBuilder, ToPretty,
+    Value,
+};
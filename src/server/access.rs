@@ -0,0 +1,31 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// What the access log records about a call, filled in while it is answered.
+#[derive(Debug, Default)]
+pub struct Access {
+    pub method: Option<String>,
+    pub fault: Option<i32>,
+    pub size: usize,
+}
+
+impl Access {
+    /// Logs the call at the info level, with the `xml_rpc::access` target, as `key=value` pairs.
+    pub fn log(&self, peer: SocketAddr, status: u16, elapsed: Duration) {
+        let outcome = match (status, self.fault) {
+            (200, Some(code)) => format!("fault({})", code),
+            (200, None) => "ok".to_owned(),
+            _ => "error".to_owned(),
+        };
+        log::info!(
+            target: "xml_rpc::access",
+            "peer={} method={} status={} outcome={} bytes={} duration_ms={:.3}",
+            peer,
+            self.method.as_deref().unwrap_or("-"),
+            status,
+            outcome,
+            self.size,
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+}
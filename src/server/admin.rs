@@ -0,0 +1,231 @@
+use super::auth::{constant_time_eq, credentials};
+use super::registry::Lister;
+use super::{MethodInfo, Registry, RequestContext};
+use crate::xmlfmt::{Fault, Members, Response, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+#[cfg(feature = "async")]
+use super::AsyncRegistry;
+#[cfg(feature = "async")]
+use std::future::ready;
+
+const STATUS: &str = "system.status";
+const SHUTDOWN: &str = "system.shutdown";
+
+type Authorize = Arc<dyn Fn(&RequestContext) -> bool + Send + Sync>;
+type Hook = Arc<dyn Fn() + Send + Sync>;
+
+/// Methods for the operators of a server, registered with `Server::serve_admin` or
+/// `AsyncServer::serve_admin`.
+///
+/// `system.status` returns a struct with the `version` of this crate, the `uptime` of the server
+/// in seconds, the names of its `methods`, and whether it is `shuttingDown`. `system.shutdown`
+/// runs the hook set with `on_shutdown`, once, and is only there if one is. Calls not
+/// authorized fail with fault 401.
+#[derive(Clone)]
+pub struct Admin {
+    authorize: Authorize,
+    shutdown: Option<Hook>,
+    started: Instant,
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl Admin {
+    /// Answers the calls the check returns `true` for, as by the address of the client.
+    pub fn new<F>(authorize: F) -> Admin
+    where
+        F: Fn(&RequestContext) -> bool + Send + Sync + 'static,
+    {
+        Admin {
+            authorize: Arc::new(authorize),
+            shutdown: None,
+            started: Instant::now(),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Answers the calls carrying the user and password as HTTP Basic credentials.
+    pub fn with_basic_auth<U: Into<String>, P: Into<String>>(user: U, password: P) -> Admin {
+        let (user, password) = (user.into(), password.into());
+        Admin::new(move |context| {
+            credentials(context.header("Authorization")).is_some_and(|(given, secret)| {
+                given == user && constant_time_eq(secret.as_bytes(), password.as_bytes())
+            })
+        })
+    }
+
+    /// Runs the hook for the first call to `system.shutdown`, before it is answered, so the hook
+    /// should tell whatever runs the server to stop rather than end the process itself.
+    pub fn on_shutdown<F: Fn() + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.shutdown = Some(Arc::new(hook));
+        self
+    }
+
+    fn methods(&self) -> Vec<(&'static str, MethodInfo)> {
+        let mut methods = vec![(
+            STATUS,
+            MethodInfo::new()
+                .help("Returns the version, uptime, methods and state of the server.")
+                .signature(["struct"]),
+        )];
+        if self.shutdown.is_some() {
+            methods.push((
+                SHUTDOWN,
+                MethodInfo::new()
+                    .help("Shuts the server down, returning whether this call did.")
+                    .signature(["boolean"]),
+            ));
+        }
+        methods
+    }
+
+    fn call(&self, method: &str, context: &RequestContext, names: &Lister) -> Response {
+        if !(self.authorize)(context) {
+            return Err(Fault::new(401, "Not authorized"));
+        }
+        if method == SHUTDOWN {
+            let first = !self.shutting_down.swap(true, Ordering::SeqCst);
+            if let (true, Some(hook)) = (first, self.shutdown.as_ref()) {
+                hook();
+            }
+            return Ok(vec![Value::Bool(first)]);
+        }
+        let mut names = names();
+        names.sort_unstable();
+        let uptime = self.started.elapsed().as_secs();
+        let mut status = Members::new();
+        status.insert(
+            "version".to_owned(),
+            Value::String(env!("CARGO_PKG_VERSION").into()),
+        );
+        status.insert(
+            "uptime".to_owned(),
+            Value::Int(uptime.min(i32::MAX as u64) as i32),
+        );
+        status.insert(
+            "methods".to_owned(),
+            Value::Array(names.into_iter().map(Value::String).collect()),
+        );
+        status.insert(
+            "shuttingDown".to_owned(),
+            Value::Bool(self.shutting_down.load(Ordering::SeqCst)),
+        );
+        Ok(vec![Value::Struct(status)])
+    }
+
+    pub(super) fn register(self, registry: &Registry) {
+        let names = registry.lister();
+        for (method, info) in self.methods() {
+            let (admin, names) = (self.clone(), names.clone());
+            registry.register_value_with_context(method, move |context, _| {
+                admin.call(method, context, &names)
+            });
+            registry.describe(method, info);
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(super) fn register_async(self, registry: &AsyncRegistry) {
+        let names = registry.lister();
+        for (method, info) in self.methods() {
+            let (admin, names) = (self.clone(), names.clone());
+            registry.register_value_with_context(method, move |context, _| {
+                ready(admin.call(method, &context, &names))
+            });
+            registry.describe(method, info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::Server;
+    use std::sync::atomic::AtomicUsize;
+
+    /// A server with the admin methods of `alice`, and the number of times shutdown hooks ran.
+    fn server() -> (Server, Arc<AtomicUsize>) {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = runs.clone();
+        let admin = Admin::with_basic_auth("alice", "secret").on_shutdown(move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+        });
+        let mut server = Server::new();
+        server.register_value("ping", |_| Ok(vec![]));
+        server.serve_admin(admin);
+        (server, runs)
+    }
+
+    fn basic(credentials: &str) -> String {
+        format!("Basic {}", base64::encode(credentials))
+    }
+
+    #[test]
+    fn refuses_calls_not_authorized() {
+        let (server, runs) = server();
+        for authorization in [None, Some(basic("alice:wrong")), Some(basic("bob:secret"))] {
+            let mut client = server.test_client();
+            if let Some(authorization) = authorization {
+                client = client.with_header("Authorization", authorization);
+            }
+            for method in [SHUTDOWN, STATUS] {
+                let fault = client.call_value(method, vec![]).unwrap().unwrap_err();
+                assert_eq!(fault.code, 401);
+            }
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn runs_the_shutdown_hook_once() {
+        let (server, runs) = server();
+        let client = server
+            .test_client()
+            .with_header("Authorization", basic("alice:secret"));
+        let first = client.call_value(SHUTDOWN, vec![]).unwrap();
+        assert_eq!(first, Ok(vec![Value::Bool(true)]));
+        let second = client.call_value(SHUTDOWN, vec![]).unwrap();
+        assert_eq!(second, Ok(vec![Value::Bool(false)]));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn reports_the_status() {
+        let (server, _) = server();
+        let client = server
+            .test_client()
+            .with_header("Authorization", basic("alice:secret"));
+        let status = match client.call_value(STATUS, vec![]).unwrap() {
+            Ok(mut values) => match values.pop() {
+                Some(Value::Struct(status)) => status,
+                other => panic!("{:?}", other),
+            },
+            Err(fault) => panic!("{:?}", fault),
+        };
+        assert_eq!(
+            status["version"],
+            Value::String(env!("CARGO_PKG_VERSION").into())
+        );
+        assert_eq!(status["shuttingDown"], Value::Bool(false));
+        let methods = match status["methods"] {
+            Value::Array(ref methods) => methods,
+            ref other => panic!("{:?}", other),
+        };
+        assert!(methods.contains(&Value::String("ping".into())));
+        assert!(methods.contains(&Value::String(SHUTDOWN.into())));
+    }
+
+    #[test]
+    fn leaves_shutdown_out_without_a_hook() {
+        let mut server = Server::new();
+        server.serve_admin(Admin::new(|_| true));
+        let fault = server
+            .test_client()
+            .call_value(SHUTDOWN, vec![])
+            .unwrap()
+            .unwrap_err();
+        assert_ne!(fault.code, 401);
+    }
+}
@@ -0,0 +1,971 @@
+use super::access::Access;
+use super::admin::Admin;
+use super::cors::Cors;
+use super::filter::AddressFilter;
+use super::health::Health;
+use super::introspection::MethodInfo;
+use super::metrics::Metrics;
+use super::middleware::{self, Middleware, Reply, Request};
+use super::multicall;
+use super::panics::{self, CatchUnwind};
+use super::pipeline::{pipeline_setters, Canned, Pipeline};
+use super::rejection::Rejections;
+use super::trace::Trace;
+use super::{on_decode_fail, on_encode_fail, on_missing_method, Overload};
+use super::{AsyncRegistry, AsyncService, AsyncTestClient, AsyncWithState, RequestContext};
+use crate::error::{ErrorKind, Result};
+use crate::xmlfmt::{error, parse, Call, Extensions, Fault, ParseOptions, Response, Value};
+use bytes::{Buf, Bytes};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper1::body::Body;
+use hyper1::header::{
+    HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, ORIGIN,
+    TRANSFER_ENCODING,
+};
+use hyper1::server::conn::http1;
+use hyper1::service::service_fn;
+use hyper1::{Request as HyperRequest, StatusCode, Version};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::{ready, Future};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+
+#[cfg(unix)]
+use super::unix;
+#[cfg(feature = "tls")]
+use super::TlsConfig;
+#[cfg(unix)]
+use super::UnixOptions;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
+
+pub(super) type Handler =
+    Arc<dyn Fn(RequestContext, Vec<Value>) -> BoxFuture<Response> + Send + Sync>;
+type Fallback = Box<dyn Fn(Call) -> BoxFuture<Response> + Send + Sync>;
+pub(super) type HttpResponse = hyper1::Response<Full<Bytes>>;
+
+/// A boxed future, as returned by `AsyncHandler::call`.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// A handler of an async server, answering calls with futures.
+///
+/// Closures returning futures are handlers as they are, while implementing the trait suits
+/// handlers holding resources of their own, such as a connection pool.
+pub trait AsyncHandler: Send + Sync {
+    fn call(&self, params: Vec<Value>) -> BoxFuture<Response>;
+}
+
+impl<T, F> AsyncHandler for T
+where
+    T: Fn(Vec<Value>) -> F + Send + Sync,
+    F: Future<Output = Response> + Send + 'static,
+{
+    fn call(&self, params: Vec<Value>) -> BoxFuture<Response> {
+        Box::pin(self(params))
+    }
+}
+
+/// XML-RPC server whose handlers return futures, running on tokio.
+///
+/// Every connection is served by a task of its own, so a handler waiting on a database or
+/// another service does not hold up the other calls. It must be bound and run within a tokio
+/// runtime.
+pub struct AsyncServer {
+    pub(super) methods: AsyncRegistry,
+    mounts: HashMap<String, AsyncServer>,
+    pub(super) pipeline: Pipeline,
+    concurrency: Option<(Semaphore, Overload)>,
+    read_timeout: Duration,
+    fallback: Fallback,
+}
+
+/// How long reading a request may take by default.
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl Default for AsyncServer {
+    fn default() -> Self {
+        AsyncServer {
+            methods: AsyncRegistry::default(),
+            mounts: HashMap::new(),
+            pipeline: Pipeline::default(),
+            concurrency: None,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            fallback: Box::new(|call: Call| Box::pin(ready(on_missing_method(call.params)))),
+        }
+    }
+}
+
+impl AsyncServer {
+    pub fn new() -> AsyncServer {
+        AsyncServer::default()
+    }
+
+    pub fn register_value<K, T, F>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.register_handler(name, handler);
+    }
+
+    /// Registers a handler given the context of the request along with the parameters, as
+    /// `Server::register_value_with_context` does.
+    pub fn register_value_with_context<K, T, F>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(RequestContext, Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.methods.register_value_with_context(name, handler);
+    }
+
+    /// Registers a handler implementing `AsyncHandler`, called with the parameters as values.
+    pub fn register_handler<K, H>(&mut self, name: K, handler: H)
+    where
+        K: Into<String>,
+        H: AsyncHandler + 'static,
+    {
+        self.methods.register_handler(name, handler);
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, F, Tef, Tdf>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        self.methods
+            .register(name, handler, encode_fail, decode_fail);
+    }
+
+    /// Registers a handler as `Server::register_simple` does, its future being awaited for the
+    /// result.
+    pub fn register_simple<K, Treq, Tres, Thandler, F>(&mut self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+    {
+        self.register(name, handler, on_encode_fail, on_decode_fail);
+    }
+
+    /// Registers a handler as `register_simple` does, given the context of the request along
+    /// with the parameters.
+    pub fn register_simple_with_context<K, Treq, Tres, Thandler, F>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(RequestContext, Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+    {
+        self.methods.register_simple_with_context(name, handler);
+    }
+
+    /// Sets the help text and signatures of a method, as `Server::describe` does.
+    pub fn describe<K: Into<String>>(&mut self, name: K, info: MethodInfo) {
+        self.methods.describe(name, info);
+    }
+
+    /// Returns the registry of the methods, as `Server::registry` does.
+    pub fn registry(&self) -> AsyncRegistry {
+        self.methods.clone()
+    }
+
+    /// Returns a registry for the namespace, as `Server::namespace` does.
+    pub fn namespace(&self, prefix: &str) -> AsyncRegistry {
+        self.methods.namespace(prefix)
+    }
+
+    /// Returns a client calling the methods in-process, as `Server::test_client` does.
+    pub fn test_client(&self) -> AsyncTestClient<'_> {
+        AsyncTestClient::new(self)
+    }
+
+    pipeline_setters!();
+
+    /// Answers calls to the URL path with another server's handlers, as `Server::mount` does.
+    pub fn mount<K: Into<String>>(&mut self, path: K, mut server: AsyncServer) {
+        for (path, mut nested) in std::mem::take(&mut server.mounts) {
+            nested
+                .pipeline
+                .middleware
+                .splice(0..0, server.pipeline.middleware.iter().cloned());
+            self.mounts.insert(path, nested);
+        }
+        self.mounts.insert(path.into(), server);
+    }
+
+    /// Limits the calls run at once, which are unlimited by default, with `Overload::Queue`
+    /// holding further calls until earlier ones are done.
+    pub fn set_max_concurrent(&mut self, limit: usize, overload: Overload) {
+        let permits = limit.clamp(1, Semaphore::MAX_PERMITS);
+        self.concurrency = Some((Semaphore::new(permits), overload));
+    }
+
+    /// Returns a registrar handing the state to the handlers registered through it.
+    pub fn with_state<S: Send + Sync + 'static>(&mut self, state: Arc<S>) -> AsyncWithState<'_, S> {
+        AsyncWithState::new(self, state)
+    }
+
+    /// Encodes the results of typed handlers with the extensions, as
+    /// `Server::set_extensions` does.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.methods.set_extensions(extensions);
+    }
+
+    /// Registers the admin methods, as `Server::serve_admin` does.
+    pub fn serve_admin(&mut self, admin: Admin) {
+        admin.register_async(&self.methods);
+    }
+
+    /// Sends responses of at least `min_size` bytes to HTTP/1.1 clients with
+    /// `Transfer-Encoding: chunked`, as `Server::set_chunked_responses` does.
+    pub fn set_chunked_responses(&mut self, min_size: usize) {
+        self.pipeline.set_chunked_responses(min_size);
+    }
+
+    /// Limits how long reading the head of a request, each chunk of its body and the TLS
+    /// handshake may take, 30 seconds by default. Connections of clients sending slower are
+    /// closed, or answered with status 408 while reading the body.
+    pub fn set_read_timeout(&mut self, limit: Duration) {
+        self.read_timeout = limit;
+    }
+
+    /// Answers calls of the method running longer than the limit with the timeout fault,
+    /// dropping the future of the handler, which cancels it.
+    pub fn set_method_timeout<K: Into<String>>(&mut self, name: K, limit: Duration) {
+        self.pipeline.timeouts.insert(name.into(), limit);
+    }
+
+    pub fn set_on_missing<T, F>(&mut self, handler: T)
+    where
+        T: Fn(Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.fallback = Box::new(move |call: Call| Box::pin(handler(call.params)));
+    }
+
+    /// Answers calls of methods without a handler, given the whole call, as
+    /// `Server::set_fallback` does.
+    pub fn set_fallback<T, F>(&mut self, handler: T)
+    where
+        T: Fn(Call) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.fallback = Box::new(move |call| Box::pin(handler(call)));
+    }
+
+    /// Turns the server into a `tower::Service`, to be served by hyper or embedded in a tower
+    /// stack instead of being bound.
+    pub fn into_service(self) -> AsyncService {
+        AsyncService::new(self)
+    }
+
+    /// Binds the server to the address, as `Server::bind` does.
+    pub async fn bind(self, uri: &SocketAddr) -> Result<BoundAsyncServer> {
+        let listener = TcpListener::bind(uri)
+            .await
+            .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        Ok(BoundAsyncServer {
+            listeners: vec![listener],
+            local_addr,
+            server: Arc::new(self),
+            #[cfg(feature = "tls")]
+            tls: None,
+        })
+    }
+
+    /// Binds the server to as many sockets on the address as there are acceptors, all with
+    /// `SO_REUSEPORT` set, so that the kernel spreads new connections over them.
+    ///
+    /// Each socket is served by a task of its own, so that on a multi-threaded runtime
+    /// connections are accepted on several cores at once, as busy servers on Linux need. Other
+    /// processes with `SO_REUSEPORT` set may bind the port as well.
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub async fn bind_reuseport(
+        self,
+        uri: &SocketAddr,
+        acceptors: usize,
+    ) -> Result<BoundAsyncServer> {
+        if acceptors == 0 {
+            bail!("The number of acceptors must not be zero.");
+        }
+        let bind = |addr: &SocketAddr| {
+            reuseport_listener(addr).map_err(|err| ErrorKind::BindFail(err.to_string()))
+        };
+        let first = bind(uri)?;
+        // The others bind the port the first was given, should it have been picked.
+        let local_addr = first
+            .local_addr()
+            .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        let mut listeners = vec![first];
+        for _ in 1..acceptors {
+            listeners.push(bind(&local_addr)?);
+        }
+        Ok(BoundAsyncServer {
+            listeners,
+            local_addr,
+            server: Arc::new(self),
+            #[cfg(feature = "tls")]
+            tls: None,
+        })
+    }
+
+    /// Binds the server to serve HTTPS, with TLS terminated by the server itself.
+    #[cfg(feature = "tls")]
+    pub async fn bind_tls(self, uri: &SocketAddr, tls: TlsConfig) -> Result<BoundAsyncServer> {
+        Ok(self.bind(uri).await?.with_tls(tls))
+    }
+
+    /// Binds the server to a Unix socket, as `Server::bind_unix` does.
+    #[cfg(unix)]
+    pub async fn bind_unix<P: AsRef<Path>>(
+        self,
+        path: P,
+        options: UnixOptions,
+    ) -> Result<BoundAsyncUnixServer> {
+        let path = path.as_ref();
+        unix::clear(path)?;
+        let listener =
+            UnixListener::bind(path).map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        options.apply(path)?;
+        Ok(BoundAsyncUnixServer {
+            listener,
+            path: path.to_owned(),
+            server: Arc::new(self),
+        })
+    }
+
+    pub(super) async fn handle_outer<B>(
+        &self,
+        request: HyperRequest<B>,
+        remote_addr: SocketAddr,
+    ) -> HttpResponse
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let received = Instant::now();
+        let mut access = Access::default();
+        let trace = Trace::new(remote_addr, received);
+        let answer = self.answer(request, remote_addr, received, &mut access, &trace);
+        let response = trace.instrument(answer).await;
+        trace.answered(response.status().as_u16());
+        if self.pipeline.access_log {
+            access.log(remote_addr, response.status().as_u16(), received.elapsed());
+        }
+        response
+    }
+
+    /// Answers preflight requests, and else the request, adding CORS headers.
+    async fn answer<B>(
+        &self,
+        request: HyperRequest<B>,
+        remote_addr: SocketAddr,
+        received: Instant,
+        access: &mut Access,
+        trace: &Trace,
+    ) -> HttpResponse
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let origin = request
+            .headers()
+            .get(ORIGIN)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        let preflight = self.pipeline.preflight(
+            request.method().as_str(),
+            origin.as_deref(),
+            remote_addr.ip(),
+        );
+        if let Some(canned) = preflight {
+            return canned_response(canned);
+        }
+        let mut response = self
+            .respond(request, remote_addr, received, access, trace)
+            .await;
+        for (name, value) in self.pipeline.cors_headers(origin.as_deref()) {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response
+    }
+
+    async fn respond<B>(
+        &self,
+        request: HyperRequest<B>,
+        remote_addr: SocketAddr,
+        received: Instant,
+        access: &mut Access,
+        trace: &Trace,
+    ) -> HttpResponse
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let path = request.uri().path().to_owned();
+        let screened = self.pipeline.screen(
+            request.method().as_str(),
+            &path,
+            request
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+            remote_addr.ip(),
+            access,
+        );
+        if let Some(canned) = screened {
+            return canned_response(canned);
+        }
+        let _permit = match self.concurrency {
+            Some((ref permits, Overload::Queue)) => permits.acquire().await.ok(),
+            Some((ref permits, Overload::Reject)) => match permits.try_acquire() {
+                Ok(permit) => Some(permit),
+                Err(_) => return canned_response(Canned::empty(503)),
+            },
+            None => None,
+        };
+        let server = self.mounted(&path);
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_owned(), value)
+            })
+            .collect();
+        let accept_encoding = request
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        // Other versions either cannot chunk bodies or frame them on their own.
+        let chunked = request.version() == Version::HTTP_11;
+        let call = match self.read_call(request.into_body(), access).await {
+            Ok(call) => call,
+            Err(canned) => return canned_response(canned),
+        };
+        access.method = Some(call.name.clone());
+        trace.parsed(&call.name);
+
+        let chain = self.pipeline.chain(&server.pipeline);
+        let mut request = Request::new(path, headers, remote_addr, call, received);
+        let (passed, mut reply) = match middleware::before(&chain, &mut request) {
+            None => {
+                let name = request.call().name.clone();
+                let params = request.take_params();
+                let context = RequestContext::new(&request);
+                trace.dispatching(context.request_id());
+                let res = server.handle(Call { name, params }, &context).await;
+                trace.dispatched(&res);
+                (chain.len(), Reply::new(res))
+            }
+            Some(rejected) => rejected,
+        };
+        middleware::after(&chain[..passed], &request, &mut reply);
+
+        let encoded = self
+            .pipeline
+            .encode(reply, accept_encoding.as_deref(), access, trace);
+        let mut response = match encoded.body {
+            Some(body) => {
+                let mut response = HttpResponse::new(Full::new(Bytes::from(body)));
+                let headers = response.headers_mut();
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/xml"));
+                if encoded.gzipped {
+                    headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+                }
+                // Hyper sends the body chunked as told, rather than with its length.
+                if chunked && encoded.chunked {
+                    headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+                }
+                response
+            }
+            None => HttpResponse::new(Full::new(Bytes::new())),
+        };
+        // Invalid statuses or headers set by middleware are bugs, surfaced as server errors.
+        *response.status_mut() = match StatusCode::from_u16(encoded.status) {
+            Ok(status) => status,
+            Err(_) => return empty(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        for (name, value) in encoded.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                (Ok(name), Ok(value)) => response.headers_mut().insert(name, value),
+                _ => return empty(StatusCode::INTERNAL_SERVER_ERROR),
+            };
+        }
+        response
+    }
+
+    /// Parses the call on a blocking thread as the body arrives, handing it over a chunk at a
+    /// time, so that it is never held in memory as a whole. Each chunk has to arrive within the
+    /// read timeout, so that slow clients cannot keep the thread waiting for long.
+    async fn read_call<B>(&self, body: B, access: &mut Access) -> std::result::Result<Call, Canned>
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let (sender, receiver) = tokio::sync::mpsc::channel(BODY_CHUNKS);
+        let (options, max_value_size) = (self.pipeline.parse_options, self.pipeline.max_value_size);
+        let parser = tokio::task::spawn_blocking(move || {
+            parse::call_with(ChunkReader::new(receiver), options, max_value_size)
+        });
+        let mut body = std::pin::pin!(Limited::new(body, self.pipeline.max_request_size));
+        let mut failed = None;
+        loop {
+            let frame = match tokio::time::timeout(self.read_timeout, body.frame()).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(_) => {
+                    failed = Some(Canned::empty(408));
+                    break;
+                }
+            };
+            match frame {
+                Ok(frame) => {
+                    let data = match frame.into_data() {
+                        Ok(mut data) => data.copy_to_bytes(data.remaining()),
+                        Err(_) => continue,
+                    };
+                    // The parser is done, with the call or with an error, once it stops taking.
+                    if sender.send(data).await.is_err() {
+                        break;
+                    }
+                }
+                Err(err) if err.is::<LengthLimitError>() => {
+                    failed = Some(Canned::empty(413));
+                    break;
+                }
+                Err(_) => {
+                    failed = Some(self.pipeline.malformed(access));
+                    break;
+                }
+            }
+        }
+        drop(sender);
+        let call = parser.await;
+        if let Some(canned) = failed {
+            return Err(canned);
+        }
+        match call {
+            Ok(Ok(call)) => Ok(call),
+            Ok(Err(err)) => Err(self.pipeline.unparsed(&err, access)),
+            Err(_) => Err(Canned::empty(500)),
+        }
+    }
+
+    /// The server answering calls to the URL path, itself or one mounted on it.
+    pub(super) fn mounted(&self, path: &str) -> &AsyncServer {
+        self.mounts.get(path).unwrap_or(self)
+    }
+
+    pub(super) async fn handle(&self, req: Call, context: &RequestContext) -> Response {
+        let multicall = self.pipeline.is_multicall(&req, &self.methods.read());
+        if multicall {
+            // Each call is dispatched only once the one before has finished, as if made one by one.
+            let mut results = Vec::new();
+            for call in multicall::unpack(req.params)? {
+                results.push(match call {
+                    Ok(call) => self.dispatch(call, context).await,
+                    Err(fault) => Err(fault),
+                });
+            }
+            return multicall::pack(results);
+        }
+        self.dispatch(req, context).await
+    }
+
+    fn dispatch(&self, req: Call, context: &RequestContext) -> BoxFuture<Response> {
+        // The handler is called without holding the lock, as on the sync server.
+        let handler = match self.pipeline.lookup(&req, &self.methods.read()) {
+            Ok(handler) => handler,
+            Err(response) => return Box::pin(ready(response)),
+        };
+        let backtraces = self.pipeline.panic_backtraces;
+        let Call { name, params } = req;
+        let future = match handler {
+            Some(handler) => panics::catch(&name, backtraces, || handler(context.clone(), params)),
+            None => panics::catch(&name, backtraces, || {
+                let name = name.clone();
+                (self.fallback)(Call { name, params })
+            }),
+        };
+        let future = match future {
+            Ok(future) => future,
+            Err(fault) => return Box::pin(ready(Err(fault))),
+        };
+        let limit = self.pipeline.timeouts.get(&name).copied();
+        let future: BoxFuture<Response> = Box::pin(CatchUnwind::new(name, backtraces, future));
+        match limit {
+            Some(limit) => {
+                let fault = self.pipeline.timeout_fault.clone();
+                Box::pin(async move {
+                    tokio::time::timeout(limit, future)
+                        .await
+                        .unwrap_or(Err(fault))
+                })
+            }
+            None => future,
+        }
+    }
+}
+
+/// How many chunks of a body may wait for the parser, before reading more of it waits in turn.
+const BODY_CHUNKS: usize = 16;
+
+/// Reads the chunks of a body sent over from the connection, blocking until each arrives.
+struct ChunkReader {
+    receiver: tokio::sync::mpsc::Receiver<Bytes>,
+    chunk: Bytes,
+}
+
+impl ChunkReader {
+    fn new(receiver: tokio::sync::mpsc::Receiver<Bytes>) -> ChunkReader {
+        ChunkReader {
+            receiver,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl std::io::Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+pub struct BoundAsyncServer {
+    listeners: Vec<TcpListener>,
+    local_addr: SocketAddr,
+    server: Arc<AsyncServer>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsAcceptor>,
+}
+
+impl BoundAsyncServer {
+    /// The address the server is bound to, with the port actually picked when binding port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    #[cfg(feature = "tls")]
+    pub(super) fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls.acceptor());
+        self
+    }
+
+    /// Serves connections, each in a task of its own, until the future is dropped.
+    ///
+    /// Connections are kept alive for further calls, which clients may send before earlier ones
+    /// are answered, unless a client asks to close them.
+    pub async fn run(self) {
+        let mut listeners = self.listeners.into_iter();
+        let first = match listeners.next() {
+            Some(listener) => listener,
+            None => return,
+        };
+        let acceptor = Acceptor {
+            server: self.server,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+        };
+        // Sockets bound with `SO_REUSEPORT` beyond the first are served by tasks of their own,
+        // stopped along with this future.
+        let _others = Tasks(
+            listeners
+                .map(|listener| tokio::spawn(acceptor.clone().run(listener)))
+                .collect(),
+        );
+        acceptor.run(first).await;
+    }
+}
+
+/// Accepts connections on a socket of a `BoundAsyncServer`.
+#[derive(Clone)]
+struct Acceptor {
+    server: Arc<AsyncServer>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsAcceptor>,
+}
+
+impl Acceptor {
+    async fn run(self, listener: TcpListener) {
+        loop {
+            let (stream, remote_addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                // Failures to accept, such as running out of file descriptors, are usually
+                // temporary, so they are waited out rather than ending the server.
+                Err(_) => {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let server = self.server.clone();
+            #[cfg(feature = "tls")]
+            {
+                if let Some(ref tls) = self.tls {
+                    // The handshake happens in the task, so slow clients cannot hold up others.
+                    let handshake = tls.accept(stream);
+                    tokio::spawn(async move {
+                        let limit = server.read_timeout;
+                        if let Ok(Ok(stream)) = tokio::time::timeout(limit, handshake).await {
+                            serve(server, stream, remote_addr).await;
+                        }
+                    });
+                    continue;
+                }
+            }
+            tokio::spawn(serve(server, stream, remote_addr));
+        }
+    }
+}
+
+/// Tasks aborted once dropped.
+struct Tasks(Vec<tokio::task::JoinHandle<()>>);
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// An async server bound to a Unix socket, as `BoundAsyncServer` is to a TCP port.
+#[cfg(unix)]
+pub struct BoundAsyncUnixServer {
+    listener: UnixListener,
+    path: PathBuf,
+    server: Arc<AsyncServer>,
+}
+
+#[cfg(unix)]
+impl BoundAsyncUnixServer {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Serves connections, each in a task of its own, until the future is dropped.
+    pub async fn run(self) {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(_) => {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            tokio::spawn(serve(self.server.clone(), stream, unix::peer()));
+        }
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+fn reuseport_listener(addr: &SocketAddr) -> std::io::Result<TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(
+        Domain::for_address(*addr),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    // As for listeners bound by the standard library, the port is free again right away once
+    // the server stops.
+    socket.set_reuse_address(true)?;
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&(*addr).into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
+async fn serve<I>(server: Arc<AsyncServer>, io: I, remote_addr: SocketAddr)
+where
+    I: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let read_timeout = server.read_timeout;
+    let service = service_fn(move |request| {
+        let server = server.clone();
+        async move { Ok::<_, Infallible>(server.handle_outer(request, remote_addr).await) }
+    });
+    // Broken connections only concern the client on the other end. Responses to pipelined
+    // calls are flushed together, once every call read so far is answered.
+    let _ = http1::Builder::new()
+        .timer(TokioTimer::new())
+        .header_read_timeout(read_timeout)
+        .pipeline_flush(true)
+        .serve_connection(TokioIo::new(io), service)
+        .await;
+}
+
+fn empty(status: StatusCode) -> HttpResponse {
+    let mut response = HttpResponse::new(Full::new(Bytes::new()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Turns a response decided by the pipeline into one for hyper.
+fn canned_response(canned: Canned) -> HttpResponse {
+    let mut response = HttpResponse::new(Full::new(Bytes::from(canned.body)));
+    *response.status_mut() = StatusCode::from_u16(canned.status).unwrap_or(StatusCode::BAD_REQUEST);
+    if let Some(content_type) = canned.content_type {
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    for (name, value) in canned.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Members;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn dispatches_multicall_calls_one_at_a_time() {
+        let done = Arc::new(AtomicBool::new(false));
+        let mut server = AsyncServer::new();
+        let first = done.clone();
+        server.register_value("first", move |_| {
+            let first = first.clone();
+            async move {
+                first.store(true, Ordering::SeqCst);
+                Ok(vec![])
+            }
+        });
+        let second = done.clone();
+        server.register_value("second", move |_| {
+            let value = Value::Bool(second.load(Ordering::SeqCst));
+            async move { Ok(vec![value]) }
+        });
+        let call = |name: &str| {
+            let mut call = Members::new();
+            call.insert("methodName".into(), Value::String(name.into()));
+            call.insert("params".into(), Value::Array(vec![]));
+            Value::Struct(call)
+        };
+        let params = vec![Value::Array(vec![call("first"), call("second")])];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let response = runtime
+            .block_on(server.test_client().call_value("system.multicall", params))
+            .unwrap()
+            .unwrap();
+        let results = Value::Array(vec![
+            Value::Array(vec![]),
+            Value::Array(vec![Value::Bool(true)]),
+        ]);
+        assert_eq!(response, vec![results]);
+    }
+
+    #[test]
+    fn times_out_reading_requests() {
+        use std::io::{Read, Write};
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let mut server = AsyncServer::new();
+                server.set_read_timeout(Duration::from_millis(200));
+                let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).await.unwrap();
+                sender.send(bound.local_addr()).unwrap();
+                bound.run().await;
+            });
+        });
+        let addr = receiver.recv().unwrap();
+        let started = Instant::now();
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream.write_all(b"POST / HTTP/1.1\r\nHost: a\r\n").unwrap();
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response);
+        assert!(!response.starts_with(b"HTTP/1.1 200"));
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        let head = "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 100\r\n\r\n<?xml";
+        stream.write_all(head.as_bytes()).unwrap();
+        let mut response = [0; 12];
+        stream.read_exact(&mut response).unwrap();
+        assert_eq!(&response, b"HTTP/1.1 408");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The limit applies to each chunk, not to the body as a whole.
+        let body = "<?xml version=\"1.0\"?><methodCall><methodName>m</methodName></methodCall>";
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        let head = format!(
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Type: text/xml\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        for part in body.as_bytes().chunks(20) {
+            std::thread::sleep(Duration::from_millis(100));
+            stream.write_all(part).unwrap();
+        }
+        let mut response = [0; 12];
+        stream.read_exact(&mut response).unwrap();
+        assert_eq!(&response, b"HTTP/1.1 200");
+    }
+}
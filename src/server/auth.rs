@@ -0,0 +1,189 @@
+use super::{Middleware, Reply, Request};
+use std::collections::HashMap;
+
+type Check = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+/// Middleware letting through only calls with valid HTTP Basic credentials, rejecting others
+/// with status 401 and a challenge for the realm.
+pub struct BasicAuth {
+    realm: String,
+    check: Check,
+}
+
+impl BasicAuth {
+    /// Accepts the users along with their passwords.
+    pub fn new<I, K, V>(users: I) -> BasicAuth
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let users = users
+            .into_iter()
+            .map(|(user, password)| (user.into(), password.into()))
+            .collect::<HashMap<String, String>>();
+        BasicAuth::with_check(move |user, password| {
+            users
+                .get(user)
+                .is_some_and(|expected| constant_time_eq(expected.as_bytes(), password.as_bytes()))
+        })
+    }
+
+    /// Accepts the users and passwords the check returns `true` for, as when looking them up in
+    /// a database.
+    pub fn with_check<F>(check: F) -> BasicAuth
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        BasicAuth {
+            realm: "XML-RPC".into(),
+            check: Box::new(check),
+        }
+    }
+
+    /// Sets the realm named in the challenge, `XML-RPC` by default. Quotes and backslashes in it
+    /// are escaped, and control characters left out.
+    pub fn realm<T: Into<String>>(mut self, realm: T) -> Self {
+        self.realm = realm.into();
+        self
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        match credentials(request.header("Authorization")) {
+            Some((user, password)) => (self.check)(&user, &password),
+            None => false,
+        }
+    }
+}
+
+/// The user and password of an `Authorization` header with Basic credentials.
+pub(super) fn credentials(header: Option<&str>) -> Option<(String, String)> {
+    let credentials = match header?.split_once(' ') {
+        Some((scheme, credentials)) if scheme.eq_ignore_ascii_case("Basic") => credentials,
+        _ => return None,
+    };
+    let credentials = String::from_utf8(base64::decode(credentials.trim()).ok()?).ok()?;
+    let (user, password) = credentials.split_once(':')?;
+    Some((user.to_owned(), password.to_owned()))
+}
+
+impl Middleware for BasicAuth {
+    fn on_request(&self, request: &mut Request) -> Option<Reply> {
+        if self.authorized(request) {
+            return None;
+        }
+        let challenge = format!("Basic realm=\"{}\", charset=\"UTF-8\"", quote(&self.realm));
+        Some(Reply::reject(401).with_header("WWW-Authenticate", challenge))
+    }
+}
+
+/// Escapes the text for a quoted string of a header, leaving out control characters, which
+/// header values cannot hold.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len());
+    for c in text.chars().filter(|c| !c.is_control()) {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted
+}
+
+/// Compares the passwords without returning early, so the time taken does not tell how much of
+/// a guess was right.
+pub(super) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Call;
+    use std::time::Instant;
+
+    fn request(authorization: Option<&str>) -> Request {
+        let call = Call {
+            name: "ping".into(),
+            params: vec![],
+        };
+        let headers = authorization
+            .map(|value| ("Authorization".to_owned(), value.to_owned()))
+            .into_iter()
+            .collect();
+        let addr = "127.0.0.1:4000".parse().unwrap();
+        Request::new("/".into(), headers, addr, call, Instant::now())
+    }
+
+    fn basic(credentials: &str) -> String {
+        format!("Basic {}", base64::encode(credentials))
+    }
+
+    fn auth() -> BasicAuth {
+        BasicAuth::new(vec![("alice", "secret"), ("bob", "pa:ss")])
+    }
+
+    /// The challenge the middleware rejects the request with, if it does.
+    fn challenge(auth: &BasicAuth, authorization: Option<&str>) -> Option<String> {
+        let reply = auth.on_request(&mut request(authorization))?;
+        assert_eq!(reply.status(), 401);
+        assert!(reply.response().is_none());
+        let (name, value) = &reply.headers()[0];
+        assert_eq!(name, "WWW-Authenticate");
+        Some(value.clone())
+    }
+
+    #[test]
+    fn accepts_valid_credentials() {
+        let auth = auth();
+        assert_eq!(challenge(&auth, Some(&basic("alice:secret"))), None);
+        assert_eq!(challenge(&auth, Some(&basic("bob:pa:ss"))), None);
+        let lowercase = basic("alice:secret").replace("Basic", "basic");
+        assert_eq!(challenge(&auth, Some(&lowercase)), None);
+    }
+
+    #[test]
+    fn rejects_invalid_credentials() {
+        let auth = auth();
+        let expected = Some("Basic realm=\"XML-RPC\", charset=\"UTF-8\"".to_owned());
+        assert_eq!(challenge(&auth, None), expected);
+        for authorization in &[
+            basic("alice:wrong"),
+            basic("alice:secre"),
+            basic("carol:secret"),
+            basic("alicesecret"),
+            basic("alice:secret").replace("Basic", "Bearer"),
+            "Basic not base64!".to_owned(),
+            format!("Basic {}", base64::encode(b"alice:\xff")),
+            "Basic".to_owned(),
+        ] {
+            assert_eq!(challenge(&auth, Some(authorization)), expected);
+        }
+    }
+
+    #[test]
+    fn asks_custom_checks() {
+        let auth = BasicAuth::with_check(|user, password| user == password);
+        assert_eq!(challenge(&auth, Some(&basic("same:same"))), None);
+        assert!(challenge(&auth, Some(&basic("same:other"))).is_some());
+    }
+
+    #[test]
+    fn escapes_the_realm() {
+        let auth = auth().realm("Say \"hi\" \\ bye\r\n");
+        assert_eq!(
+            challenge(&auth, None),
+            Some("Basic realm=\"Say \\\"hi\\\" \\\\ bye\", charset=\"UTF-8\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn compares_in_full() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+    }
+}
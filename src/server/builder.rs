@@ -0,0 +1,326 @@
+use super::pipeline::Pipeline;
+use super::{
+    AddressFilter, BoundServer, Cors, Health, Middleware, Overload, Rejections, Server,
+    DEFAULT_MAX_REQUEST_SIZE,
+};
+use crate::error::Result;
+use crate::xmlfmt::{Extensions, Fault, ParseOptions};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(all(feature = "async", feature = "tls"))]
+use super::TlsConfig;
+#[cfg(feature = "async")]
+use super::{AsyncServer, BoundAsyncServer};
+
+/// Collects the configuration of a server and checks it for conflicts before binding the
+/// server, instead of setting it up piece by piece.
+///
+/// The methods come from the `Server` or `AsyncServer` handed to `build` or `build_async`,
+/// whose settings are replaced by those set on the builder.
+#[derive(Clone, Default)]
+pub struct ServerBuilder {
+    addr: Option<SocketAddr>,
+    max_request_size: Option<usize>,
+    max_value_size: Option<usize>,
+    parse_options: Option<ParseOptions>,
+    concurrency: Option<(usize, Overload)>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    address_filter: Option<AddressFilter>,
+    health: Option<Health>,
+    cors: Option<Cors>,
+    rejections: Option<Rejections>,
+    extensions: Option<Extensions>,
+    timeouts: HashMap<String, Duration>,
+    timeout_fault: Option<Fault>,
+    multicall: Option<bool>,
+    check_signatures: Option<bool>,
+    access_log: Option<bool>,
+    panic_backtraces: Option<bool>,
+    compress_threshold: Option<usize>,
+    chunked_threshold: Option<usize>,
+    acceptors: Option<usize>,
+    read_timeout: Option<Duration>,
+    #[cfg(all(feature = "async", feature = "tls"))]
+    tls: Option<TlsConfig>,
+}
+
+impl ServerBuilder {
+    pub fn new() -> ServerBuilder {
+        ServerBuilder::default()
+    }
+
+    /// Sets the address to bind, which is required.
+    pub fn addr(mut self, addr: SocketAddr) -> Self {
+        self.addr = Some(addr);
+        self
+    }
+
+    pub fn max_request_size(mut self, limit: usize) -> Self {
+        self.max_request_size = Some(limit);
+        self
+    }
+
+    /// Sets the largest string or base64 value accepted, which may not exceed the largest request.
+    pub fn max_value_size(mut self, limit: usize) -> Self {
+        self.max_value_size = Some(limit);
+        self
+    }
+
+    pub fn parse_options(mut self, options: ParseOptions) -> Self {
+        self.parse_options = Some(options);
+        self
+    }
+
+    pub fn max_concurrent(mut self, limit: usize, overload: Overload) -> Self {
+        self.concurrency = Some((limit, overload));
+        self
+    }
+
+    /// Adds middleware, run after that the server already has.
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn address_filter(mut self, filter: AddressFilter) -> Self {
+        self.address_filter = Some(filter);
+        self
+    }
+
+    pub fn health(mut self, health: Health) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    pub fn rejections(mut self, rejections: Rejections) -> Self {
+        self.rejections = Some(rejections);
+        self
+    }
+
+    pub fn extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Sets the timeout of a method, which the server must have a handler for.
+    pub fn method_timeout<K: Into<String>>(mut self, name: K, limit: Duration) -> Self {
+        self.timeouts.insert(name.into(), limit);
+        self
+    }
+
+    pub fn timeout_fault(mut self, fault: Fault) -> Self {
+        self.timeout_fault = Some(fault);
+        self
+    }
+
+    pub fn multicall(mut self, enabled: bool) -> Self {
+        self.multicall = Some(enabled);
+        self
+    }
+
+    pub fn check_signatures(mut self, enabled: bool) -> Self {
+        self.check_signatures = Some(enabled);
+        self
+    }
+
+    pub fn access_log(mut self, enabled: bool) -> Self {
+        self.access_log = Some(enabled);
+        self
+    }
+
+    pub fn panic_backtraces(mut self, enabled: bool) -> Self {
+        self.panic_backtraces = Some(enabled);
+        self
+    }
+
+    /// Gzips responses of at least `min_size` bytes for clients accepting it.
+    pub fn compress_responses(mut self, min_size: usize) -> Self {
+        self.compress_threshold = Some(min_size);
+        self
+    }
+
+    /// Sends responses of at least `min_size` bytes chunked to HTTP/1.1 clients.
+    pub fn chunked_responses(mut self, min_size: usize) -> Self {
+        self.chunked_threshold = Some(min_size);
+        self
+    }
+
+    /// Accepts connections on as many sockets bound with `SO_REUSEPORT`, which only
+    /// `build_async` supports, as `AsyncServer::bind_reuseport` does.
+    #[cfg(all(
+        feature = "async",
+        unix,
+        not(any(target_os = "solaris", target_os = "illumos"))
+    ))]
+    pub fn acceptors(mut self, acceptors: usize) -> Self {
+        self.acceptors = Some(acceptors);
+        self
+    }
+
+    /// Limits how long reading the head of a request, each chunk of its body and the TLS
+    /// handshake may take, which only `build_async` supports.
+    #[cfg(feature = "async")]
+    pub fn read_timeout(mut self, limit: Duration) -> Self {
+        self.read_timeout = Some(limit);
+        self
+    }
+
+    /// Serves HTTPS, which only `build_async` supports.
+    #[cfg(all(feature = "async", feature = "tls"))]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    fn check<H>(&self, handlers: &HashMap<String, H>) -> Result<SocketAddr> {
+        let addr = match self.addr {
+            Some(addr) => addr,
+            None => bail!("No address to bind the server to."),
+        };
+        let max_request_size = self.max_request_size.unwrap_or(DEFAULT_MAX_REQUEST_SIZE);
+        if max_request_size == 0 {
+            bail!("The largest request size must not be zero.");
+        }
+        match self.max_value_size {
+            Some(size) if size > max_request_size => bail!(
+                "The largest value size of {} bytes exceeds the largest request size of {} bytes.",
+                size,
+                max_request_size
+            ),
+            _ => {}
+        }
+        if self.acceptors == Some(0) {
+            bail!("The number of acceptors must not be zero.");
+        }
+        if self.read_timeout == Some(Duration::from_secs(0)) {
+            bail!("The read timeout must not be zero.");
+        }
+        if let Some((0, _)) = self.concurrency {
+            bail!("The number of calls run at once must not be zero.");
+        }
+        for (name, limit) in &self.timeouts {
+            if !handlers.contains_key(name) {
+                bail!("Timeout set for {}, which has no handler.", name);
+            }
+            if *limit == Duration::from_secs(0) {
+                bail!("Timeout of {} must not be zero.", name);
+            }
+        }
+        Ok(addr)
+    }
+
+    /// Applies the settings both servers share to the pipeline of the server being built.
+    fn configure(&mut self, pipeline: &mut Pipeline) {
+        if let Some(limit) = self.max_request_size {
+            pipeline.max_request_size = limit;
+        }
+        if let Some(limit) = self.max_value_size {
+            pipeline.max_value_size = limit;
+        }
+        if let Some(options) = self.parse_options {
+            pipeline.parse_options = options;
+        }
+        pipeline.middleware.append(&mut self.middleware);
+        if let Some(filter) = self.address_filter.take() {
+            pipeline.set_address_filter(filter);
+        }
+        if let Some(health) = self.health.take() {
+            pipeline.serve_health(health);
+        }
+        if let Some(cors) = self.cors.take() {
+            pipeline.set_cors(cors);
+        }
+        if let Some(rejections) = self.rejections.take() {
+            pipeline.set_rejections(rejections);
+        }
+        pipeline.timeouts.extend(self.timeouts.drain());
+        if let Some(fault) = self.timeout_fault.take() {
+            pipeline.timeout_fault = fault;
+        }
+        if let Some(enabled) = self.multicall {
+            pipeline.multicall = enabled;
+        }
+        if let Some(enabled) = self.check_signatures {
+            pipeline.check_signatures = enabled;
+        }
+        if let Some(enabled) = self.access_log {
+            pipeline.access_log = enabled;
+        }
+        if let Some(enabled) = self.panic_backtraces {
+            pipeline.set_panic_backtraces(enabled);
+        }
+        if let Some(min_size) = self.compress_threshold {
+            pipeline.set_compress_responses(min_size);
+        }
+        if let Some(min_size) = self.chunked_threshold {
+            pipeline.set_chunked_responses(min_size);
+        }
+    }
+
+    /// Configures the server and binds it, once the configuration is checked.
+    pub fn build(
+        mut self,
+        mut server: Server,
+    ) -> Result<BoundServer<impl Fn(&rouille::Request) -> rouille::Response + Send + Sync + 'static>>
+    {
+        let addr = self.check(&server.methods.read().handlers)?;
+        #[cfg(all(feature = "async", feature = "tls"))]
+        {
+            if self.tls.is_some() {
+                bail!("TLS is only supported by the async server.");
+            }
+        }
+        if self.acceptors.is_some() {
+            bail!("Multiple acceptors are only supported by the async server.");
+        }
+        if self.read_timeout.is_some() {
+            bail!("Read timeouts are only supported by the async server.");
+        }
+        if let Some((limit, overload)) = self.concurrency {
+            server.set_max_concurrent(limit, overload);
+        }
+        if let Some(extensions) = self.extensions.take() {
+            server.set_extensions(extensions);
+        }
+        self.configure(&mut server.pipeline);
+        server.bind(&addr)
+    }
+
+    /// Configures the async server and binds it, as `build` does.
+    #[cfg(feature = "async")]
+    pub async fn build_async(mut self, mut server: AsyncServer) -> Result<BoundAsyncServer> {
+        let addr = self.check(&server.methods.read().handlers)?;
+        if let Some((limit, overload)) = self.concurrency {
+            server.set_max_concurrent(limit, overload);
+        }
+        if let Some(limit) = self.read_timeout {
+            server.set_read_timeout(limit);
+        }
+        if let Some(extensions) = self.extensions.take() {
+            server.set_extensions(extensions);
+        }
+        self.configure(&mut server.pipeline);
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        let bound = match self.acceptors {
+            Some(acceptors) => server.bind_reuseport(&addr, acceptors).await?,
+            None => server.bind(&addr).await?,
+        };
+        #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+        let bound = server.bind(&addr).await?;
+        #[cfg(feature = "tls")]
+        {
+            if let Some(tls) = self.tls {
+                return Ok(bound.with_tls(tls));
+            }
+        }
+        Ok(bound)
+    }
+}
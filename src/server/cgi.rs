@@ -0,0 +1,176 @@
+use super::Server;
+use crate::error::{Result, ResultExt};
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// Answers the request a web server hands to a CGI script, read from the environment and
+/// standard input, by writing the response to standard output.
+pub fn handle(server: &Server) -> Result<()> {
+    let stdout = io::stdout();
+    respond(
+        server,
+        &env::vars().collect::<Vec<_>>(),
+        io::stdin(),
+        stdout.lock(),
+    )
+}
+
+/// Answers the request given by the CGI variables and the input, writing the response to the
+/// output.
+fn respond<R, W>(server: &Server, vars: &[(String, String)], input: R, mut output: W) -> Result<()>
+where
+    R: Read,
+    W: Write,
+{
+    let var = |name: &str| {
+        vars.iter()
+            .find(|(key, value)| key == name && !value.is_empty())
+            .map(|(_, value)| value.clone())
+    };
+
+    let method = var("REQUEST_METHOD").unwrap_or_else(|| "POST".into());
+    let mut url = var("PATH_INFO").unwrap_or_else(|| "/".into());
+    if let Some(query) = var("QUERY_STRING") {
+        url = format!("{}?{}", url, query);
+    }
+    let remote_addr = SocketAddr::new(
+        var("REMOTE_ADDR")
+            .and_then(|addr| addr.parse().ok())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        var("REMOTE_PORT")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(0),
+    );
+
+    // The web server passes HTTP headers as `HTTP_` variables, except for those of the body.
+    let mut headers = vars
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = name.strip_prefix("HTTP_")?;
+            let name = name
+                .split('_')
+                .map(|part| {
+                    let mut part = part.to_ascii_lowercase();
+                    if let Some(first) = part.get_mut(..1) {
+                        first.make_ascii_uppercase();
+                    }
+                    part
+                })
+                .collect::<Vec<_>>()
+                .join("-");
+            Some((name, value.clone()))
+        })
+        .collect::<Vec<_>>();
+    let length = var("CONTENT_LENGTH").and_then(|length| length.parse::<u64>().ok());
+    if let Some(length) = length {
+        headers.push(("Content-Length".into(), length.to_string()));
+    }
+    if let Some(content_type) = var("CONTENT_TYPE") {
+        headers.push(("Content-Type".into(), content_type));
+    }
+
+    // Web servers need not end standard input after the body, so no more than its length is
+    // read, and no more than one byte past the limit, which tells the server it is too large.
+    let limit = (server.pipeline.max_request_size as u64).saturating_add(1);
+    let mut body = Vec::new();
+    input
+        .take(length.unwrap_or(0).min(limit))
+        .read_to_end(&mut body)
+        .chain_err(|| "Failed to read the CGI request.")?;
+
+    let request = rouille::Request::fake_http_from(remote_addr, method, url, headers, body);
+    let response = server.handle_outer(&request);
+
+    let reason = tiny_http::StatusCode(response.status_code).default_reason_phrase();
+    let mut head = format!("Status: {} {}\n", response.status_code, reason);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\n", name, value));
+    }
+    head.push('\n');
+    let (mut data, _) = response.data.into_reader_and_size();
+    output
+        .write_all(head.as_bytes())
+        .and_then(|_| io::copy(&mut data, &mut output))
+        .and_then(|_| output.flush())
+        .chain_err(|| "Failed to write the CGI response.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{Rejection, Rejections};
+    use crate::xmlfmt::{parse, Value};
+
+    const CALL: &str = "<?xml version=\"1.0\"?><methodCall><methodName>whoami</methodName>\
+                        <params></params></methodCall>";
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    fn server() -> Server {
+        let mut server = Server::new();
+        server.register_value_with_context("whoami", |context, _| {
+            let header = context.header("X-Client-Name").unwrap_or("none").to_owned();
+            Ok(vec![
+                Value::String(context.remote_addr().to_string()),
+                Value::String(context.path().to_owned()),
+                Value::String(header),
+            ])
+        });
+        server
+    }
+
+    /// Answers the request, splitting the output into the CGI head and body.
+    fn answer(server: &Server, vars: &[(String, String)], input: &[u8]) -> (String, Vec<u8>) {
+        let mut output = Vec::new();
+        respond(server, vars, input, &mut output).unwrap();
+        let split = output.windows(2).position(|w| w == b"\n\n").unwrap();
+        let body = output.split_off(split + 2);
+        (String::from_utf8(output).unwrap(), body)
+    }
+
+    #[test]
+    fn answers_calls_from_the_environment_and_input() {
+        let length = CALL.len().to_string();
+        let vars = vars(&[
+            ("REQUEST_METHOD", "POST"),
+            ("PATH_INFO", "/RPC2"),
+            ("REMOTE_ADDR", "192.0.2.7"),
+            ("REMOTE_PORT", "4711"),
+            ("CONTENT_LENGTH", &length),
+            ("CONTENT_TYPE", "text/xml"),
+            ("HTTP_X_CLIENT_NAME", "tester"),
+        ]);
+        // Input past the length is none of the request's.
+        let input = format!("{}trailing bytes", CALL);
+        let (head, body) = answer(&server(), &vars, input.as_bytes());
+        assert!(head.starts_with("Status: 200 OK\n"), "{}", head);
+        assert!(head.contains("Content-Type: text/xml"), "{}", head);
+        let response = parse::response(&body[..]).unwrap();
+        assert_eq!(
+            response,
+            Ok(vec![
+                Value::String("192.0.2.7:4711".into()),
+                Value::String("/RPC2".into()),
+                Value::String("tester".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn answers_with_the_status_of_rejections() {
+        let mut server = server();
+        server.set_rejections(Rejections::new().wrong_method(Rejection::new(405)));
+        let (head, _) = answer(&server, &vars(&[("REQUEST_METHOD", "GET")]), b"");
+        assert!(head.starts_with("Status: 405 "), "{}", head);
+        // Without a length, no input is read, leaving the call empty.
+        let vars = vars(&[("CONTENT_TYPE", "text/xml")]);
+        let (head, _) = answer(&server, &vars, CALL.as_bytes());
+        assert!(head.starts_with("Status: 400 "), "{}", head);
+    }
+}
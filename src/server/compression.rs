@@ -0,0 +1,123 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Tells whether the `Accept-Encoding` header of a request lets the response be gzipped, as it
+/// does when it lists gzip, or `*`, with a quality above zero.
+pub fn accepts_gzip(header: Option<&str>) -> bool {
+    let header = match header {
+        Some(header) => header,
+        None => return false,
+    };
+    header.split(',').any(|coding| {
+        let mut parts = coding.split(';').map(str::trim);
+        let name = parts.next().unwrap_or("").to_ascii_lowercase();
+        let quality = parts
+            .filter_map(|param| {
+                param
+                    .strip_prefix("q=")
+                    .or_else(|| param.strip_prefix("Q="))
+            })
+            .filter_map(|quality| quality.parse::<f32>().ok())
+            .next()
+            .unwrap_or(1.0);
+        matches!(name.as_str(), "gzip" | "x-gzip" | "*") && quality > 0.0
+    })
+}
+
+/// Gzips the body, giving up on it, to be sent as it is, should that fail.
+pub fn gzip(body: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).and_then(|_| encoder.finish()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::Server;
+    use crate::xmlfmt::{parse, Value};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::net::{SocketAddr, TcpStream};
+
+    #[test]
+    fn reads_accept_encoding_headers() {
+        assert!(accepts_gzip(Some("gzip")));
+        assert!(accepts_gzip(Some("deflate, GZIP;q=0.5")));
+        assert!(accepts_gzip(Some("x-gzip")));
+        assert!(accepts_gzip(Some("*;q=0.1")));
+        assert!(!accepts_gzip(None));
+        assert!(!accepts_gzip(Some("deflate, br")));
+        assert!(!accepts_gzip(Some("gzip;q=0")));
+        assert!(!accepts_gzip(Some("gzip; Q=0.0, identity")));
+    }
+
+    #[test]
+    fn gzips_bodies_to_be_decoded_as_they_were() {
+        let body = "<value>".repeat(100);
+        let gzipped = gzip(body.as_bytes()).unwrap();
+        assert!(gzipped.len() < body.len());
+        let mut decoded = String::new();
+        GzDecoder::new(&gzipped[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    /// Calls `echo` with the text, returning the head and the decoded body of the response.
+    fn echo(addr: SocketAddr, text: &str, accept_encoding: Option<&str>) -> (String, String) {
+        let call = format!(
+            "<?xml version=\"1.0\"?><methodCall><methodName>echo</methodName><params>\
+             <param><value><string>{}</string></value></param></params></methodCall>",
+            text
+        );
+        let mut request = format!(
+            "POST / HTTP/1.0\r\nContent-Type: text/xml\r\nContent-Length: {}\r\n",
+            call.len()
+        );
+        if let Some(accept_encoding) = accept_encoding {
+            request.push_str(&format!("Accept-Encoding: {}\r\n", accept_encoding));
+        }
+        request.push_str("\r\n");
+        request.push_str(&call);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let body = response.split_off(split + 4);
+        let head = String::from_utf8(response).unwrap().to_ascii_lowercase();
+        let mut decoded = String::new();
+        if head.contains("content-encoding: gzip") {
+            GzDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .unwrap();
+        } else {
+            decoded = String::from_utf8(body).unwrap();
+        }
+        let response = parse::response(decoded.as_bytes()).unwrap();
+        assert_eq!(response, Ok(vec![Value::String(text.into())]));
+        (head, decoded)
+    }
+
+    #[test]
+    fn gzips_large_responses_for_clients_accepting_it() {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        server.set_compress_responses(500);
+        let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = bound.local_addr();
+        std::thread::spawn(move || bound.run());
+
+        let large = "x".repeat(1000);
+        let (head, _) = echo(addr, &large, Some("gzip"));
+        assert!(head.contains("content-encoding: gzip"), "{}", head);
+        let (head, _) = echo(addr, "small", Some("gzip"));
+        assert!(!head.contains("content-encoding"), "{}", head);
+        let (head, _) = echo(addr, &large, None);
+        assert!(!head.contains("content-encoding"), "{}", head);
+        let (head, _) = echo(addr, &large, Some("gzip;q=0, identity"));
+        assert!(!head.contains("content-encoding"), "{}", head);
+    }
+}
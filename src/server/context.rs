@@ -0,0 +1,81 @@
+use super::Request;
+use std::net::SocketAddr;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The header carrying the ID of a request, taken from the client or from middleware when set.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// What handlers registered with context know of the request carrying their call, for auditing
+/// clients or deciding on their access within the method.
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    remote_addr: SocketAddr,
+    path: String,
+    headers: Vec<(String, String)>,
+    request_id: String,
+}
+
+impl RequestContext {
+    /// Takes the context of the request as middleware left it.
+    pub(super) fn new(request: &Request) -> RequestContext {
+        RequestContext::from_parts(
+            request.remote_addr(),
+            request.path().to_owned(),
+            request.headers().to_vec(),
+        )
+    }
+
+    pub(super) fn from_parts(
+        remote_addr: SocketAddr,
+        path: String,
+        headers: Vec<(String, String)>,
+    ) -> RequestContext {
+        let request_id = headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+            .map_or_else(generate_id, |(_, id)| id.clone());
+        RequestContext {
+            remote_addr,
+            path,
+            headers,
+            request_id,
+        }
+    }
+
+    /// The address of the client, `0.0.0.0:0` when there is none, as over Unix sockets.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the first value of the header, looked up regardless of case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The ID of the request, from its `X-Request-Id` header, or else made up unique to the
+    /// process. The calls of a multicall share it.
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+}
+
+fn generate_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!(
+        "{:x}-{:x}",
+        process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+/// Lets browsers on other origins call the server, as WASM and JavaScript clients do, answering
+/// preflight `OPTIONS` requests and adding the CORS headers to responses.
+///
+/// No origin is allowed until some are, with `allow_origin` or `allow_any_origin`. Preflight
+/// requests from other origins are rejected with status 403, and the responses to their calls
+/// lack the headers, so that browsers keep them from the page.
+#[derive(Clone, Debug)]
+pub struct Cors {
+    origins: Vec<String>,
+    any_origin: bool,
+    headers: Vec<String>,
+    max_age: Option<Duration>,
+    credentials: bool,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            origins: Vec::new(),
+            any_origin: false,
+            headers: vec!["Content-Type".into()],
+            max_age: None,
+            credentials: false,
+        }
+    }
+}
+
+impl Cors {
+    pub fn new() -> Cors {
+        Cors::default()
+    }
+
+    /// Allows the origin, such as `https://app.example.com`.
+    pub fn allow_origin<T: Into<String>>(mut self, origin: T) -> Self {
+        self.origins.push(origin.into());
+        self
+    }
+
+    pub fn allow_any_origin(mut self) -> Self {
+        self.any_origin = true;
+        self
+    }
+
+    /// Allows the request header in calls, on top of `Content-Type`, as `Authorization` is
+    /// needed for HTTP authentication.
+    pub fn allow_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.headers.push(name.into());
+        self
+    }
+
+    /// Lets browsers cache the answers to preflight requests for as long.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Lets pages send cookies and HTTP authentication along with their calls, when enabled.
+    pub fn allow_credentials(mut self, enabled: bool) -> Self {
+        self.credentials = enabled;
+        self
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        self.any_origin || self.origins.iter().any(|allowed| allowed == origin)
+    }
+
+    /// The headers added to responses to calls from the origin, none if it is not allowed.
+    pub(super) fn headers(&self, origin: Option<&str>) -> Vec<(String, String)> {
+        let origin = match origin {
+            Some(origin) if self.allows(origin) => origin,
+            _ => return Vec::new(),
+        };
+        // Credentials are only sent to origins named, never to any.
+        let mut headers = if self.any_origin && !self.credentials {
+            vec![("Access-Control-Allow-Origin".into(), "*".into())]
+        } else {
+            vec![
+                ("Access-Control-Allow-Origin".into(), origin.into()),
+                ("Vary".into(), "Origin".into()),
+            ]
+        };
+        if self.credentials {
+            headers.push(("Access-Control-Allow-Credentials".into(), "true".into()));
+        }
+        headers
+    }
+
+    /// The headers of the answer to a preflight request from the origin, or `None` if it is not
+    /// allowed.
+    pub(super) fn preflight(&self, origin: Option<&str>) -> Option<Vec<(String, String)>> {
+        let mut headers = self.headers(origin);
+        if headers.is_empty() {
+            return None;
+        }
+        headers.push((
+            "Access-Control-Allow-Methods".into(),
+            "POST, OPTIONS".into(),
+        ));
+        headers.push((
+            "Access-Control-Allow-Headers".into(),
+            self.headers.join(", "),
+        ));
+        if let Some(max_age) = self.max_age {
+            headers.push((
+                "Access-Control-Max-Age".into(),
+                max_age.as_secs().to_string(),
+            ));
+        }
+        Some(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn adds_no_headers_for_other_origins() {
+        let cors = Cors::new()
+            .allow_origin("https://a.com")
+            .allow_credentials(true);
+        assert!(cors.headers(Some("https://b.com")).is_empty());
+        assert!(cors.headers(Some("https://a.com.b.com")).is_empty());
+        assert!(cors.headers(None).is_empty());
+        assert!(Cors::new().headers(Some("https://a.com")).is_empty());
+        assert!(cors.preflight(Some("https://b.com")).is_none());
+        assert!(cors.preflight(None).is_none());
+    }
+
+    #[test]
+    fn names_allowed_origins() {
+        let cors = Cors::new().allow_origin("https://a.com");
+        let headers = cors.headers(Some("https://a.com"));
+        assert_eq!(
+            header(&headers, "Access-Control-Allow-Origin"),
+            Some("https://a.com")
+        );
+        assert_eq!(header(&headers, "Vary"), Some("Origin"));
+        assert_eq!(header(&headers, "Access-Control-Allow-Credentials"), None);
+    }
+
+    #[test]
+    fn names_origins_when_sending_credentials() {
+        let any = Cors::new().allow_any_origin();
+        let headers = any.headers(Some("https://b.com"));
+        assert_eq!(header(&headers, "Access-Control-Allow-Origin"), Some("*"));
+        assert_eq!(header(&headers, "Vary"), None);
+
+        let headers = any.allow_credentials(true).headers(Some("https://b.com"));
+        assert_eq!(
+            header(&headers, "Access-Control-Allow-Origin"),
+            Some("https://b.com")
+        );
+        assert_eq!(
+            header(&headers, "Access-Control-Allow-Credentials"),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn answers_preflights_with_methods_and_headers() {
+        let cors = Cors::new()
+            .allow_origin("https://a.com")
+            .allow_header("Authorization")
+            .max_age(Duration::from_secs(600));
+        let headers = cors.preflight(Some("https://a.com")).unwrap();
+        assert_eq!(
+            header(&headers, "Access-Control-Allow-Methods"),
+            Some("POST, OPTIONS")
+        );
+        assert_eq!(
+            header(&headers, "Access-Control-Allow-Headers"),
+            Some("Content-Type, Authorization")
+        );
+        assert_eq!(header(&headers, "Access-Control-Max-Age"), Some("600"));
+    }
+}
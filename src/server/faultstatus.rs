@@ -0,0 +1,68 @@
+use super::{Middleware, Reply, Request};
+use std::ops::RangeInclusive;
+
+/// Middleware answering faults with HTTP statuses other than 200, by their code, for load
+/// balancers and proxies that go by the status alone.
+///
+/// The first rule matching the code of a fault sets the status, and faults matching none keep
+/// status 200 unless `otherwise` is set. Responses that are not faults are left alone. Added
+/// before `Metrics`, its statuses are the ones counted.
+#[derive(Clone, Debug, Default)]
+pub struct FaultStatus {
+    rules: Vec<(RangeInclusive<i32>, u16)>,
+    http_codes: bool,
+    otherwise: Option<u16>,
+}
+
+impl FaultStatus {
+    pub fn new() -> FaultStatus {
+        FaultStatus::default()
+    }
+
+    /// Answers faults with the code with the status, such as authentication faults with 401.
+    pub fn map(self, code: i32, status: u16) -> Self {
+        self.map_range(code..=code, status)
+    }
+
+    /// Answers faults with codes in the range with the status, as for a class of faults.
+    pub fn map_range(mut self, codes: RangeInclusive<i32>, status: u16) -> Self {
+        self.rules.push((codes, status));
+        self
+    }
+
+    /// Answers faults whose code is an HTTP error status, from 400 to 599, with that status, as
+    /// the faults the server raises itself are, such as 404 for missing methods.
+    pub fn http_codes(mut self) -> Self {
+        self.http_codes = true;
+        self
+    }
+
+    /// Answers faults matching no rule with the status.
+    pub fn otherwise(mut self, status: u16) -> Self {
+        self.otherwise = Some(status);
+        self
+    }
+
+    /// The status faults with the code are answered with, if other than 200.
+    pub fn status(&self, code: i32) -> Option<u16> {
+        if let Some(&(_, status)) = self.rules.iter().find(|(codes, _)| codes.contains(&code)) {
+            return Some(status);
+        }
+        match code {
+            400..=599 if self.http_codes => Some(code as u16),
+            _ => self.otherwise,
+        }
+    }
+}
+
+impl Middleware for FaultStatus {
+    fn on_response(&self, _request: &Request, reply: &mut Reply) {
+        let status = match reply.response() {
+            Some(Err(fault)) => self.status(fault.code),
+            _ => None,
+        };
+        if let Some(status) = status {
+            reply.set_status(status);
+        }
+    }
+}
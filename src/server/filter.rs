@@ -0,0 +1,167 @@
+use crate::error::{Error, Result};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A block of IP addresses in CIDR notation, as in `192.168.1.0/24` or `fd00::/8`, with a plain
+/// address standing for itself alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Cidr> {
+        let bits = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix > bits {
+            bail!("Prefix length {} is too long for {}.", prefix, addr);
+        }
+        Ok(Cidr { addr, prefix })
+    }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Cidr> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = match addr.parse::<IpAddr>() {
+            Ok(addr) => addr,
+            Err(_) => bail!("Invalid IP address: {}", addr),
+        };
+        let prefix = match prefix {
+            Some(prefix) => match prefix.parse() {
+                Ok(prefix) => prefix,
+                Err(_) => bail!("Invalid prefix length: {}", prefix),
+            },
+            None if addr.is_ipv4() => 32,
+            None => 128,
+        };
+        // IPv4-mapped blocks stand for the IPv4 blocks they map, as clients are matched by
+        // their canonical address.
+        match addr.to_canonical() {
+            IpAddr::V4(mapped) if addr.is_ipv6() && (96..=128).contains(&prefix) => {
+                Cidr::new(IpAddr::V4(mapped), prefix - 96)
+            }
+            _ => Cidr::new(addr, prefix),
+        }
+    }
+}
+
+/// Lets requests through by the IP address of the client, before anything else is answered,
+/// rejecting the others with status 403.
+///
+/// Denied blocks take precedence over allowed ones, and once any block is allowed, addresses
+/// outside all of them are denied. Calls without an address, as over Unix sockets, are let
+/// through.
+#[derive(Clone, Debug, Default)]
+pub struct AddressFilter {
+    allow: Vec<Cidr>,
+    deny: Vec<Cidr>,
+}
+
+impl AddressFilter {
+    pub fn new() -> AddressFilter {
+        AddressFilter::default()
+    }
+
+    pub fn allow(mut self, block: Cidr) -> Self {
+        self.allow.push(block);
+        self
+    }
+
+    pub fn deny(mut self, block: Cidr) -> Self {
+        self.deny.push(block);
+        self
+    }
+
+    pub fn permits(&self, addr: IpAddr) -> bool {
+        if addr.is_unspecified() {
+            return true;
+        }
+        if self.deny.iter().any(|block| block.contains(addr)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|block| block.contains(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let block = Cidr::new(addr("192.168.1.0"), 24).unwrap();
+        assert_eq!(cidr("192.168.1.0/24"), block);
+        assert_eq!(cidr("10.0.0.1"), Cidr::new(addr("10.0.0.1"), 32).unwrap());
+        assert_eq!(cidr("fd00::"), Cidr::new(addr("fd00::"), 128).unwrap());
+        assert_eq!(cidr("::ffff:10.0.0.0/104"), cidr("10.0.0.0/8"));
+        assert_eq!(cidr("::ffff:10.0.0.1"), cidr("10.0.0.1/32"));
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("fd00::/129".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/x".parse::<Cidr>().is_err());
+        assert!("10.0.0/8".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn contains_addresses() {
+        assert!(cidr("0.0.0.0/0").contains(addr("203.0.113.9")));
+        assert!(!cidr("0.0.0.0/0").contains(addr("fd00::1")));
+        assert!(cidr("::/0").contains(addr("fd00::1")));
+        assert!(cidr("10.0.0.1/32").contains(addr("10.0.0.1")));
+        assert!(!cidr("10.0.0.1/32").contains(addr("10.0.0.2")));
+        assert!(cidr("fd00::1/128").contains(addr("fd00::1")));
+        assert!(!cidr("fd00::1/128").contains(addr("fd00::2")));
+        assert!(cidr("::ffff:10.0.0.0/104").contains(addr("10.1.2.3")));
+        assert!(cidr("10.0.0.0/8").contains(addr("::ffff:10.1.2.3")));
+        assert!(!cidr("10.0.0.0/8").contains(addr("11.0.0.1")));
+    }
+
+    #[test]
+    fn denies_over_allowing() {
+        let filter = AddressFilter::new()
+            .allow(cidr("10.0.0.0/8"))
+            .deny(cidr("10.0.0.13"));
+        assert!(filter.permits(addr("10.0.0.12")));
+        assert!(!filter.permits(addr("10.0.0.13")));
+        assert!(!filter.permits(addr("192.168.0.1")));
+        assert!(filter.permits(addr("0.0.0.0")));
+        assert!(AddressFilter::new().permits(addr("192.168.0.1")));
+        let filter = AddressFilter::new().deny(cidr("192.168.0.0/16"));
+        assert!(!filter.permits(addr("192.168.0.1")));
+        assert!(filter.permits(addr("10.0.0.1")));
+    }
+}
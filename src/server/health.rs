@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Liveness and readiness probes for orchestrators, answered to GET requests without an RPC.
+///
+/// The liveness path, `/healthz` by default, is answered with status 200 whenever the server
+/// answers at all. The readiness path, `/readyz` by default, is answered with status 200 while
+/// ready and with 503 otherwise, as told with `set_ready`, say while warming up or draining
+/// before a shutdown. Clones share whether they are ready.
+///
+/// Probes are answered ahead of the address filter and the limit on concurrent calls, so that
+/// a busy server is not taken for a dead one.
+#[derive(Clone, Debug)]
+pub struct Health {
+    liveness: String,
+    readiness: String,
+    ready: Arc<AtomicBool>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health {
+            liveness: "/healthz".into(),
+            readiness: "/readyz".into(),
+            ready: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl Health {
+    pub fn new() -> Health {
+        Health::default()
+    }
+
+    pub fn liveness_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.liveness = path.into();
+        self
+    }
+
+    pub fn readiness_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.readiness = path.into();
+        self
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::SeqCst);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    /// Answers a probe of the path with its status and body, or gives `None` for other paths.
+    pub(super) fn probe(&self, path: &str) -> Option<(u16, &'static str)> {
+        if path == self.liveness {
+            Some((200, "ok\n"))
+        } else if path == self.readiness {
+            Some(if self.is_ready() {
+                (200, "ready\n")
+            } else {
+                (503, "not ready\n")
+            })
+        } else {
+            None
+        }
+    }
+}
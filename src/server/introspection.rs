@@ -0,0 +1,281 @@
+use super::multicall::MULTICALL;
+use crate::xmlfmt::{Fault, Response, Value};
+use std::collections::HashMap;
+
+const LIST_METHODS: &str = "system.listMethods";
+const METHOD_HELP: &str = "system.methodHelp";
+const METHOD_SIGNATURE: &str = "system.methodSignature";
+
+/// The help text and signatures of a method, returned to callers by `system.methodHelp` and
+/// `system.methodSignature`.
+#[derive(Clone, Debug, Default)]
+pub struct MethodInfo {
+    help: String,
+    signatures: Vec<Vec<String>>,
+}
+
+impl MethodInfo {
+    pub fn new() -> MethodInfo {
+        MethodInfo::default()
+    }
+
+    pub fn help<T: Into<String>>(mut self, help: T) -> Self {
+        self.help = help.into();
+        self
+    }
+
+    /// Adds a signature, made of the XML-RPC type of the result followed by the types of the
+    /// parameters, as in `["int", "int", "int"]` for adding two integers.
+    pub fn signature<I, T>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.signatures
+            .push(types.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl MethodInfo {
+    /// Checks the parameters against the signatures, failing with a fault telling the expected
+    /// ones when they match none. Methods without signatures take any parameters.
+    pub(super) fn check(&self, name: &str, params: &[Value]) -> Result<(), Fault> {
+        let matches = |signature: &Vec<String>| {
+            let types = signature.get(1..).unwrap_or(&[]);
+            types.len() == params.len()
+                && types
+                    .iter()
+                    .zip(params)
+                    .all(|(kind, param)| is_type(kind, param))
+        };
+        if self.signatures.is_empty() || self.signatures.iter().any(matches) {
+            return Ok(());
+        }
+        let expected = self
+            .signatures
+            .iter()
+            .map(|signature| format!("({})", signature.get(1..).unwrap_or(&[]).join(", ")))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        Err(Fault::new(
+            400,
+            format!("Invalid parameters for {}, expected {}", name, expected),
+        ))
+    }
+}
+
+/// Tells whether the value is of the XML-RPC type, with types unknown here taking any value.
+fn is_type(kind: &str, value: &Value) -> bool {
+    let actual = match *value {
+        Value::Int(_) => "int",
+        Value::Int64(_) => "i8",
+        Value::Bool(_) => "boolean",
+        Value::String(_) => "string",
+        Value::Double(_) => "double",
+        Value::DateTime(_) => "dateTime.iso8601",
+        Value::Base64(_) => "base64",
+        Value::Array(_) => "array",
+        Value::Struct(_) => "struct",
+        Value::Nil => "nil",
+    };
+    match kind {
+        "i4" => actual == "int",
+        "int" | "i8" | "boolean" | "string" | "double" | "dateTime.iso8601" | "base64"
+        | "array" | "struct" | "nil" => kind == actual,
+        _ => true,
+    }
+}
+
+/// Answers the introspection methods, unless the server has handlers of its own for them.
+///
+/// `methods` holds the names of the registered handlers, `info` the descriptions of methods, and
+/// `multicall` whether the server answers `system.multicall`.
+pub fn handle<H>(
+    name: &str,
+    params: &[Value],
+    methods: &HashMap<String, H>,
+    info: &HashMap<String, MethodInfo>,
+    multicall: bool,
+) -> Option<Response> {
+    if methods.contains_key(name) {
+        return None;
+    }
+    Some(match name {
+        LIST_METHODS => {
+            let mut names = methods
+                .keys()
+                .map(String::as_str)
+                .chain(vec![LIST_METHODS, METHOD_HELP, METHOD_SIGNATURE])
+                .chain(if multicall { Some(MULTICALL) } else { None })
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            names.dedup();
+            let names = names.into_iter().map(|name| Value::String(name.into()));
+            Ok(vec![Value::Array(names.collect())])
+        }
+        METHOD_HELP => describe(params, methods, info, multicall).map(|info| {
+            let help = info.map_or_else(String::new, |info| info.help);
+            vec![Value::String(help)]
+        }),
+        METHOD_SIGNATURE => describe(params, methods, info, multicall).map(|info| {
+            // By convention, methods without known signatures are described as "undef".
+            let signatures = match info {
+                Some(ref info) if !info.signatures.is_empty() => Value::Array(
+                    info.signatures
+                        .iter()
+                        .map(|types| {
+                            Value::Array(types.iter().cloned().map(Value::String).collect())
+                        })
+                        .collect(),
+                ),
+                _ => Value::String("undef".into()),
+            };
+            vec![signatures]
+        }),
+        _ => return None,
+    })
+}
+
+/// Looks up the description of the method named by the parameters.
+fn describe<H>(
+    params: &[Value],
+    methods: &HashMap<String, H>,
+    info: &HashMap<String, MethodInfo>,
+    multicall: bool,
+) -> Result<Option<MethodInfo>, Fault> {
+    let name = match params {
+        [Value::String(name)] => name,
+        _ => return Err(Fault::new(400, "Expected the name of a method")),
+    };
+    if let Some(info) = info.get(name) {
+        return Ok(Some(info.clone()));
+    }
+    if methods.contains_key(name) {
+        return Ok(None);
+    }
+    let builtin = match name.as_str() {
+        LIST_METHODS => MethodInfo::new()
+            .help("Lists the methods of the server.")
+            .signature(vec!["array"]),
+        METHOD_HELP => MethodInfo::new()
+            .help("Returns the help text of the named method.")
+            .signature(vec!["string", "string"]),
+        METHOD_SIGNATURE => MethodInfo::new()
+            .help("Returns the signatures of the named method, or \"undef\" if unknown.")
+            .signature(vec!["array", "string"]),
+        MULTICALL if multicall => MethodInfo::new()
+            .help("Runs the calls in the array of methodName and params structs, in order.")
+            .signature(vec!["array", "array"]),
+        _ => return Err(Fault::new(404, "Requested method does not exist")),
+    };
+    Ok(Some(builtin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn methods() -> HashMap<String, ()> {
+        vec![("add".to_owned(), ()), ("ping".to_owned(), ())]
+            .into_iter()
+            .collect()
+    }
+
+    fn info() -> HashMap<String, MethodInfo> {
+        let add = MethodInfo::new()
+            .help("Adds two integers.")
+            .signature(vec!["int", "int", "int"])
+            .signature(vec!["double", "double", "double"]);
+        vec![("add".to_owned(), add)].into_iter().collect()
+    }
+
+    fn call(name: &str, params: &[Value], multicall: bool) -> Option<Response> {
+        handle(name, params, &methods(), &info(), multicall)
+    }
+
+    fn strings(names: &[&str]) -> Value {
+        Value::Array(
+            names
+                .iter()
+                .map(|name| Value::String(name.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn lists_methods_in_order() {
+        let listed = call(LIST_METHODS, &[], false).unwrap().unwrap();
+        let names = [
+            "add",
+            "ping",
+            "system.listMethods",
+            "system.methodHelp",
+            "system.methodSignature",
+        ];
+        assert_eq!(listed, vec![strings(&names)]);
+        let listed = call(LIST_METHODS, &[], true).unwrap().unwrap();
+        let names = [&names[..], &["system.multicall"]].concat();
+        assert_eq!(listed, vec![strings(&names)]);
+    }
+
+    #[test]
+    fn answers_help_texts() {
+        let help = |name: &str| call(METHOD_HELP, &[Value::String(name.into())], false);
+        assert_eq!(
+            help("add"),
+            Some(Ok(vec![Value::String("Adds two integers.".into())]))
+        );
+        assert_eq!(help("ping"), Some(Ok(vec![Value::String(String::new())])));
+        assert_eq!(help("missing").unwrap().unwrap_err().code, 404);
+        assert_eq!(help(MULTICALL).unwrap().unwrap_err().code, 404);
+        let fault = call(METHOD_HELP, &[Value::Int(1)], false)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(fault.code, 400);
+    }
+
+    #[test]
+    fn answers_signatures_or_undef() {
+        let signature = |name: &str| call(METHOD_SIGNATURE, &[Value::String(name.into())], true);
+        assert_eq!(
+            signature("add"),
+            Some(Ok(vec![Value::Array(vec![
+                strings(&["int", "int", "int"]),
+                strings(&["double", "double", "double"]),
+            ])]))
+        );
+        assert_eq!(
+            signature("ping"),
+            Some(Ok(vec![Value::String("undef".into())]))
+        );
+        assert_eq!(
+            signature(MULTICALL),
+            Some(Ok(vec![Value::Array(vec![strings(&["array", "array"])])]))
+        );
+    }
+
+    #[test]
+    fn leaves_registered_and_unknown_methods_alone() {
+        let mut methods = methods();
+        methods.insert(LIST_METHODS.to_owned(), ());
+        assert!(handle(LIST_METHODS, &[], &methods, &info(), false).is_none());
+        assert!(call("add", &[], false).is_none());
+        assert!(call("system.other", &[], false).is_none());
+    }
+
+    #[test]
+    fn checks_parameters_against_signatures() {
+        let add = &info()["add"];
+        assert!(add.check("add", &[Value::Int(1), Value::Int(2)]).is_ok());
+        assert!(add
+            .check("add", &[Value::Double(1.0), Value::Double(2.0)])
+            .is_ok());
+        let fault = add.check("add", &[Value::Int(1)]).unwrap_err();
+        assert_eq!(
+            fault.message,
+            "Invalid parameters for add, expected (int, int) or (double, double)"
+        );
+        assert!(MethodInfo::new().check("ping", &[Value::Int(1)]).is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+use super::{Middleware, Reply, Request};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Methods beyond this many are counted together as `other`, so that clients calling made-up
+/// names cannot grow the metrics without bound.
+const MAX_METHODS: usize = 1000;
+
+/// Middleware counting the calls to a server, for Prometheus to scrape once served with
+/// `Server::serve_metrics` or `AsyncServer::serve_metrics`.
+///
+/// It exports the calls and faults per method, the latency per method as a histogram, the
+/// responses per HTTP status and the calls in flight. Calls rejected before middleware runs,
+/// such as those failing to parse, are not seen.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    methods: BTreeMap<String, Method>,
+    statuses: BTreeMap<u16, u64>,
+    in_flight: i64,
+}
+
+#[derive(Default)]
+struct Method {
+    calls: u64,
+    faults: u64,
+    buckets: [u64; BUCKETS.len()],
+    seconds: f64,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Renders the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let state = self.lock();
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP xmlrpc_calls_total Calls received, by method.");
+        let _ = writeln!(out, "# TYPE xmlrpc_calls_total counter");
+        for (name, method) in &state.methods {
+            let _ = writeln!(
+                out,
+                "xmlrpc_calls_total{{method=\"{}\"}} {}",
+                label(name),
+                method.calls
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP xmlrpc_faults_total Calls answered with a fault, by method."
+        );
+        let _ = writeln!(out, "# TYPE xmlrpc_faults_total counter");
+        for (name, method) in &state.methods {
+            let _ = writeln!(
+                out,
+                "xmlrpc_faults_total{{method=\"{}\"}} {}",
+                label(name),
+                method.faults
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP xmlrpc_call_duration_seconds Time taken to answer calls, by method."
+        );
+        let _ = writeln!(out, "# TYPE xmlrpc_call_duration_seconds histogram");
+        for (name, method) in &state.methods {
+            let name = label(name);
+            for (bound, count) in BUCKETS.iter().zip(&method.buckets) {
+                let _ = writeln!(
+                    out,
+                    "xmlrpc_call_duration_seconds_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    name, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "xmlrpc_call_duration_seconds_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                name, method.calls
+            );
+            let _ = writeln!(
+                out,
+                "xmlrpc_call_duration_seconds_sum{{method=\"{}\"}} {}",
+                name, method.seconds
+            );
+            let _ = writeln!(
+                out,
+                "xmlrpc_call_duration_seconds_count{{method=\"{}\"}} {}",
+                name, method.calls
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP xmlrpc_responses_total Responses sent, by HTTP status."
+        );
+        let _ = writeln!(out, "# TYPE xmlrpc_responses_total counter");
+        for (status, count) in &state.statuses {
+            let _ = writeln!(
+                out,
+                "xmlrpc_responses_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+        let _ = writeln!(out, "# HELP xmlrpc_calls_in_flight Calls being answered.");
+        let _ = writeln!(out, "# TYPE xmlrpc_calls_in_flight gauge");
+        let _ = writeln!(out, "xmlrpc_calls_in_flight {}", state.in_flight);
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Middleware for Metrics {
+    fn on_request(&self, _request: &mut Request) -> Option<Reply> {
+        self.lock().in_flight += 1;
+        None
+    }
+
+    fn on_response(&self, request: &Request, reply: &mut Reply) {
+        let seconds = request.elapsed().as_secs_f64();
+        let faulted = matches!(reply.response(), Some(Err(_)));
+        let mut state = self.lock();
+        state.in_flight -= 1;
+        *state.statuses.entry(reply.status()).or_insert(0) += 1;
+        let name = &request.call().name;
+        let name = if state.methods.contains_key(name) || state.methods.len() < MAX_METHODS {
+            name.clone()
+        } else {
+            "other".to_owned()
+        };
+        let method = state.methods.entry(name).or_default();
+        method.calls += 1;
+        if faulted {
+            method.faults += 1;
+        }
+        for (bound, count) in BUCKETS.iter().zip(method.buckets.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        method.seconds += seconds;
+    }
+}
+
+/// Escapes a label value for the text format.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::{Call, Fault, Value};
+    use std::time::Instant;
+
+    fn request(method: &str) -> Request {
+        let call = Call {
+            name: method.into(),
+            params: vec![],
+        };
+        let addr = "127.0.0.1:4000".parse().unwrap();
+        Request::new("/".into(), vec![], addr, call, Instant::now())
+    }
+
+    fn answer(metrics: &Metrics, method: &str, mut reply: Reply) {
+        let mut request = request(method);
+        assert!(metrics.on_request(&mut request).is_none());
+        metrics.on_response(&request, &mut reply);
+    }
+
+    fn lines(metrics: &Metrics) -> Vec<String> {
+        metrics.render().lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn counts_calls_and_faults() {
+        let metrics = Metrics::new();
+        answer(&metrics, "add", Reply::new(Ok(vec![Value::Int(3)])));
+        answer(&metrics, "add", Reply::new(Err(Fault::new(4, "Too many"))));
+        answer(&metrics, "ping", Reply::reject(429));
+        let lines = lines(&metrics);
+        for expected in &[
+            "xmlrpc_calls_total{method=\"add\"} 2",
+            "xmlrpc_calls_total{method=\"ping\"} 1",
+            "xmlrpc_faults_total{method=\"add\"} 1",
+            "xmlrpc_faults_total{method=\"ping\"} 0",
+            "xmlrpc_call_duration_seconds_bucket{method=\"add\",le=\"10\"} 2",
+            "xmlrpc_call_duration_seconds_bucket{method=\"add\",le=\"+Inf\"} 2",
+            "xmlrpc_call_duration_seconds_count{method=\"add\"} 2",
+            "xmlrpc_responses_total{status=\"200\"} 2",
+            "xmlrpc_responses_total{status=\"429\"} 1",
+            "xmlrpc_calls_in_flight 0",
+        ] {
+            assert!(lines.iter().any(|line| line == expected), "{}", expected);
+        }
+    }
+
+    #[test]
+    fn counts_calls_in_flight() {
+        let metrics = Metrics::new();
+        let mut request = request("slow");
+        metrics.on_request(&mut request);
+        assert!(lines(&metrics).contains(&"xmlrpc_calls_in_flight 1".to_owned()));
+        metrics.on_response(&request, &mut Reply::new(Ok(vec![])));
+        assert!(lines(&metrics).contains(&"xmlrpc_calls_in_flight 0".to_owned()));
+    }
+
+    #[test]
+    fn bounds_and_escapes_method_names() {
+        let metrics = Metrics::new();
+        answer(&metrics, "say \"hi\"\n", Reply::new(Ok(vec![])));
+        for index in 1..MAX_METHODS + 5 {
+            answer(&metrics, &format!("m{}", index), Reply::new(Ok(vec![])));
+        }
+        let lines = lines(&metrics);
+        assert!(lines.contains(&"xmlrpc_calls_total{method=\"say \\\"hi\\\"\\n\"} 1".to_owned()));
+        assert!(lines.contains(&"xmlrpc_calls_total{method=\"other\"} 5".to_owned()));
+        let counted = lines
+            .iter()
+            .filter(|line| line.starts_with("xmlrpc_calls_total{"))
+            .count();
+        assert_eq!(counted, MAX_METHODS + 1);
+    }
+}
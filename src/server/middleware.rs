@@ -0,0 +1,177 @@
+use crate::xmlfmt::{Call, Params, Response};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Hooks into every call to a server, for authentication, logging, metrics or request IDs.
+///
+/// `on_request` runs in the order the middleware was added, once the call has been read, and
+/// rejects the call by returning a reply of its own. `on_response` runs in reverse order, for the
+/// middleware whose `on_request` let the call through, and may change the reply before it is
+/// sent. Middleware of a server runs after that of the server it is mounted on.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request) -> Option<Reply> {
+        None
+    }
+
+    fn on_response(&self, _request: &Request, _reply: &mut Reply) {}
+}
+
+/// A call to a server, along with the HTTP request carrying it.
+#[derive(Clone, Debug)]
+pub struct Request {
+    path: String,
+    headers: Vec<(String, String)>,
+    remote_addr: SocketAddr,
+    call: Call,
+    received: Instant,
+}
+
+impl Request {
+    pub(super) fn new(
+        path: String,
+        headers: Vec<(String, String)>,
+        remote_addr: SocketAddr,
+        call: Call,
+        received: Instant,
+    ) -> Request {
+        Request {
+            path,
+            headers,
+            remote_addr,
+            call,
+            received,
+        }
+    }
+
+    /// The URL path of the request, without the query.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the first value of the header, looked up regardless of case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces all values of the header, where later middleware can read it.
+    pub fn set_header<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        let name = name.into();
+        self.headers
+            .retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// The call, whose parameters `on_response` sees emptied, as they are passed on to the
+    /// handler.
+    pub fn call(&self) -> &Call {
+        &self.call
+    }
+
+    pub fn call_mut(&mut self) -> &mut Call {
+        &mut self.call
+    }
+
+    /// The time passed since the request was received.
+    pub fn elapsed(&self) -> Duration {
+        self.received.elapsed()
+    }
+
+    pub(super) fn take_params(&mut self) -> Params {
+        std::mem::take(&mut self.call.params)
+    }
+}
+
+/// The HTTP response to a call, carrying the XML-RPC response unless the call was rejected.
+#[derive(Clone, Debug)]
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    response: Option<Response>,
+}
+
+impl Reply {
+    /// A reply with status 200 and the XML-RPC response.
+    pub fn new(response: Response) -> Reply {
+        Reply {
+            status: 200,
+            headers: Vec::new(),
+            response: Some(response),
+        }
+    }
+
+    /// An empty reply with the HTTP status, such as 401 or 429, rejecting the call.
+    pub fn reject(status: u16) -> Reply {
+        Reply {
+            status,
+            headers: Vec::new(),
+            response: None,
+        }
+    }
+
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: u16) {
+        self.status = status;
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Replaces all values of the header.
+    pub fn set_header<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        let name = name.into();
+        self.headers
+            .retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+    }
+
+    pub fn response(&self) -> Option<&Response> {
+        self.response.as_ref()
+    }
+
+    pub fn response_mut(&mut self) -> Option<&mut Response> {
+        self.response.as_mut()
+    }
+
+    pub(super) fn into_parts(self) -> (u16, Vec<(String, String)>, Option<Response>) {
+        (self.status, self.headers, self.response)
+    }
+}
+
+/// Runs `on_request` along the chain, returning the rejecting reply, if any, along with the
+/// number of middleware that let the call through.
+pub fn before(chain: &[Arc<dyn Middleware>], request: &mut Request) -> Option<(usize, Reply)> {
+    for (index, middleware) in chain.iter().enumerate() {
+        if let Some(reply) = middleware.on_request(request) {
+            return Some((index, reply));
+        }
+    }
+    None
+}
+
+/// Runs `on_response` backwards along the chain.
+pub fn after(chain: &[Arc<dyn Middleware>], request: &Request, reply: &mut Reply) {
+    for middleware in chain.iter().rev() {
+        middleware.on_response(request, reply);
+    }
+}
@@ -0,0 +1,650 @@
+use rouille;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::{ErrorKind, Result};
+use super::xmlfmt::{error, parse, Call, Extensions, Fault, ParseOptions, Response, Value};
+
+mod access;
+mod admin;
+#[cfg(feature = "async")]
+mod async_server;
+mod auth;
+mod builder;
+mod cgi;
+mod compression;
+mod context;
+mod cors;
+mod faultstatus;
+mod filter;
+mod health;
+mod introspection;
+mod metrics;
+mod middleware;
+mod multicall;
+mod panics;
+mod pipeline;
+mod ratelimit;
+mod registry;
+mod rejection;
+#[cfg(feature = "async")]
+mod service;
+mod state;
+mod testing;
+mod trace;
+
+#[cfg(all(feature = "async", feature = "tls"))]
+mod tls;
+#[cfg(unix)]
+mod unix;
+
+use self::access::Access;
+pub use self::admin::Admin;
+#[cfg(all(feature = "async", unix))]
+pub use self::async_server::BoundAsyncUnixServer;
+#[cfg(feature = "async")]
+pub use self::async_server::{AsyncHandler, AsyncServer, BoundAsyncServer, BoxFuture};
+pub use self::auth::BasicAuth;
+pub use self::builder::ServerBuilder;
+pub use self::context::RequestContext;
+pub use self::cors::Cors;
+pub use self::faultstatus::FaultStatus;
+pub use self::filter::{AddressFilter, Cidr};
+pub use self::health::Health;
+pub use self::introspection::MethodInfo;
+pub use self::metrics::Metrics;
+pub use self::middleware::{Middleware, Reply, Request};
+use self::pipeline::{pipeline_setters, Canned, Pipeline};
+pub use self::ratelimit::RateLimit;
+#[cfg(feature = "async")]
+pub use self::registry::AsyncRegistry;
+pub use self::registry::Registry;
+pub use self::rejection::{Rejection, Rejections};
+#[cfg(feature = "async")]
+pub use self::service::AsyncService;
+#[cfg(feature = "async")]
+pub use self::state::AsyncWithState;
+pub use self::state::WithState;
+#[cfg(feature = "async")]
+pub use self::testing::AsyncTestClient;
+pub use self::testing::TestClient;
+#[cfg(all(feature = "async", feature = "tls"))]
+pub use self::tls::TlsConfig;
+use self::trace::Trace;
+#[cfg(unix)]
+pub use self::unix::{BoundUnixServer, UnixOptions};
+
+/// The largest request body the servers accept by default, 16 MiB.
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = 16 * 1024 * 1024;
+
+/// What a server does with calls arriving while it runs as many as it may at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overload {
+    /// Holds the calls until earlier ones are done.
+    Queue,
+    /// Answers the calls with status 503 right away.
+    Reject,
+}
+
+type Handler = Arc<dyn Fn(&RequestContext, Vec<Value>) -> Response + Send + Sync>;
+type Fallback = Arc<dyn Fn(Call) -> Response + Send + Sync>;
+
+/// Faults with the innermost cause, which tells the caller what is wrong with their parameters,
+/// such as an integer given where a struct is expected.
+pub fn on_decode_fail(err: &error::Error) -> Response {
+    let cause = err
+        .iter()
+        .last()
+        .map_or_else(|| err.to_string(), ToString::to_string);
+    Err(Fault::new(
+        400,
+        format!("Failed to decode request: {}", cause),
+    ))
+}
+
+pub fn on_encode_fail(err: &error::Error) -> Response {
+    Err(Fault::new(
+        500,
+        format!("Failed to encode response: {}", err),
+    ))
+}
+
+fn on_missing_method(_: Vec<Value>) -> Response {
+    Err(Fault::new(404, "Requested method does not exist"))
+}
+
+fn timeout_fault() -> Fault {
+    Fault::new(408, "Method took too long to run")
+}
+
+pub struct Server {
+    methods: Registry,
+    mounts: HashMap<String, Server>,
+    pipeline: Pipeline,
+    concurrency: Option<(usize, Overload)>,
+    in_flight: AtomicUsize,
+    fallback: Fallback,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            methods: Registry::default(),
+            mounts: HashMap::new(),
+            pipeline: Pipeline::default(),
+            concurrency: None,
+            in_flight: AtomicUsize::new(0),
+            fallback: Arc::new(|call: Call| on_missing_method(call.params)),
+        }
+    }
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    pub fn register_value<K, T>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        self.methods.register_value(name, handler);
+    }
+
+    /// Registers a handler given the context of the request along with the parameters, such as
+    /// the address of the client.
+    pub fn register_value_with_context<K, T>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(&RequestContext, Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        self.methods.register_value_with_context(name, handler);
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, Tef, Tdf>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        self.methods
+            .register(name, handler, encode_fail, decode_fail);
+    }
+
+    /// Registers a handler taking its parameters as a Rust type, a tuple for several of them,
+    /// and failing calls with the default faults when they do not decode or the result does not
+    /// encode.
+    pub fn register_simple<K, Treq, Tres, Thandler>(&mut self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+    {
+        self.register(name, handler, on_encode_fail, on_decode_fail);
+    }
+
+    /// Registers a handler as `register_simple` does, given the context of the request along
+    /// with the parameters.
+    pub fn register_simple_with_context<K, Treq, Tres, Thandler>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler:
+            Fn(&RequestContext, Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+    {
+        self.methods.register_simple_with_context(name, handler);
+    }
+
+    /// Sets the help text and signatures of a method, for `system.methodHelp` and
+    /// `system.methodSignature`.
+    pub fn describe<K: Into<String>>(&mut self, name: K, info: MethodInfo) {
+        self.methods.describe(name, info);
+    }
+
+    /// Returns the registry of the methods, through which they may be changed once the server
+    /// runs.
+    pub fn registry(&self) -> Registry {
+        self.methods.clone()
+    }
+
+    /// Returns a registry for the namespace, registering methods named with it in front, as in
+    /// `server.namespace("math").register_simple("add", add)` for `math.add`.
+    pub fn namespace(&self, prefix: &str) -> Registry {
+        self.methods.namespace(prefix)
+    }
+
+    /// Returns a client calling the methods in-process, without binding the server, so that
+    /// handlers can be unit tested.
+    pub fn test_client(&self) -> TestClient<'_> {
+        TestClient::new(self)
+    }
+
+    pipeline_setters!();
+
+    /// Answers calls to the URL path, such as `/api/legacy`, with another server's handlers,
+    /// while calls to all other paths stay with this one.
+    ///
+    /// Paths must match exactly, without the query. Servers mounted on the other server are
+    /// mounted on this one as well, as paths are absolute.
+    pub fn mount<K: Into<String>>(&mut self, path: K, mut server: Server) {
+        for (path, mut nested) in std::mem::take(&mut server.mounts) {
+            nested
+                .pipeline
+                .middleware
+                .splice(0..0, server.pipeline.middleware.iter().cloned());
+            self.mounts.insert(path, nested);
+        }
+        self.mounts.insert(path.into(), server);
+    }
+
+    /// Limits the calls run at once, with a thread each, which are unlimited by default.
+    ///
+    /// With `Overload::Queue`, the server runs a pool of as many worker threads, instead of
+    /// starting a thread for every call. With `Overload::Reject`, calls beyond the limit are
+    /// answered on threads of their own, which do nothing but reject them.
+    pub fn set_max_concurrent(&mut self, limit: usize, overload: Overload) {
+        self.concurrency = Some((limit.max(1), overload));
+    }
+
+    /// Returns a registrar handing the state to the handlers registered through it.
+    pub fn with_state<S: Send + Sync + 'static>(&mut self, state: Arc<S>) -> WithState<'_, S> {
+        WithState::new(self, state)
+    }
+
+    /// Encodes the results of typed handlers with the extensions, which clients have to
+    /// understand, such as `nil`.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.methods.set_extensions(extensions);
+    }
+
+    /// Registers the admin methods, `system.status` and `system.shutdown`.
+    pub fn serve_admin(&mut self, admin: Admin) {
+        admin.register(&self.methods);
+    }
+
+    /// Sends responses of at least `min_size` bytes to HTTP/1.1 clients with
+    /// `Transfer-Encoding: chunked`, rather than with a `Content-Length`, as some proxies prefer.
+    ///
+    /// Responses of 32 KiB or more are always chunked, as are those to clients asking for it with
+    /// a `TE` header. Chunked requests are accepted either way.
+    pub fn set_chunked_responses(&mut self, min_size: usize) {
+        self.pipeline.set_chunked_responses(min_size);
+    }
+
+    /// Answers calls of the method running longer than the limit with the timeout fault.
+    ///
+    /// The handler then runs in a thread of its own, which cannot be stopped, so it runs on to
+    /// the end with its result thrown away.
+    pub fn set_method_timeout<K: Into<String>>(&mut self, name: K, limit: Duration) {
+        self.pipeline.timeouts.insert(name.into(), limit);
+    }
+
+    pub fn set_on_missing<T>(&mut self, handler: T)
+    where
+        T: Fn(Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        self.fallback = Arc::new(move |call: Call| handler(call.params));
+    }
+
+    /// Answers calls of methods without a handler, given the whole call, as a gateway passing
+    /// them on to another server would. It replaces the handler set by `set_on_missing`.
+    pub fn set_fallback<T>(&mut self, handler: T)
+    where
+        T: Fn(Call) -> Response + Send + Sync + 'static,
+    {
+        self.fallback = Arc::new(handler);
+    }
+
+    /// Binds the server to the address, where port 0 picks a free port, told by
+    /// `BoundServer::local_addr`.
+    pub fn bind(
+        self,
+        uri: &std::net::SocketAddr,
+    ) -> Result<BoundServer<impl Fn(&rouille::Request) -> rouille::Response + Send + Sync + 'static>>
+    {
+        let concurrency = self.concurrency;
+        rouille::Server::new(uri, move |req| self.handle_outer(req))
+            .map_err(|err| ErrorKind::BindFail(err.description().into()).into())
+            .map(|server| match concurrency {
+                Some((limit, Overload::Queue)) => server.pool_size(limit),
+                _ => server,
+            })
+            .map(BoundServer::new)
+    }
+
+    /// Binds the server to a Unix socket at the path, replacing a socket left there by an
+    /// earlier run. Middleware sees such calls come from `0.0.0.0:0`.
+    #[cfg(unix)]
+    pub fn bind_unix<P: AsRef<std::path::Path>>(
+        self,
+        path: P,
+        options: UnixOptions,
+    ) -> Result<BoundUnixServer> {
+        BoundUnixServer::new(self, path.as_ref(), options)
+    }
+
+    /// Answers a single call as a CGI script, reading the request from the environment and
+    /// standard input, and writing the response to standard output.
+    pub fn handle_cgi(&self) -> Result<()> {
+        cgi::handle(self)
+    }
+
+    fn handle_outer(&self, request: &rouille::Request) -> rouille::Response {
+        let received = Instant::now();
+        let mut access = Access::default();
+        let trace = Trace::new(*request.remote_addr(), received);
+        let origin = request.header("Origin");
+        let response = trace.in_scope(|| {
+            let preflight =
+                self.pipeline
+                    .preflight(request.method(), origin, request.remote_addr().ip());
+            let mut response = match preflight {
+                Some(canned) => return canned_response(canned),
+                None => self.respond(request, received, &mut access, &trace),
+            };
+            for (name, value) in self.pipeline.cors_headers(origin) {
+                response = response.with_unique_header(name, value);
+            }
+            response
+        });
+        trace.answered(response.status_code);
+        if self.pipeline.access_log {
+            access.log(
+                *request.remote_addr(),
+                response.status_code,
+                received.elapsed(),
+            );
+        }
+        response
+    }
+
+    fn respond(
+        &self,
+        request: &rouille::Request,
+        received: Instant,
+        access: &mut Access,
+        trace: &Trace,
+    ) -> rouille::Response {
+        let path = request.url();
+        let screened = self.pipeline.screen(
+            request.method(),
+            &path,
+            request.header("Content-Type"),
+            request.remote_addr().ip(),
+            access,
+        );
+        if let Some(canned) = screened {
+            return canned_response(canned);
+        }
+        let _slot = match self.concurrency {
+            Some((limit, Overload::Reject)) => match Slot::take(&self.in_flight, limit) {
+                Some(slot) => Some(slot),
+                None => return canned_response(Canned::empty(503)),
+            },
+            _ => None,
+        };
+        let server = self.mounts.get(&path).unwrap_or(self);
+        let headers = request
+            .headers()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect();
+        let remote_addr = *request.remote_addr();
+
+        let max_request_size = self.pipeline.max_request_size;
+        let declared = request
+            .header("Content-Length")
+            .and_then(|length| length.parse::<usize>().ok());
+        if declared.is_some_and(|length| length > max_request_size) {
+            return canned_response(Canned::empty(413));
+        }
+        let body = match request.data() {
+            Some(data) => data,
+            None => return rouille::Response::empty_400(),
+        };
+        // The call is parsed as the body arrives, rather than once it is all read.
+        let mut body = Capped::new(body, max_request_size);
+        let parsed = parse::call_with(
+            &mut body,
+            self.pipeline.parse_options,
+            self.pipeline.max_value_size,
+        );
+        let call: Call = match parsed {
+            _ if body.exceeded() => return canned_response(Canned::empty(413)),
+            Ok(data) => data,
+            Err(err) => return canned_response(self.pipeline.unparsed(&err, access)),
+        };
+        access.method = Some(call.name.clone());
+        trace.parsed(&call.name);
+
+        let chain = self.pipeline.chain(&server.pipeline);
+        let mut request_info = Request::new(path, headers, remote_addr, call, received);
+        let (passed, mut reply) = match middleware::before(&chain, &mut request_info) {
+            None => {
+                let name = request_info.call().name.clone();
+                let params = request_info.take_params();
+                let context = RequestContext::new(&request_info);
+                trace.dispatching(context.request_id());
+                let res = server.handle(Call { name, params }, &context);
+                trace.dispatched(&res);
+                (chain.len(), Reply::new(res))
+            }
+            Some(rejected) => rejected,
+        };
+        middleware::after(&chain[..passed], &request_info, &mut reply);
+
+        let encoded = self
+            .pipeline
+            .encode(reply, request.header("Accept-Encoding"), access, trace);
+        let mut response = match encoded.body {
+            Some(body) => {
+                // Bodies of unknown length are sent chunked.
+                let data = if encoded.chunked {
+                    rouille::ResponseBody::from_reader(std::io::Cursor::new(body))
+                } else {
+                    rouille::ResponseBody::from_data(body)
+                };
+                let response = rouille::Response {
+                    status_code: 200,
+                    headers: vec![("Content-Type".into(), "text/xml".into())],
+                    data,
+                    upgrade: None,
+                };
+                if encoded.gzipped {
+                    response.with_unique_header("Content-Encoding", "gzip")
+                } else {
+                    response
+                }
+            }
+            None => rouille::Response {
+                status_code: encoded.status,
+                headers: Vec::new(),
+                data: rouille::ResponseBody::empty(),
+                upgrade: None,
+            },
+        };
+        response.status_code = encoded.status;
+        for (name, value) in encoded.headers {
+            response = response.with_unique_header(name, value);
+        }
+        response
+    }
+
+    fn handle(&self, req: Call, context: &RequestContext) -> Response {
+        if self.pipeline.is_multicall(&req, &self.methods.read()) {
+            let calls = multicall::unpack(req.params)?;
+            let results = calls
+                .into_iter()
+                .map(|call| call.and_then(|call| self.dispatch(call, context)))
+                .collect();
+            return multicall::pack(results);
+        }
+        self.dispatch(req, context)
+    }
+
+    fn dispatch(&self, req: Call, context: &RequestContext) -> Response {
+        // The handler runs without holding the lock, so that it may change the methods itself.
+        let handler = match self.pipeline.lookup(&req, &self.methods.read()) {
+            Ok(handler) => handler,
+            Err(response) => return response,
+        };
+        let backtraces = self.pipeline.panic_backtraces;
+        let Call { name, params } = req;
+        let handler: Handler = match handler {
+            Some(handler) => handler,
+            None => {
+                let fallback = self.fallback.clone();
+                let name = name.clone();
+                Arc::new(move |_: &RequestContext, params| {
+                    let name = name.clone();
+                    fallback(Call { name, params })
+                })
+            }
+        };
+        let limit = match self.pipeline.timeouts.get(&name) {
+            Some(&limit) => limit,
+            None => {
+                return panics::catch(&name, backtraces, || handler(context, params))
+                    .unwrap_or_else(Err)
+            }
+        };
+        let (sender, receiver) = mpsc::channel();
+        let context = context.clone();
+        thread::spawn(move || {
+            let response = panics::catch(&name, backtraces, || handler(&context, params));
+            let _ = sender.send(response.unwrap_or_else(Err));
+        });
+        receiver
+            .recv_timeout(limit)
+            .unwrap_or_else(|_| Err(self.pipeline.timeout_fault.clone()))
+    }
+}
+
+/// Turns a response decided by the pipeline into one for rouille.
+fn canned_response(canned: Canned) -> rouille::Response {
+    let mut response = rouille::Response {
+        status_code: canned.status,
+        headers: Vec::new(),
+        data: rouille::ResponseBody::from_data(canned.body),
+        upgrade: None,
+    };
+    if let Some(content_type) = canned.content_type {
+        response = response.with_unique_header("Content-Type", content_type);
+    }
+    for (name, value) in canned.headers {
+        response = response.with_unique_header(name, value);
+    }
+    response
+}
+
+/// A request body failing to read once more than the limit has been read from it.
+pub(super) struct Capped<R> {
+    inner: R,
+    left: usize,
+    exceeded: bool,
+}
+
+impl<R: Read> Capped<R> {
+    pub(super) fn new(inner: R, limit: usize) -> Capped<R> {
+        Capped {
+            inner,
+            left: limit,
+            exceeded: false,
+        }
+    }
+
+    pub(super) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+impl<R: Read> Read for Capped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Reading one byte past the limit tells bodies at the limit from larger ones.
+        let len = buf.len().min(self.left.saturating_add(1));
+        let read = self.inner.read(&mut buf[..len])?;
+        if read > self.left {
+            self.exceeded = true;
+            return Err(std::io::Error::other("Request body too large."));
+        }
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+/// One of the calls a server runs at once, given back when dropped.
+struct Slot<'a>(&'a AtomicUsize);
+
+impl<'a> Slot<'a> {
+    fn take(in_flight: &'a AtomicUsize, limit: usize) -> Option<Slot<'a>> {
+        in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                if count < limit {
+                    Some(count + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| Slot(in_flight))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub struct BoundServer<F>
+where
+    F: Send + Sync + 'static + Fn(&rouille::Request) -> rouille::Response,
+{
+    server: rouille::Server<F>,
+    // server: hyper::Server<NewService, hyper::Body>,
+}
+
+impl<F> BoundServer<F>
+where
+    F: Send + Sync + 'static + Fn(&rouille::Request) -> rouille::Response,
+{
+    fn new(server: rouille::Server<F>) -> Self {
+        Self { server }
+    }
+
+    /// The address the server is bound to, with the port actually picked when binding port 0.
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.server.server_addr()
+    }
+
+    /// Serves requests until the process ends, keeping connections alive for further calls,
+    /// which clients may send before earlier ones are answered.
+    pub fn run(self) {
+        self.server.run()
+    }
+
+    pub fn poll(&self) {
+        self.server.poll()
+    }
+}
@@ -0,0 +1,138 @@
+use crate::xmlfmt::value::take_member;
+use crate::xmlfmt::{Call, Fault, Params, Response, Value};
+
+pub const MULTICALL: &str = "system.multicall";
+
+/// Unpacks the calls of a `system.multicall` call, with faults in place of invalid entries.
+pub fn unpack(mut params: Params) -> Result<Vec<Result<Call, Fault>>, Fault> {
+    let calls = match (params.pop(), params.is_empty()) {
+        (Some(Value::Array(calls)), true) => calls,
+        _ => return Err(Fault::new(400, "Expected an array of calls")),
+    };
+    Ok(calls.into_iter().map(call).collect())
+}
+
+fn call(entry: Value) -> Result<Call, Fault> {
+    let mut entry = match entry {
+        Value::Struct(entry) => entry,
+        _ => return Err(Fault::new(400, "Expected a call struct")),
+    };
+    let name = match take_member(&mut entry, "methodName") {
+        Some(Value::String(name)) => name,
+        _ => return Err(Fault::new(400, "Expected a methodName string")),
+    };
+    // Nesting would let a single request fan out without bound.
+    if name == MULTICALL {
+        return Err(Fault::new(400, "Recursive system.multicall is not allowed"));
+    }
+    match take_member(&mut entry, "params") {
+        Some(Value::Array(params)) => Ok(Call { name, params }),
+        _ => Err(Fault::new(400, "Expected a params array")),
+    }
+}
+
+/// Packs the results into the response of a `system.multicall` call, as one-element arrays for
+/// successful calls and as fault structs for failed ones.
+pub fn pack(results: Vec<Response>) -> Response {
+    let results = results
+        .into_iter()
+        .map(|result| match result {
+            Ok(params) => Value::Array(params),
+            Err(fault) => {
+                let (code, message) = (fault.code, fault.message.clone());
+                let mut entry = fault.into_members();
+                entry.insert("faultCode".to_owned(), Value::Int(code));
+                entry.insert("faultString".to_owned(), Value::String(message));
+                Value::Struct(entry)
+            }
+        })
+        .collect();
+    Ok(vec![Value::Array(results)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Members;
+
+    fn entry(name: Value, params: Value) -> Value {
+        let mut entry = Members::new();
+        entry.insert("methodName".to_owned(), name);
+        entry.insert("params".to_owned(), params);
+        Value::Struct(entry)
+    }
+
+    #[test]
+    fn unpacks_calls_with_faults_for_invalid_entries() {
+        let calls = unpack(vec![Value::Array(vec![
+            entry("add".into(), Value::Array(vec![Value::Int(1)])),
+            Value::Int(2),
+            entry(Value::Int(3), Value::Array(vec![])),
+            entry("add".into(), Value::Int(4)),
+        ])])
+        .unwrap();
+        assert_eq!(
+            calls[0],
+            Ok(Call {
+                name: "add".into(),
+                params: vec![Value::Int(1)],
+            })
+        );
+        let messages = calls[1..]
+            .iter()
+            .map(|call| call.as_ref().unwrap_err().message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Expected a call struct",
+                "Expected a methodName string",
+                "Expected a params array",
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_nested_multicalls() {
+        let nested = entry(MULTICALL.into(), Value::Array(vec![Value::Array(vec![])]));
+        let calls = unpack(vec![Value::Array(vec![nested])]).unwrap();
+        let fault = calls[0].as_ref().unwrap_err();
+        assert_eq!(fault.code, 400);
+        assert_eq!(fault.message, "Recursive system.multicall is not allowed");
+    }
+
+    #[test]
+    fn rejects_parameters_other_than_one_array() {
+        for params in vec![
+            vec![],
+            vec![Value::Struct(Members::new())],
+            vec![Value::Array(vec![]), Value::Array(vec![])],
+        ] {
+            let fault = unpack(params).unwrap_err();
+            assert_eq!(fault.code, 400);
+            assert_eq!(fault.message, "Expected an array of calls");
+        }
+    }
+
+    #[test]
+    fn packs_results_and_faults() {
+        let response = pack(vec![
+            Ok(vec![Value::Int(3)]),
+            Err(Fault::new(4, "Too many parameters.")),
+        ])
+        .unwrap();
+        let mut fault = Members::new();
+        fault.insert("faultCode".to_owned(), Value::Int(4));
+        fault.insert(
+            "faultString".to_owned(),
+            Value::String("Too many parameters.".into()),
+        );
+        assert_eq!(
+            response,
+            vec![Value::Array(vec![
+                Value::Array(vec![Value::Int(3)]),
+                Value::Struct(fault),
+            ])]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::xmlfmt::Fault;
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+#[cfg(feature = "async")]
+use crate::xmlfmt::Response;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+thread_local! {
+    static BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+/// The fault answering calls whose handler panicked.
+pub fn fault() -> Fault {
+    Fault::new(500, "Handler panicked")
+}
+
+/// Runs the handler, failing with the fault if it panics, after logging the panic at the error
+/// level with the `xml_rpc::panic` target.
+pub fn catch<R, F: FnOnce() -> R>(name: &str, backtraces: bool, handler: F) -> Result<R, Fault> {
+    panic::catch_unwind(AssertUnwindSafe(handler)).map_err(|payload| {
+        report(name, backtraces, &*payload);
+        fault()
+    })
+}
+
+/// Installs a panic hook, running before the hook already in place, that keeps the backtrace
+/// of the panic for the log. Backtraces are only known where the panic happens, and only hooks
+/// run there.
+pub fn capture_backtraces() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            BACKTRACE.with(|backtrace| *backtrace.borrow_mut() = Some(Backtrace::force_capture()));
+            previous(info);
+        }));
+    });
+}
+
+fn report(name: &str, backtraces: bool, payload: &(dyn Any + Send)) {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    let backtrace = BACKTRACE.with(|backtrace| backtrace.borrow_mut().take());
+    match backtrace {
+        Some(backtrace) if backtraces => log::error!(
+            target: "xml_rpc::panic",
+            "Handler of {} panicked: {}\n{}",
+            name,
+            message,
+            backtrace
+        ),
+        _ => log::error!(target: "xml_rpc::panic", "Handler of {} panicked: {}", name, message),
+    }
+}
+
+/// A future answering with the fault if the one it wraps panics while polled.
+#[cfg(feature = "async")]
+pub struct CatchUnwind<F> {
+    name: String,
+    backtraces: bool,
+    future: F,
+}
+
+#[cfg(feature = "async")]
+impl<F> CatchUnwind<F> {
+    pub fn new(name: String, backtraces: bool, future: F) -> Self {
+        CatchUnwind {
+            name,
+            backtraces,
+            future,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F: Future<Output = Response> + Unpin> Future for CatchUnwind<F> {
+    type Output = Response;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Response> {
+        let this = &mut *self;
+        let future = &mut this.future;
+        match panic::catch_unwind(AssertUnwindSafe(|| Pin::new(future).poll(cx))) {
+            Ok(poll) => poll,
+            Err(payload) => {
+                report(&this.name, this.backtraces, &*payload);
+                Poll::Ready(Err(fault()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::Server;
+    use crate::xmlfmt::Value;
+    use crate::Client;
+
+    #[test]
+    fn answers_panics_with_the_fault() {
+        assert_eq!(catch("m", false, || 1), Ok(1));
+        assert_eq!(catch("m", true, || -> i32 { panic!("boom") }), Err(fault()));
+        let message = String::from("boom");
+        assert_eq!(
+            catch("m", false, || -> i32 { panic!("{}", message) }),
+            Err(fault())
+        );
+    }
+
+    #[test]
+    fn keeps_serving_after_a_handler_panics() {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        server.register_value("boom", |_| panic!("boom"));
+        let bound = server.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let url = format!("http://{}/", bound.local_addr()).parse().unwrap();
+        std::thread::spawn(move || bound.run());
+
+        let client = Client::new().unwrap();
+        for _ in 0..2 {
+            let response = client.call_value(&url, "boom", vec![]).unwrap();
+            assert_eq!(response, Err(fault()));
+            let params = vec![Value::Int(1)];
+            let response = client.call_value(&url, "echo", params.clone()).unwrap();
+            assert_eq!(response, Ok(params));
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn answers_panicking_futures_with_the_fault() {
+        use crate::server::AsyncServer;
+
+        let mut server = AsyncServer::new();
+        server.register_value("boom", |_| async { panic!("boom") });
+        server.register_value("echo", |params| async { Ok(params) });
+        let client = server.test_client();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let response = client.call_value("boom", vec![]).await.unwrap();
+            assert_eq!(response, Err(fault()));
+            let response = client
+                .call_value("echo", vec![Value::Int(1)])
+                .await
+                .unwrap();
+            assert_eq!(response, Ok(vec![Value::Int(1)]));
+        });
+    }
+}
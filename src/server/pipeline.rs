@@ -0,0 +1,545 @@
+use super::access::Access;
+use super::compression;
+use super::cors::Cors;
+use super::filter::AddressFilter;
+use super::health::Health;
+use super::introspection;
+use super::metrics::Metrics;
+use super::middleware::{Middleware, Reply};
+use super::multicall::MULTICALL;
+use super::panics;
+use super::registry::Methods;
+use super::rejection::{Rejection, Rejections};
+use super::trace::Trace;
+use super::{timeout_fault, DEFAULT_MAX_REQUEST_SIZE};
+use crate::xmlfmt::value::ToXml;
+use crate::xmlfmt::{error, Call, Fault, ParseOptions, Response};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The configuration shared by `Server` and `AsyncServer`, along with the steps of answering a
+/// request that do not depend on the HTTP library serving it.
+pub(super) struct Pipeline {
+    pub(super) multicall: bool,
+    pub(super) check_signatures: bool,
+    pub(super) middleware: Vec<Arc<dyn Middleware>>,
+    pub(super) max_request_size: usize,
+    pub(super) max_value_size: usize,
+    pub(super) parse_options: ParseOptions,
+    pub(super) access_log: bool,
+    address_filter: Option<AddressFilter>,
+    health: Option<Health>,
+    cors: Option<Cors>,
+    rejections: Rejections,
+    metrics: Option<(String, Metrics)>,
+    compress_threshold: Option<usize>,
+    chunked_threshold: Option<usize>,
+    pub(super) timeouts: HashMap<String, Duration>,
+    pub(super) timeout_fault: Fault,
+    pub(super) panic_backtraces: bool,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline {
+            multicall: true,
+            check_signatures: true,
+            middleware: Vec::new(),
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            max_value_size: usize::MAX,
+            parse_options: ParseOptions::default(),
+            access_log: false,
+            address_filter: None,
+            health: None,
+            cors: None,
+            rejections: Rejections::default(),
+            metrics: None,
+            compress_threshold: None,
+            chunked_threshold: None,
+            timeouts: HashMap::new(),
+            timeout_fault: timeout_fault(),
+            panic_backtraces: false,
+        }
+    }
+}
+
+/// A response decided without running a call, which each server turns into one of its own.
+pub(super) struct Canned {
+    pub(super) status: u16,
+    pub(super) content_type: Option<&'static str>,
+    pub(super) body: Vec<u8>,
+    pub(super) headers: Vec<(String, String)>,
+}
+
+impl Canned {
+    pub(super) fn empty(status: u16) -> Canned {
+        Canned {
+            status,
+            content_type: None,
+            body: Vec::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    fn text(status: u16, content_type: &'static str, body: &str, access: &mut Access) -> Canned {
+        access.size = body.len();
+        Canned {
+            status,
+            content_type: Some(content_type),
+            body: body.as_bytes().to_vec(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Answers a request that is not an XML-RPC call with the rejection.
+    pub(super) fn rejected(rejection: &Rejection, access: &mut Access) -> Canned {
+        let mut canned = match rejection.html() {
+            Some(html) => {
+                Canned::text(rejection.status(), "text/html; charset=utf-8", html, access)
+            }
+            None => Canned::empty(rejection.status()),
+        };
+        if rejection.status() == 405 {
+            canned.headers.push(("Allow".into(), "POST".into()));
+        }
+        canned
+    }
+}
+
+/// The response to a call, once the middleware is done with it.
+pub(super) struct Encoded {
+    pub(super) status: u16,
+    pub(super) headers: Vec<(String, String)>,
+    /// The XML of the response, unless middleware rejected the call.
+    pub(super) body: Option<Vec<u8>>,
+    pub(super) gzipped: bool,
+    /// Whether the body is large enough to be sent chunked.
+    pub(super) chunked: bool,
+}
+
+impl Pipeline {
+    pub(super) fn set_address_filter(&mut self, filter: AddressFilter) {
+        self.address_filter = Some(filter);
+    }
+
+    pub(super) fn set_cors(&mut self, cors: Cors) {
+        self.cors = Some(cors);
+    }
+
+    pub(super) fn set_rejections(&mut self, rejections: Rejections) {
+        self.rejections = rejections;
+    }
+
+    pub(super) fn serve_health(&mut self, health: Health) {
+        self.health = Some(health);
+    }
+
+    pub(super) fn serve_metrics(&mut self, path: String, metrics: Metrics) {
+        self.middleware.push(Arc::new(metrics.clone()));
+        self.metrics = Some((path, metrics));
+    }
+
+    pub(super) fn set_compress_responses(&mut self, min_size: usize) {
+        self.compress_threshold = Some(min_size);
+    }
+
+    pub(super) fn set_chunked_responses(&mut self, min_size: usize) {
+        self.chunked_threshold = Some(min_size);
+    }
+
+    pub(super) fn set_panic_backtraces(&mut self, enabled: bool) {
+        if enabled {
+            panics::capture_backtraces();
+        }
+        self.panic_backtraces = enabled;
+    }
+
+    /// Answers preflight requests, if the server lets browsers call it, once the address filter
+    /// lets them through.
+    pub(super) fn preflight(
+        &self,
+        method: &str,
+        origin: Option<&str>,
+        remote_ip: IpAddr,
+    ) -> Option<Canned> {
+        let cors = self.cors.as_ref().filter(|_| method == "OPTIONS")?;
+        if let Some(refused) = self.refused(remote_ip) {
+            return Some(refused);
+        }
+        Some(match cors.preflight(origin) {
+            Some(headers) => Canned {
+                headers,
+                ..Canned::empty(204)
+            },
+            None => Canned::empty(403),
+        })
+    }
+
+    /// Refuses requests from addresses the filter does not let through.
+    fn refused(&self, remote_ip: IpAddr) -> Option<Canned> {
+        match self.address_filter {
+            Some(ref filter) if !filter.permits(remote_ip) => Some(Canned::empty(403)),
+            _ => None,
+        }
+    }
+
+    /// The CORS headers to add to the response to a request from the origin.
+    pub(super) fn cors_headers(&self, origin: Option<&str>) -> Vec<(String, String)> {
+        self.cors
+            .as_ref()
+            .map_or_else(Vec::new, |cors| cors.headers(origin))
+    }
+
+    /// Answers the requests that are not calls to run, before their body is read: requests from
+    /// addresses the filter refuses, health probes, metrics scrapes, and requests rejected for
+    /// their method or content type.
+    pub(super) fn screen(
+        &self,
+        method: &str,
+        path: &str,
+        content_type: Option<&str>,
+        remote_ip: IpAddr,
+        access: &mut Access,
+    ) -> Option<Canned> {
+        if let Some(refused) = self.refused(remote_ip) {
+            return Some(refused);
+        }
+        if let Some(ref health) = self.health {
+            if method == "GET" {
+                if let Some((status, body)) = health.probe(path) {
+                    return Some(Canned::text(
+                        status,
+                        "text/plain; charset=utf-8",
+                        body,
+                        access,
+                    ));
+                }
+            }
+        }
+        if let Some((ref metrics_path, ref metrics)) = self.metrics {
+            if method == "GET" && path == metrics_path {
+                let body = metrics.render();
+                return Some(Canned::text(
+                    200,
+                    "text/plain; version=0.0.4",
+                    &body,
+                    access,
+                ));
+            }
+        }
+        self.rejections
+            .check(method, content_type)
+            .map(|rejection| Canned::rejected(rejection, access))
+    }
+
+    /// Answers a call that failed to parse, with status 413 for values over the limit.
+    pub(super) fn unparsed(&self, err: &error::Error, access: &mut Access) -> Canned {
+        match *err.kind() {
+            error::ErrorKind::ValueTooLarge(_) => Canned::empty(413),
+            _ => self.malformed(access),
+        }
+    }
+
+    /// Answers a request whose body is not an XML-RPC call.
+    pub(super) fn malformed(&self, access: &mut Access) -> Canned {
+        Canned::rejected(self.rejections.malformed_body(), access)
+    }
+
+    /// The middleware run for calls answered by the server, itself or one mounted on it.
+    pub(super) fn chain(&self, mounted: &Pipeline) -> Vec<Arc<dyn Middleware>> {
+        if std::ptr::eq(mounted, self) {
+            return self.middleware.clone();
+        }
+        self.middleware
+            .iter()
+            .chain(&mounted.middleware)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the call is one to the built-in `system.multicall`.
+    pub(super) fn is_multicall<H>(&self, call: &Call, methods: &Methods<H>) -> bool {
+        self.multicall && call.name == MULTICALL && !methods.handlers.contains_key(&call.name)
+    }
+
+    /// Finds the handler of the call, unless introspection answers it or its parameters match
+    /// none of the signatures of the method.
+    pub(super) fn lookup<H: Clone>(
+        &self,
+        call: &Call,
+        methods: &Methods<H>,
+    ) -> std::result::Result<Option<H>, Response> {
+        let introspected = introspection::handle(
+            &call.name,
+            &call.params,
+            &methods.handlers,
+            &methods.info,
+            self.multicall,
+        );
+        if let Some(response) = introspected {
+            return Err(response);
+        }
+        let handler = methods.handler(&call.name);
+        if let Some(info) = methods.info.get(&call.name) {
+            if self.check_signatures && handler.is_some() {
+                info.check(&call.name, &call.params).map_err(Err)?;
+            }
+        }
+        Ok(handler)
+    }
+
+    /// Encodes the response the middleware is done with, gzipped for clients accepting it.
+    pub(super) fn encode(
+        &self,
+        reply: Reply,
+        accept_encoding: Option<&str>,
+        access: &mut Access,
+        trace: &Trace,
+    ) -> Encoded {
+        let (status, headers, res) = reply.into_parts();
+        access.fault = res
+            .as_ref()
+            .and_then(|res| res.as_ref().err())
+            .map(|f| f.code);
+        let mut encoded = Encoded {
+            status,
+            headers,
+            body: None,
+            gzipped: false,
+            chunked: false,
+        };
+        if let Some(res) = res {
+            let body = res.to_xml().into_bytes();
+            trace.serialized(body.len());
+            let compressed = self
+                .compress_threshold
+                .filter(|&min_size| body.len() >= min_size)
+                .filter(|_| compression::accepts_gzip(accept_encoding))
+                .and_then(|_| compression::gzip(&body));
+            encoded.gzipped = compressed.is_some();
+            let body = compressed.unwrap_or(body);
+            access.size = body.len();
+            encoded.chunked = self
+                .chunked_threshold
+                .is_some_and(|min_size| body.len() >= min_size);
+            encoded.body = Some(body);
+        }
+        encoded
+    }
+}
+
+/// Generates the setters of the configuration held by the pipelines of both servers.
+macro_rules! pipeline_setters {
+    () => {
+        /// Enables or disables the built-in `system.multicall`, which is on by default.
+        ///
+        /// Each multicall runs any number of calls for a single request, which deployments
+        /// limiting requests, such as through a proxy, may want to rule out.
+        pub fn set_multicall(&mut self, enabled: bool) {
+            self.pipeline.multicall = enabled;
+        }
+
+        /// Checks the parameters of calls to methods described with signatures against them
+        /// when enabled, as it is by default, failing calls matching none with a fault of code
+        /// 400 before the handler runs.
+        pub fn set_check_signatures(&mut self, enabled: bool) {
+            self.pipeline.check_signatures = enabled;
+        }
+
+        /// Adds the middleware to the end of the chain run for every call.
+        pub fn add_middleware<M: Middleware + 'static>(&mut self, middleware: M) {
+            self.pipeline.middleware.push(Arc::new(middleware));
+        }
+
+        /// Sets the largest request body accepted, in bytes, answering larger ones with status
+        /// 413.
+        ///
+        /// Defaults to `DEFAULT_MAX_REQUEST_SIZE`, and applies to servers mounted on this one,
+        /// whose own limits are ignored.
+        pub fn set_max_request_size(&mut self, limit: usize) {
+            self.pipeline.max_request_size = limit;
+        }
+
+        /// Sets the largest string or base64 value accepted in calls, in decoded bytes,
+        /// answering calls with larger ones with status 413. Values are only limited by the
+        /// request size by default.
+        pub fn set_max_value_size(&mut self, limit: usize) {
+            self.pipeline.max_value_size = limit;
+        }
+
+        /// Reads calls with the options, tolerating the deviations from the specification they
+        /// allow, or none with `ParseOptions::strict`. Like the size limits, they apply to
+        /// servers mounted on this one.
+        pub fn set_parse_options(&mut self, options: ParseOptions) {
+            self.pipeline.parse_options = options;
+        }
+
+        /// Logs every request through the `log` crate when enabled, at the info level with the
+        /// `xml_rpc::access` target, along with the peer address, method, HTTP status, outcome,
+        /// response size and duration.
+        pub fn set_access_log(&mut self, enabled: bool) {
+            self.pipeline.access_log = enabled;
+        }
+
+        /// Lets through only the requests from addresses the filter permits, health probes and
+        /// CORS preflights included, before reading their body. The address is that of the
+        /// connection rather than one from proxy headers.
+        pub fn set_address_filter(&mut self, filter: AddressFilter) {
+            self.pipeline.set_address_filter(filter);
+        }
+
+        /// Lets browsers on the origins allowed call the server.
+        pub fn set_cors(&mut self, cors: Cors) {
+            self.pipeline.set_cors(cors);
+        }
+
+        /// Answers requests that are not XML-RPC calls as set, instead of with status 400.
+        pub fn set_rejections(&mut self, rejections: Rejections) {
+            self.pipeline.set_rejections(rejections);
+        }
+
+        /// Answers liveness and readiness probes, on the paths of the probes, as orchestrators
+        /// such as Kubernetes expect.
+        pub fn serve_health(&mut self, health: Health) {
+            self.pipeline.serve_health(health);
+        }
+
+        /// Counts the calls with the metrics, served in the Prometheus text format to GET
+        /// requests for the path, such as `/metrics`.
+        ///
+        /// The metrics are kept by middleware added here, so middleware added before runs
+        /// outside of what they measure.
+        pub fn serve_metrics<P: Into<String>>(&mut self, path: P, metrics: Metrics) {
+            self.pipeline.serve_metrics(path.into(), metrics);
+        }
+
+        /// Gzips responses of at least `min_size` bytes for clients accepting it, as told by
+        /// their `Accept-Encoding` header, which pays off for large arrays and structs.
+        pub fn set_compress_responses(&mut self, min_size: usize) {
+            self.pipeline.set_compress_responses(min_size);
+        }
+
+        /// Sets the fault answering calls that run out of time, code 408 by default.
+        pub fn set_timeout_fault(&mut self, fault: Fault) {
+            self.pipeline.timeout_fault = fault;
+        }
+
+        /// Logs the backtrace of handlers panicking along with the panic, when enabled.
+        ///
+        /// Panics are always answered with a fault of code 500 and logged through the `log`
+        /// crate, at the error level with the `xml_rpc::panic` target. Backtraces are captured
+        /// by a panic hook run before the one already installed, which is set up the first time
+        /// this is enabled.
+        pub fn set_panic_backtraces(&mut self, enabled: bool) {
+            self.pipeline.set_panic_backtraces(enabled);
+        }
+    };
+}
+
+pub(super) use pipeline_setters;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Value;
+
+    const LOCAL: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn screen(pipeline: &Pipeline, method: &str, path: &str) -> Option<u16> {
+        pipeline
+            .screen(
+                method,
+                path,
+                Some("text/xml"),
+                LOCAL,
+                &mut Access::default(),
+            )
+            .map(|canned| canned.status)
+    }
+
+    #[test]
+    fn screens_requests_before_reading_them() {
+        let mut pipeline = Pipeline::default();
+        assert_eq!(screen(&pipeline, "POST", "/"), None);
+        pipeline.serve_health(Health::new());
+        pipeline.serve_metrics("/metrics".into(), Metrics::new());
+        pipeline.set_rejections(Rejections::new().wrong_method(Rejection::new(405)));
+        assert_eq!(screen(&pipeline, "GET", "/healthz"), Some(200));
+        assert_eq!(screen(&pipeline, "GET", "/metrics"), Some(200));
+        assert_eq!(screen(&pipeline, "GET", "/"), Some(405));
+        assert_eq!(screen(&pipeline, "POST", "/"), None);
+    }
+
+    #[test]
+    fn refuses_addresses_first() {
+        let mut pipeline = Pipeline::default();
+        pipeline.serve_health(Health::new());
+        pipeline.serve_metrics("/metrics".into(), Metrics::new());
+        pipeline.set_cors(Cors::new().allow_origin("https://a.com"));
+        pipeline.set_address_filter(AddressFilter::new().deny("127.0.0.0/8".parse().unwrap()));
+        assert_eq!(screen(&pipeline, "GET", "/healthz"), Some(403));
+        assert_eq!(screen(&pipeline, "GET", "/metrics"), Some(403));
+        assert_eq!(screen(&pipeline, "POST", "/"), Some(403));
+        let preflight = pipeline.preflight("OPTIONS", Some("https://a.com"), LOCAL);
+        assert_eq!(preflight.map(|canned| canned.status), Some(403));
+    }
+
+    #[test]
+    fn rejects_with_pages() {
+        let mut access = Access::default();
+        let canned = Canned::rejected(&Rejection::new(405).with_html("<p>POST</p>"), &mut access);
+        assert_eq!(canned.content_type, Some("text/html; charset=utf-8"));
+        assert_eq!(canned.body, b"<p>POST</p>");
+        assert_eq!(canned.headers, vec![("Allow".into(), "POST".into())]);
+        assert_eq!(access.size, 11);
+    }
+
+    #[test]
+    fn answers_preflights_with_cors() {
+        let mut pipeline = Pipeline::default();
+        assert!(pipeline
+            .preflight("OPTIONS", Some("https://a.com"), LOCAL)
+            .is_none());
+        pipeline.set_cors(Cors::new().allow_origin("https://a.com"));
+        let allowed = pipeline
+            .preflight("OPTIONS", Some("https://a.com"), LOCAL)
+            .unwrap();
+        assert_eq!(allowed.status, 204);
+        assert!(!allowed.headers.is_empty());
+        let refused = pipeline
+            .preflight("OPTIONS", Some("https://b.com"), LOCAL)
+            .unwrap();
+        assert_eq!(refused.status, 403);
+        assert!(pipeline
+            .preflight("POST", Some("https://a.com"), LOCAL)
+            .is_none());
+    }
+
+    #[test]
+    fn encodes_responses() {
+        let mut pipeline = Pipeline::default();
+        pipeline.set_compress_responses(500);
+        pipeline.set_chunked_responses(500);
+        let trace = Trace::new("127.0.0.1:1".parse().unwrap(), std::time::Instant::now());
+        let small = Reply::new(Ok(vec![Value::Int(1)]));
+        let mut access = Access::default();
+        let encoded = pipeline.encode(small, Some("gzip"), &mut access, &trace);
+        assert!(!encoded.gzipped && !encoded.chunked);
+        assert_eq!(access.size, encoded.body.unwrap().len());
+
+        let large = Reply::new(Ok(vec![Value::String("x".repeat(1000))]));
+        let encoded = pipeline.encode(large, Some("gzip"), &mut access, &trace);
+        assert!(encoded.gzipped);
+        let large = Reply::new(Ok(vec![Value::String("x".repeat(1000))]));
+        let encoded = pipeline.encode(large, None, &mut access, &trace);
+        assert!(!encoded.gzipped && encoded.chunked);
+
+        let fault = Reply::new(Err(Fault::new(3, "no")));
+        pipeline.encode(fault, None, &mut access, &trace);
+        assert_eq!(access.fault, Some(3));
+        let rejected = pipeline.encode(Reply::reject(429), None, &mut access, &trace);
+        assert_eq!((rejected.status, rejected.body), (429, None));
+    }
+}
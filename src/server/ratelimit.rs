@@ -0,0 +1,227 @@
+use super::{Middleware, Reply, Request};
+use crate::xmlfmt::Fault;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Middleware limiting the calls of every client, told by IP address, with a token bucket.
+///
+/// Calls over the limit are rejected with status 429 and a `Retry-After` header, or answered
+/// with a fault when one is set.
+pub struct RateLimit {
+    rate: f64,
+    burst: f64,
+    per_method: bool,
+    fault: Option<Fault>,
+    buckets: Mutex<Buckets>,
+}
+
+struct Buckets {
+    clients: HashMap<IpAddr, HashMap<Option<String>, Bucket>>,
+    prune_at: usize,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Buckets are only cleared out once there are this many clients, or twice as many as last time.
+const PRUNE_AT: usize = 1024;
+
+/// How many methods of a client are limited on their own, past which calls to other methods
+/// share a bucket, so that made-up method names do not take up ever more of them.
+const METHODS_PER_CLIENT: usize = 64;
+
+impl RateLimit {
+    /// Lets every client make `rate` calls a second on average, and up to `burst` calls at once.
+    pub fn new(rate: f64, burst: u32) -> RateLimit {
+        RateLimit {
+            rate,
+            burst: f64::from(burst.max(1)),
+            per_method: false,
+            fault: None,
+            buckets: Mutex::new(Buckets {
+                clients: HashMap::new(),
+                prune_at: PRUNE_AT,
+            }),
+        }
+    }
+
+    /// Limits the calls of every client to each method on their own, when enabled.
+    pub fn per_method(mut self, enabled: bool) -> Self {
+        self.per_method = enabled;
+        self
+    }
+
+    /// Answers calls over the limit with the fault, instead of status 429.
+    pub fn fault(mut self, fault: Fault) -> Self {
+        self.fault = Some(fault);
+        self
+    }
+
+    /// Takes a token for the call, returning how many seconds are left until one is there if
+    /// none is.
+    fn take(&self, request: &Request) -> Result<(), f64> {
+        let method = if self.per_method {
+            Some(request.call().name.clone())
+        } else {
+            None
+        };
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.clients.len() >= buckets.prune_at {
+            // Full buckets are no different from new ones, so they need not be kept.
+            let (rate, burst) = (self.rate, self.burst);
+            buckets.clients.retain(|_, client| {
+                client.retain(|_, bucket| bucket.refilled(now, rate, burst) < burst);
+                !client.is_empty()
+            });
+            buckets.prune_at = (buckets.clients.len() * 2).max(PRUNE_AT);
+        }
+        let client = buckets
+            .clients
+            .entry(request.remote_addr().ip())
+            .or_default();
+        let method = match method {
+            Some(_) if client.len() >= METHODS_PER_CLIENT && !client.contains_key(&method) => None,
+            method => method,
+        };
+        let bucket = client.entry(method).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        bucket.tokens = bucket.refilled(now, self.rate, self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err((1.0 - bucket.tokens) / self.rate)
+        }
+    }
+}
+
+impl Bucket {
+    fn refilled(&self, now: Instant, rate: f64, burst: f64) -> f64 {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+}
+
+impl Middleware for RateLimit {
+    fn on_request(&self, request: &mut Request) -> Option<Reply> {
+        let wait = self.take(request).err()?;
+        Some(match self.fault {
+            Some(ref fault) => Reply::new(Err(fault.clone())),
+            None => {
+                let wait = if wait.is_finite() { wait.ceil() } else { 1.0 };
+                Reply::reject(429).with_header("Retry-After", format!("{}", wait.max(1.0)))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Call;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    fn request(addr: &str, method: &str) -> Request {
+        let call = Call {
+            name: method.into(),
+            params: vec![],
+        };
+        let addr = SocketAddr::new(addr.parse().unwrap(), 4000);
+        Request::new("/".into(), vec![], addr, call, Instant::now())
+    }
+
+    fn bucket_count(limit: &RateLimit) -> usize {
+        let buckets = limit.buckets.lock().unwrap();
+        buckets.clients.values().map(HashMap::len).sum()
+    }
+
+    #[test]
+    fn refills_buckets_up_to_the_burst() {
+        let now = Instant::now();
+        let bucket = Bucket {
+            tokens: 0.5,
+            updated: now,
+        };
+        assert_eq!(bucket.refilled(now, 2.0, 3.0), 0.5);
+        assert_eq!(
+            bucket.refilled(now + Duration::from_millis(500), 2.0, 3.0),
+            1.5
+        );
+        assert_eq!(
+            bucket.refilled(now + Duration::from_secs(10), 2.0, 3.0),
+            3.0
+        );
+    }
+
+    #[test]
+    fn lets_bursts_through_per_client() {
+        let limit = RateLimit::new(0.001, 2);
+        let call = request("10.0.0.1", "add");
+        assert_eq!(limit.take(&call), Ok(()));
+        assert_eq!(limit.take(&call), Ok(()));
+        let wait = limit.take(&call).unwrap_err();
+        assert!(wait > 990.0 && wait <= 1000.0, "{}", wait);
+        assert!(limit.take(&request("10.0.0.1", "sub")).is_err());
+        assert_eq!(limit.take(&request("10.0.0.2", "add")), Ok(()));
+    }
+
+    #[test]
+    fn rejects_with_retry_after() {
+        let limit = RateLimit::new(0.5, 1);
+        let mut call = request("10.0.0.1", "add");
+        assert!(limit.on_request(&mut call).is_none());
+        let reply = limit.on_request(&mut call).unwrap();
+        assert_eq!(reply.status(), 429);
+        let header = ("Retry-After".to_owned(), "2".to_owned());
+        assert_eq!(reply.headers(), &[header][..]);
+        let limit = RateLimit::new(0.5, 1).fault(Fault::new(429, "Slow down"));
+        assert!(limit.on_request(&mut call).is_none());
+        let reply = limit.on_request(&mut call).unwrap();
+        assert_eq!(reply.response(), Some(&Err(Fault::new(429, "Slow down"))));
+    }
+
+    #[test]
+    fn limits_methods_on_their_own() {
+        let limit = RateLimit::new(0.001, 1).per_method(true);
+        assert_eq!(limit.take(&request("10.0.0.1", "add")), Ok(()));
+        assert!(limit.take(&request("10.0.0.1", "add")).is_err());
+        assert_eq!(limit.take(&request("10.0.0.1", "sub")), Ok(()));
+    }
+
+    #[test]
+    fn caps_the_methods_of_a_client() {
+        let limit = RateLimit::new(0.001, 1).per_method(true);
+        for n in 0..METHODS_PER_CLIENT {
+            assert_eq!(limit.take(&request("10.0.0.1", &n.to_string())), Ok(()));
+        }
+        assert_eq!(limit.take(&request("10.0.0.1", "made.up")), Ok(()));
+        assert!(limit.take(&request("10.0.0.1", "made.up.too")).is_err());
+        assert_eq!(bucket_count(&limit), METHODS_PER_CLIENT + 1);
+        assert!(limit.take(&request("10.0.0.1", "0")).is_err());
+    }
+
+    #[test]
+    fn prunes_full_buckets() {
+        let limit = RateLimit::new(1e9, 1);
+        for n in 0..PRUNE_AT {
+            let addr = format!("10.0.{}.{}", n / 256, n % 256);
+            assert_eq!(limit.take(&request(&addr, "add")), Ok(()));
+        }
+        assert_eq!(bucket_count(&limit), PRUNE_AT);
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(limit.take(&request("10.1.0.1", "add")), Ok(()));
+        assert_eq!(bucket_count(&limit), 1);
+    }
+}
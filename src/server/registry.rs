@@ -0,0 +1,530 @@
+use super::introspection::MethodInfo;
+use super::{on_decode_fail, on_encode_fail, Handler, RequestContext};
+use crate::xmlfmt::{
+    error, from_params, into_params_with, Extensions, Fault, Params, Response, Value,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
+
+#[cfg(feature = "async")]
+use super::async_server::Handler as AsyncHandlerBox;
+#[cfg(feature = "async")]
+use super::{AsyncHandler, BoxFuture};
+#[cfg(feature = "async")]
+use std::future::Future;
+
+/// Makes the handler for the method named by the rest of the name, past the namespace.
+type Dispatcher<H> = Arc<dyn Fn(&str) -> H + Send + Sync>;
+
+/// Lists the names of the methods of a server.
+pub(super) type Lister = Arc<dyn Fn() -> Vec<String> + Send + Sync>;
+
+/// Encodes the results of typed handlers with the extensions set on the server, which may
+/// change after the handlers are registered.
+#[derive(Clone, Default)]
+struct Encoder(Arc<RwLock<Extensions>>);
+
+impl Encoder {
+    fn encode<T: Serialize>(&self, value: &T) -> error::Result<Params> {
+        let extensions = *self
+            .0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        into_params_with(value, extensions)
+    }
+
+    fn set(&self, extensions: Extensions) {
+        *self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = extensions;
+    }
+}
+
+/// The handlers and descriptions of the methods of a server.
+pub(super) struct Methods<H> {
+    pub(super) handlers: HashMap<String, H>,
+    pub(super) info: HashMap<String, MethodInfo>,
+    dispatchers: HashMap<String, Dispatcher<H>>,
+}
+
+impl<H: Clone> Methods<H> {
+    /// Returns the handler of the method, or else that of the dispatcher of the innermost
+    /// namespace holding it.
+    pub(super) fn handler(&self, name: &str) -> Option<H> {
+        if let Some(handler) = self.handlers.get(name) {
+            return Some(handler.clone());
+        }
+        self.dispatchers
+            .iter()
+            .filter(|(prefix, _)| name.len() > prefix.len() && name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, dispatcher)| dispatcher(&name[prefix.len()..]))
+    }
+}
+
+/// The methods behind a lock, shared by the server and the registries handed out, along with
+/// the namespace of the registry, as in `math.`.
+struct Shared<H> {
+    methods: Arc<RwLock<Methods<H>>>,
+    extensions: Encoder,
+    prefix: String,
+}
+
+impl<H> Clone for Shared<H> {
+    fn clone(&self) -> Self {
+        Shared {
+            methods: self.methods.clone(),
+            extensions: self.extensions.clone(),
+            prefix: self.prefix.clone(),
+        }
+    }
+}
+
+impl<H> Default for Shared<H> {
+    fn default() -> Self {
+        Shared {
+            methods: Arc::new(RwLock::new(Methods {
+                handlers: HashMap::new(),
+                info: HashMap::new(),
+                dispatchers: HashMap::new(),
+            })),
+            extensions: Encoder::default(),
+            prefix: String::new(),
+        }
+    }
+}
+
+impl<H: Clone> Shared<H> {
+    fn read(&self) -> RwLockReadGuard<'_, Methods<H>> {
+        self.methods
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Lists the names of all methods, without keeping them alive, for handlers among them.
+    fn lister(&self) -> Lister
+    where
+        H: Send + Sync + 'static,
+    {
+        let methods: Weak<RwLock<Methods<H>>> = Arc::downgrade(&self.methods);
+        Arc::new(move || match methods.upgrade() {
+            Some(methods) => methods
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .handlers
+                .keys()
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Methods<H>> {
+        self.methods
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn namespace(&self, prefix: &str) -> Shared<H> {
+        Shared {
+            methods: self.methods.clone(),
+            extensions: self.extensions.clone(),
+            prefix: format!("{}{}.", self.prefix, prefix.trim_end_matches('.')),
+        }
+    }
+
+    /// The namespace without its trailing dot, as handed to dispatchers.
+    fn namespace_name(&self) -> String {
+        self.prefix.trim_end_matches('.').to_owned()
+    }
+
+    fn name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn insert(&self, name: &str, handler: H) {
+        let name = self.name(name);
+        self.write().handlers.insert(name, handler);
+    }
+
+    fn describe(&self, name: &str, info: MethodInfo) {
+        let name = self.name(name);
+        self.write().info.insert(name, info);
+    }
+
+    fn alias(&self, alias: &str, target: &str) -> bool {
+        let (alias, target) = (self.name(alias), self.name(target));
+        let mut methods = self.write();
+        let handler = match methods.handlers.get(&target) {
+            Some(handler) => handler.clone(),
+            None => return false,
+        };
+        if let Some(info) = methods.info.get(&target).cloned() {
+            methods.info.insert(alias.clone(), info);
+        }
+        methods.handlers.insert(alias, handler);
+        true
+    }
+
+    fn set_dispatcher(&self, dispatcher: Dispatcher<H>) {
+        let prefix = self.prefix.clone();
+        self.write().dispatchers.insert(prefix, dispatcher);
+    }
+
+    fn remove_dispatcher(&self) -> bool {
+        self.write().dispatchers.remove(&self.prefix).is_some()
+    }
+
+    fn unregister(&self, name: &str) -> bool {
+        let name = self.name(name);
+        let mut methods = self.write();
+        methods.info.remove(&name);
+        methods.handlers.remove(&name).is_some()
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.read().handlers.contains_key(&self.name(name))
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.read()
+            .handlers
+            .keys()
+            .filter_map(|name| name.strip_prefix(self.prefix.as_str()))
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+}
+
+/// The methods of a server, which may be registered, replaced and removed while it runs, as by
+/// applications loading plugins.
+///
+/// `Server::registry` hands out the registry of a server, whose clones all share its methods.
+/// Registering a name again replaces its handler, and calls already running finish with the
+/// handler they started with.
+///
+/// A registry for a namespace, as made by `namespace`, puts it in front of the names it is
+/// given, so that `registry.namespace("math").register_simple("add", add)` registers
+/// `math.add`.
+#[derive(Clone, Default)]
+pub struct Registry {
+    shared: Shared<Handler>,
+}
+
+impl Registry {
+    pub fn register_value<K, T>(&self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        self.register_value_with_context(name, move |_: &RequestContext, params| handler(params));
+    }
+
+    pub fn register_value_with_context<K, T>(&self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(&RequestContext, Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        self.shared.insert(&name.into(), Arc::new(handler));
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, Tef, Tdf>(
+        &self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        let encoder = self.shared.extensions.clone();
+        self.register_value(name, move |req| {
+            let params = match from_params(req) {
+                Ok(v) => v,
+                Err(err) => return decode_fail(&err),
+            };
+            let response = handler(params)?;
+            encoder.encode(&response).or_else(|v| encode_fail(&v))
+        });
+    }
+
+    pub fn register_simple<K, Treq, Tres, Thandler>(&self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+    {
+        self.register(name, handler, on_encode_fail, on_decode_fail);
+    }
+
+    pub fn register_simple_with_context<K, Treq, Tres, Thandler>(&self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler:
+            Fn(&RequestContext, Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+    {
+        let encoder = self.shared.extensions.clone();
+        self.register_value_with_context(name, move |context, req| {
+            let params = match from_params(req) {
+                Ok(v) => v,
+                Err(err) => return on_decode_fail(&err),
+            };
+            let response = handler(context, params)?;
+            encoder.encode(&response).or_else(|v| on_encode_fail(&v))
+        });
+    }
+
+    pub fn describe<K: Into<String>>(&self, name: K, info: MethodInfo) {
+        self.shared.describe(&name.into(), info);
+    }
+
+    /// Returns a registry for the namespace within that of this registry.
+    pub fn namespace(&self, prefix: &str) -> Registry {
+        Registry {
+            shared: self.shared.namespace(prefix),
+        }
+    }
+
+    /// Registers the method under another name too, with the handler and description it has
+    /// now, telling whether it is registered.
+    pub fn alias(&self, alias: &str, name: &str) -> bool {
+        self.shared.alias(alias, name)
+    }
+
+    /// Answers calls to methods of the namespace without a handler of their own, given the
+    /// namespace and the rest of the name, as `math` and `add` for `math.add`.
+    ///
+    /// The dispatcher of the innermost namespace holding the method is used, nested ones
+    /// included, while those of the registry of the server itself answer every method.
+    pub fn register_dispatcher<T>(&self, dispatcher: T)
+    where
+        T: Fn(&str, &str, Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        let dispatcher = Arc::new(dispatcher);
+        let prefix = self.shared.namespace_name();
+        self.shared.set_dispatcher(Arc::new(move |rest: &str| {
+            let (dispatcher, prefix, rest) = (dispatcher.clone(), prefix.clone(), rest.to_owned());
+            Arc::new(move |_: &RequestContext, params| dispatcher(&prefix, &rest, params))
+        }));
+    }
+
+    /// Removes the dispatcher of the namespace, telling whether there was one.
+    pub fn remove_dispatcher(&self) -> bool {
+        self.shared.remove_dispatcher()
+    }
+
+    /// Removes the method along with its description, telling whether it was registered.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.shared.unregister(name)
+    }
+
+    /// Encodes the results of typed handlers with the extensions, for all namespaces, including
+    /// those of handlers registered already.
+    pub fn set_extensions(&self, extensions: Extensions) {
+        self.shared.extensions.set(extensions);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.shared.contains(name)
+    }
+
+    /// Lists the names of the registered methods, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.shared.names()
+    }
+
+    pub(super) fn read(&self) -> RwLockReadGuard<'_, Methods<Handler>> {
+        self.shared.read()
+    }
+
+    pub(super) fn lister(&self) -> Lister {
+        self.shared.lister()
+    }
+}
+
+/// The methods of an async server, changed while it runs as those of a `Registry` are.
+#[cfg(feature = "async")]
+#[derive(Clone, Default)]
+pub struct AsyncRegistry {
+    shared: Shared<AsyncHandlerBox>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncRegistry {
+    pub fn register_value<K, T, F>(&self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.register_handler(name, handler);
+    }
+
+    pub fn register_handler<K, H>(&self, name: K, handler: H)
+    where
+        K: Into<String>,
+        H: AsyncHandler + 'static,
+    {
+        self.shared.insert(
+            &name.into(),
+            Arc::new(move |_: RequestContext, params| handler.call(params)),
+        );
+    }
+
+    pub fn register_value_with_context<K, T, F>(&self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(RequestContext, Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        self.shared.insert(
+            &name.into(),
+            Arc::new(move |context, params| Box::pin(handler(context, params)) as BoxFuture<_>),
+        );
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, F, Tef, Tdf>(
+        &self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        // The response is encoded once the handler is done, after the closure has returned.
+        let encode_fail = Arc::new(encode_fail);
+        let encoder = self.shared.extensions.clone();
+        self.register_value(name, move |req| {
+            let call = from_params(req)
+                .map(&handler)
+                .map_err(|err| decode_fail(&err));
+            let (encode_fail, encoder) = (encode_fail.clone(), encoder.clone());
+            async move {
+                let response = match call {
+                    Ok(future) => future.await?,
+                    Err(response) => return response,
+                };
+                encoder.encode(&response).or_else(|v| encode_fail(&v))
+            }
+        });
+    }
+
+    pub fn register_simple<K, Treq, Tres, Thandler, F>(&self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+    {
+        self.register(name, handler, on_encode_fail, on_decode_fail);
+    }
+
+    pub fn register_simple_with_context<K, Treq, Tres, Thandler, F>(
+        &self,
+        name: K,
+        handler: Thandler,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(RequestContext, Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+    {
+        let encoder = self.shared.extensions.clone();
+        self.register_value_with_context(name, move |context, req| {
+            let call = from_params(req)
+                .map(|params| handler(context, params))
+                .map_err(|err| on_decode_fail(&err));
+            let encoder = encoder.clone();
+            async move {
+                let response = match call {
+                    Ok(future) => future.await?,
+                    Err(response) => return response,
+                };
+                encoder.encode(&response).or_else(|v| on_encode_fail(&v))
+            }
+        });
+    }
+
+    pub fn describe<K: Into<String>>(&self, name: K, info: MethodInfo) {
+        self.shared.describe(&name.into(), info);
+    }
+
+    pub fn namespace(&self, prefix: &str) -> AsyncRegistry {
+        AsyncRegistry {
+            shared: self.shared.namespace(prefix),
+        }
+    }
+
+    pub fn alias(&self, alias: &str, name: &str) -> bool {
+        self.shared.alias(alias, name)
+    }
+
+    /// Answers calls to methods of the namespace without a handler of their own, as
+    /// `Registry::register_dispatcher` does.
+    pub fn register_dispatcher<T, F>(&self, dispatcher: T)
+    where
+        T: Fn(&str, &str, Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        let dispatcher = Arc::new(dispatcher);
+        let prefix = self.shared.namespace_name();
+        self.shared.set_dispatcher(Arc::new(move |rest: &str| {
+            let (dispatcher, prefix, rest) = (dispatcher.clone(), prefix.clone(), rest.to_owned());
+            Arc::new(move |_: RequestContext, params| {
+                Box::pin(dispatcher(&prefix, &rest, params)) as BoxFuture<_>
+            })
+        }));
+    }
+
+    pub fn remove_dispatcher(&self) -> bool {
+        self.shared.remove_dispatcher()
+    }
+
+    /// Removes the method along with its description, telling whether it was registered.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.shared.unregister(name)
+    }
+
+    /// Encodes the results of typed handlers with the extensions, for all namespaces, including
+    /// those of handlers registered already.
+    pub fn set_extensions(&self, extensions: Extensions) {
+        self.shared.extensions.set(extensions);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.shared.contains(name)
+    }
+
+    /// Lists the names of the registered methods, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.shared.names()
+    }
+
+    pub(super) fn read(&self) -> RwLockReadGuard<'_, Methods<AsyncHandlerBox>> {
+        self.shared.read()
+    }
+
+    pub(super) fn lister(&self) -> Lister {
+        self.shared.lister()
+    }
+}
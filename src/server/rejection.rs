@@ -0,0 +1,97 @@
+/// The response to a request that is not an XML-RPC call: a status, with an HTML page to explain
+/// it to people opening the server in a browser, if one is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rejection {
+    status: u16,
+    html: Option<String>,
+}
+
+impl Rejection {
+    pub fn new(status: u16) -> Rejection {
+        Rejection { status, html: None }
+    }
+
+    /// Sends the page along, as `text/html`.
+    pub fn with_html<T: Into<String>>(mut self, html: T) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn html(&self) -> Option<&str> {
+        self.html.as_deref()
+    }
+}
+
+/// How a server answers requests that are not XML-RPC calls.
+///
+/// By default the method and `Content-Type` of requests are not looked at, and bodies that fail
+/// to parse, as those of GET requests, are rejected with status 400. Requests over the size
+/// limits are always rejected with status 413.
+#[derive(Clone, Debug)]
+pub struct Rejections {
+    method: Option<Rejection>,
+    content_type: Option<Rejection>,
+    malformed: Rejection,
+}
+
+impl Default for Rejections {
+    fn default() -> Self {
+        Rejections {
+            method: None,
+            content_type: None,
+            malformed: Rejection::new(400),
+        }
+    }
+}
+
+impl Rejections {
+    pub fn new() -> Rejections {
+        Rejections::default()
+    }
+
+    /// Rejects requests using a method other than POST, such as `Rejection::new(405)`, which is
+    /// sent with an `Allow` header.
+    pub fn wrong_method(mut self, rejection: Rejection) -> Self {
+        self.method = Some(rejection);
+        self
+    }
+
+    /// Rejects requests whose `Content-Type` is missing or other than `text/xml` or
+    /// `application/xml`, such as `Rejection::new(415)`.
+    pub fn wrong_content_type(mut self, rejection: Rejection) -> Self {
+        self.content_type = Some(rejection);
+        self
+    }
+
+    /// Rejects requests whose body is not an XML-RPC call as told, instead of with status 400.
+    pub fn malformed(mut self, rejection: Rejection) -> Self {
+        self.malformed = rejection;
+        self
+    }
+
+    /// The rejection of a request with the method and content type, if it is rejected before its
+    /// body is read.
+    pub(super) fn check(&self, method: &str, content_type: Option<&str>) -> Option<&Rejection> {
+        if method != "POST" {
+            if let Some(ref rejection) = self.method {
+                return Some(rejection);
+            }
+        }
+        let xml = content_type
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .is_some_and(|value| value == "text/xml" || value == "application/xml");
+        match self.content_type {
+            Some(ref rejection) if !xml => Some(rejection),
+            _ => None,
+        }
+    }
+
+    pub(super) fn malformed_body(&self) -> &Rejection {
+        &self.malformed
+    }
+}
@@ -0,0 +1,68 @@
+use super::async_server::HttpResponse;
+use super::{AsyncServer, BoxFuture};
+use hyper1::body::Body;
+use hyper1::Request;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// An async server as a `tower::Service`, answering HTTP requests within an existing hyper or
+/// tower stack rather than on a listener of its own.
+///
+/// Web frameworks built on tower take it as a route of their own, as axum does with
+/// `Router::route_service("/RPC2", server.into_service())`, so an application can serve
+/// XML-RPC next to its other endpoints, on the same listener. The service is `Clone`, `Send`
+/// and `Sync`, and never fails, answering errors with HTTP statuses instead.
+///
+/// There are no adapters of frameworks beyond this. axum needs none, and frameworks not built
+/// on tower, such as actix-web, have to forward requests to the service themselves.
+///
+/// The address of the client, which middleware and the address filter go by, is the one given
+/// to `with_remote_addr`, or else a `SocketAddr` put in the extensions of the request by layers
+/// in front, or else `0.0.0.0:0`.
+#[derive(Clone)]
+pub struct AsyncService {
+    server: Arc<AsyncServer>,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl AsyncService {
+    pub(super) fn new(server: AsyncServer) -> AsyncService {
+        AsyncService {
+            server: Arc::new(server),
+            remote_addr: None,
+        }
+    }
+
+    /// Answers requests as coming from the address, as for a service made for a connection.
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.remote_addr = Some(remote_addr);
+        self
+    }
+}
+
+impl<B> Service<Request<B>> for AsyncService
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = HttpResponse;
+    type Error = Infallible;
+    type Future = BoxFuture<Result<HttpResponse, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let server = self.server.clone();
+        let remote_addr = self
+            .remote_addr
+            .or_else(|| request.extensions().get::<SocketAddr>().copied())
+            .unwrap_or_else(|| SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)));
+        Box::pin(async move { Ok(server.handle_outer(request, remote_addr).await) })
+    }
+}
@@ -0,0 +1,132 @@
+use super::Server;
+use crate::xmlfmt::{error, Fault, Response, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use super::AsyncServer;
+#[cfg(feature = "async")]
+use std::future::Future;
+
+/// Registers handlers on a server that receive shared state along with their parameters, such
+/// as a database pool, instead of each capturing it in its closure.
+pub struct WithState<'a, S> {
+    server: &'a mut Server,
+    state: Arc<S>,
+}
+
+impl<'a, S: Send + Sync + 'static> WithState<'a, S> {
+    pub(super) fn new(server: &'a mut Server, state: Arc<S>) -> WithState<'a, S> {
+        WithState { server, state }
+    }
+
+    pub fn register_value<K, T>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(&S, Vec<Value>) -> Response + Send + Sync + 'static,
+    {
+        let state = self.state.clone();
+        self.server
+            .register_value(name, move |params| handler(&state, params));
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, Tef, Tdf>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(&S, Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        let state = self.state.clone();
+        self.server.register(
+            name,
+            move |params| handler(&state, params),
+            encode_fail,
+            decode_fail,
+        );
+    }
+
+    pub fn register_simple<K, Treq, Tres, Thandler>(&mut self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(&S, Treq) -> std::result::Result<Tres, Fault> + Send + Sync + 'static,
+    {
+        let state = self.state.clone();
+        self.server
+            .register_simple(name, move |params| handler(&state, params));
+    }
+}
+
+/// Registers handlers on an async server that receive shared state, like `WithState`.
+///
+/// Handlers get the state as an `Arc`, which their futures can hold on to.
+#[cfg(feature = "async")]
+pub struct AsyncWithState<'a, S> {
+    server: &'a mut AsyncServer,
+    state: Arc<S>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, S: Send + Sync + 'static> AsyncWithState<'a, S> {
+    pub(super) fn new(server: &'a mut AsyncServer, state: Arc<S>) -> AsyncWithState<'a, S> {
+        AsyncWithState { server, state }
+    }
+
+    pub fn register_value<K, T, F>(&mut self, name: K, handler: T)
+    where
+        K: Into<String>,
+        T: Fn(Arc<S>, Vec<Value>) -> F + Send + Sync + 'static,
+        F: Future<Output = Response> + Send + 'static,
+    {
+        let state = self.state.clone();
+        self.server
+            .register_value(name, move |params| handler(state.clone(), params));
+    }
+
+    pub fn register<K, Treq, Tres, Thandler, F, Tef, Tdf>(
+        &mut self,
+        name: K,
+        handler: Thandler,
+        encode_fail: Tef,
+        decode_fail: Tdf,
+    ) where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Arc<S>, Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+        Tef: Fn(&error::Error) -> Response + Send + Sync + 'static,
+        Tdf: Fn(&error::Error) -> Response + Send + Sync + 'static,
+    {
+        let state = self.state.clone();
+        self.server.register(
+            name,
+            move |params| handler(state.clone(), params),
+            encode_fail,
+            decode_fail,
+        );
+    }
+
+    pub fn register_simple<K, Treq, Tres, Thandler, F>(&mut self, name: K, handler: Thandler)
+    where
+        K: Into<String>,
+        Treq: DeserializeOwned,
+        Tres: Serialize,
+        Thandler: Fn(Arc<S>, Treq) -> F + Send + Sync + 'static,
+        F: Future<Output = std::result::Result<Tres, Fault>> + Send + 'static,
+    {
+        let state = self.state.clone();
+        self.server
+            .register_simple(name, move |params| handler(state.clone(), params));
+    }
+}
@@ -0,0 +1,166 @@
+use super::{RequestContext, Server};
+use crate::error::Result;
+use crate::xmlfmt::value::ToXml;
+use crate::xmlfmt::{from_params, into_params, parse, Call, Fault, Params, Response};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+
+#[cfg(feature = "async")]
+use super::AsyncServer;
+
+/// Calls the methods of a server in-process, without sockets, for unit testing handlers.
+///
+/// Calls and responses are turned into XML and parsed back as over HTTP, so handlers get the
+/// parameters a client would send them. Middleware and the limits on requests are left out.
+pub struct TestClient<'a> {
+    server: &'a Server,
+    parts: Parts,
+}
+
+/// What handlers see of the request, taken from the client by default.
+#[derive(Clone)]
+struct Parts {
+    remote_addr: SocketAddr,
+    path: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Default for Parts {
+    fn default() -> Self {
+        Parts {
+            remote_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+            path: "/".into(),
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl Parts {
+    fn context(&self) -> RequestContext {
+        RequestContext::from_parts(self.remote_addr, self.path.clone(), self.headers.clone())
+    }
+}
+
+impl<'a> TestClient<'a> {
+    pub(super) fn new(server: &'a Server) -> TestClient<'a> {
+        TestClient {
+            server,
+            parts: Parts::default(),
+        }
+    }
+
+    /// Makes the calls as coming from the address, `127.0.0.1:0` by default.
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.parts.remote_addr = remote_addr;
+        self
+    }
+
+    /// Makes the calls to the URL path, `/` by default, answered by the server mounted there if
+    /// any.
+    pub fn with_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.parts.path = path.into();
+        self
+    }
+
+    /// Adds a header to the requests the handlers see in their context.
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.parts.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn call_value<Tkey>(&self, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        let call = reparse_call(name.into(), params)?;
+        let server = self
+            .server
+            .mounts
+            .get(&self.parts.path)
+            .unwrap_or(self.server);
+        reparse_response(server.handle(call, &self.parts.context()))
+    }
+
+    pub fn call<'b, Tkey, Treq, Tres>(
+        &self,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'b>,
+    {
+        match self.call_value(name, into_params(&req)?) {
+            Ok(Ok(v)) => from_params(v).map(Ok).map_err(Into::into),
+            Ok(Err(v)) => Ok(Err(v)),
+            Err(v) => Err(v),
+        }
+    }
+}
+
+/// Calls the methods of an async server in-process, as `TestClient` does.
+#[cfg(feature = "async")]
+pub struct AsyncTestClient<'a> {
+    server: &'a AsyncServer,
+    parts: Parts,
+}
+
+#[cfg(feature = "async")]
+impl<'a> AsyncTestClient<'a> {
+    pub(super) fn new(server: &'a AsyncServer) -> AsyncTestClient<'a> {
+        AsyncTestClient {
+            server,
+            parts: Parts::default(),
+        }
+    }
+
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.parts.remote_addr = remote_addr;
+        self
+    }
+
+    pub fn with_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.parts.path = path.into();
+        self
+    }
+
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.parts.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub async fn call_value<Tkey>(&self, name: Tkey, params: Params) -> Result<Response>
+    where
+        Tkey: Into<String>,
+    {
+        let call = reparse_call(name.into(), params)?;
+        let server = self.server.mounted(&self.parts.path);
+        reparse_response(server.handle(call, &self.parts.context()).await)
+    }
+
+    pub async fn call<'b, Tkey, Treq, Tres>(
+        &self,
+        name: Tkey,
+        req: Treq,
+    ) -> Result<std::result::Result<Tres, Fault>>
+    where
+        Tkey: Into<String>,
+        Treq: Serialize,
+        Tres: Deserialize<'b>,
+    {
+        match self.call_value(name, into_params(&req)?).await {
+            Ok(Ok(v)) => from_params(v).map(Ok).map_err(Into::into),
+            Ok(Err(v)) => Ok(Err(v)),
+            Err(v) => Err(v),
+        }
+    }
+}
+
+fn reparse_call(name: String, params: Params) -> Result<Call> {
+    Ok(parse::call(Call { name, params }.to_xml().as_bytes())?)
+}
+
+fn reparse_response(response: Response) -> Result<Response> {
+    Ok(parse::response(response.to_xml().as_bytes())?)
+}
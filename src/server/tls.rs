@@ -0,0 +1,67 @@
+use crate::error::{Result, ResultExt};
+use rustls::crypto;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The certificate and private key an `AsyncServer` presents when serving HTTPS.
+#[derive(Clone)]
+pub struct TlsConfig {
+    config: Arc<ServerConfig>,
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsConfig").finish()
+    }
+}
+
+impl TlsConfig {
+    /// Reads the PEM encoded server certificate, followed by any intermediate certificates, and
+    /// private key.
+    pub fn from_pem(certificates: &[u8], key: &[u8]) -> Result<TlsConfig> {
+        let chain = CertificateDer::pem_slice_iter(certificates)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .chain_err(|| "Failed to parse PEM server certificate.")?;
+        if chain.is_empty() {
+            bail!("No certificates found in PEM server certificate.");
+        }
+        let key =
+            PrivateKeyDer::from_pem_slice(key).chain_err(|| "Failed to parse PEM private key.")?;
+        let mut config =
+            ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .chain_err(|| "Failed to set up TLS protocol versions.")?
+                .with_no_client_auth()
+                .with_single_cert(chain, key)
+                .chain_err(|| "Failed to set up the TLS server certificate.")?;
+        // The server only speaks HTTP/1.1.
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsConfig {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Reads the certificates and private key from PEM files, as issued by most certificate
+    /// authorities.
+    pub fn from_pem_files<C, K>(certificates: C, key: K) -> Result<TlsConfig>
+    where
+        C: AsRef<Path>,
+        K: AsRef<Path>,
+    {
+        let certificates = certificates.as_ref();
+        let key = key.as_ref();
+        let certificates = fs::read(certificates)
+            .chain_err(|| format!("Failed to read {}.", certificates.display()))?;
+        let key = fs::read(key).chain_err(|| format!("Failed to read {}.", key.display()))?;
+        TlsConfig::from_pem(&certificates, &key)
+    }
+
+    pub(super) fn acceptor(&self) -> tokio_rustls::TlsAcceptor {
+        tokio_rustls::TlsAcceptor::from(self.config.clone())
+    }
+}
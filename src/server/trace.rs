@@ -0,0 +1,217 @@
+use crate::xmlfmt::Response;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+
+/// The span of a request, with the `tracing` feature, carrying the method called, the address
+/// of the client and the ID of the request, and the events of its phases, each with the
+/// microseconds since the request was received. Without the feature it does nothing.
+pub(super) struct Trace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    received: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl Trace {
+    pub(super) fn new(peer: SocketAddr, received: Instant) -> Trace {
+        let span = tracing::info_span!(
+            "xmlrpc.request",
+            peer = %peer,
+            method = tracing::field::Empty,
+            request_id = tracing::field::Empty,
+        );
+        Trace { span, received }
+    }
+
+    fn elapsed(&self) -> u64 {
+        self.received.elapsed().as_micros() as u64
+    }
+
+    /// Runs the answering of the request within the span.
+    pub(super) fn in_scope<T>(&self, answer: impl FnOnce() -> T) -> T {
+        self.span.in_scope(answer)
+    }
+
+    #[cfg(feature = "async")]
+    pub(super) fn instrument<F: Future>(&self, answer: F) -> impl Future<Output = F::Output> {
+        tracing::Instrument::instrument(answer, self.span.clone())
+    }
+
+    pub(super) fn parsed(&self, method: &str) {
+        self.span.record("method", method);
+        tracing::debug!(parent: &self.span, elapsed_us = self.elapsed(), "parsed call");
+    }
+
+    /// Tells of the request being answered, with a response or having been rejected.
+    pub(super) fn answered(&self, status: u16) {
+        tracing::debug!(parent: &self.span, elapsed_us = self.elapsed(), status, "answered request");
+    }
+
+    pub(super) fn dispatching(&self, request_id: &str) {
+        self.span.record("request_id", request_id);
+    }
+
+    pub(super) fn dispatched(&self, response: &Response) {
+        match *response {
+            Ok(_) => {
+                tracing::debug!(parent: &self.span, elapsed_us = self.elapsed(), "dispatched call")
+            }
+            Err(ref fault) => tracing::debug!(
+                parent: &self.span,
+                elapsed_us = self.elapsed(),
+                fault = fault.code,
+                "dispatched call"
+            ),
+        }
+    }
+
+    pub(super) fn serialized(&self, size: usize) {
+        tracing::debug!(parent: &self.span, elapsed_us = self.elapsed(), size, "serialized response");
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl Trace {
+    pub(super) fn new(_peer: SocketAddr, _received: Instant) -> Trace {
+        Trace {}
+    }
+
+    pub(super) fn in_scope<T>(&self, answer: impl FnOnce() -> T) -> T {
+        answer()
+    }
+
+    #[cfg(feature = "async")]
+    pub(super) fn instrument<F: Future>(&self, answer: F) -> F {
+        answer
+    }
+
+    pub(super) fn parsed(&self, _method: &str) {}
+
+    pub(super) fn answered(&self, _status: u16) {}
+
+    pub(super) fn dispatching(&self, _request_id: &str) {}
+
+    pub(super) fn dispatched(&self, _response: &Response) {}
+
+    pub(super) fn serialized(&self, _size: usize) {}
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Fault;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// The fields of a span or event, as debug-formatted.
+    #[derive(Default)]
+    struct Fields(Vec<(String, String)>);
+
+    impl Fields {
+        fn get(&self, name: &str) -> Option<&str> {
+            self.0
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push((field.name().into(), format!("{:?}", value)));
+        }
+    }
+
+    #[derive(Default)]
+    struct Log {
+        span: Fields,
+        events: Vec<(Option<u64>, Fields)>,
+        entered: usize,
+    }
+
+    /// Records the one span made and the events within it.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Log>>);
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            assert_eq!(span.metadata().name(), "xmlrpc.request");
+            span.record(&mut self.0.lock().unwrap().span);
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.0.lock().unwrap().span);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let parent = event.parent().map(Id::into_u64);
+            self.0.lock().unwrap().events.push((parent, fields));
+        }
+
+        fn enter(&self, _: &Id) {
+            self.0.lock().unwrap().entered += 1;
+        }
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn traces_the_phases_of_requests() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let trace = Trace::new("127.0.0.1:4000".parse().unwrap(), Instant::now());
+            trace.in_scope(|| {
+                trace.parsed("ping");
+                trace.dispatching("req-1");
+                trace.dispatched(&Err(Fault::new(3, "Failed")));
+                trace.serialized(120);
+            });
+            trace.answered(200);
+        });
+
+        let log = recorder.0.lock().unwrap();
+        assert_eq!(log.span.get("peer"), Some("127.0.0.1:4000"));
+        assert_eq!(log.span.get("method"), Some("\"ping\""));
+        assert_eq!(log.span.get("request_id"), Some("\"req-1\""));
+        assert_eq!(log.entered, 1);
+
+        let messages: Vec<_> = log
+            .events
+            .iter()
+            .map(|(_, fields)| fields.get("message").unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "parsed call",
+                "dispatched call",
+                "serialized response",
+                "answered request"
+            ]
+        );
+        for (parent, fields) in &log.events {
+            assert_eq!(*parent, Some(1));
+            assert!(fields.get("elapsed_us").is_some());
+        }
+        assert_eq!(log.events[1].1.get("fault"), Some("3"));
+        assert_eq!(log.events[2].1.get("size"), Some("120"));
+        assert_eq!(log.events[3].1.get("status"), Some("200"));
+    }
+}
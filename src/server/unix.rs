@@ -0,0 +1,213 @@
+use super::{Overload, Server};
+use crate::error::{ErrorKind, Result};
+use std::fs;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+/// The permissions and ownership given to a Unix socket once it is bound, deciding which local
+/// users may connect to it.
+#[derive(Clone, Debug, Default)]
+pub struct UnixOptions {
+    mode: Option<u32>,
+    owner: Option<u32>,
+    group: Option<u32>,
+}
+
+impl UnixOptions {
+    pub fn new() -> UnixOptions {
+        UnixOptions::default()
+    }
+
+    /// Sets the permission bits of the socket, such as `0o660` to let in its owner and group.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the user owning the socket, by ID.
+    pub fn owner(mut self, uid: u32) -> Self {
+        self.owner = Some(uid);
+        self
+    }
+
+    /// Sets the group owning the socket, by ID.
+    pub fn group(mut self, gid: u32) -> Self {
+        self.group = Some(gid);
+        self
+    }
+
+    pub(super) fn apply(&self, path: &Path) -> Result<()> {
+        if let Some(mode) = self.mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))
+                .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        }
+        if self.owner.is_some() || self.group.is_some() {
+            std::os::unix::fs::chown(path, self.owner, self.group)
+                .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes a socket left at the path by an earlier run, so the path can be bound again. Other
+/// kinds of files are left alone, and make binding fail.
+pub(super) fn clear(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            fs::remove_file(path).map_err(|err| ErrorKind::BindFail(err.to_string()).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The address middleware and the access log see for calls over a Unix socket, which have none.
+pub(super) fn peer() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 0))
+}
+
+/// A server bound to a Unix socket, as `BoundServer` is to a TCP port.
+pub struct BoundUnixServer {
+    listener: tiny_http::Server,
+    path: PathBuf,
+    server: Arc<Server>,
+}
+
+impl BoundUnixServer {
+    pub(super) fn new(server: Server, path: &Path, options: UnixOptions) -> Result<Self> {
+        clear(path)?;
+        let listener = tiny_http::Server::http_unix(path)
+            .map_err(|err| ErrorKind::BindFail(err.to_string()))?;
+        options.apply(path)?;
+        Ok(BoundUnixServer {
+            listener,
+            path: path.to_owned(),
+            server: Arc::new(server),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Serves requests, each in a thread of its own, or with `Overload::Queue` in as many
+    /// threads as calls may run at once.
+    pub fn run(self) {
+        let BoundUnixServer {
+            listener, server, ..
+        } = self;
+        let listener = Arc::new(listener);
+        let workers = match server.concurrency {
+            Some((limit, Overload::Queue)) => limit,
+            _ => 0,
+        };
+        if workers == 0 {
+            while let Ok(request) = listener.recv() {
+                let server = server.clone();
+                thread::spawn(move || answer(&server, request));
+            }
+            return;
+        }
+        let threads = (0..workers)
+            .map(|_| {
+                let listener = listener.clone();
+                let server = server.clone();
+                thread::spawn(move || {
+                    while let Ok(request) = listener.recv() {
+                        answer(&server, request);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Answers the request as the TCP server would, going through the same `rouille::Request`.
+fn answer(server: &Server, mut request: tiny_http::Request) {
+    let headers = request
+        .headers()
+        .iter()
+        .map(|header| (header.field.to_string(), header.value.to_string()))
+        .collect();
+    // Reading one byte past the limit lets the server tell the body is too large, and a broken
+    // body fails to parse, so read errors need no answer of their own.
+    let mut body = Vec::new();
+    let limit = server.pipeline.max_request_size.saturating_add(1) as u64;
+    let _ = request.as_reader().take(limit).read_to_end(&mut body);
+    let inner = rouille::Request::fake_http_from(
+        peer(),
+        request.method().as_str(),
+        request.url(),
+        headers,
+        body,
+    );
+    let response = server.handle_outer(&inner);
+
+    let (data, size) = response.data.into_reader_and_size();
+    let mut reply = tiny_http::Response::empty(response.status_code).with_data(data, size);
+    for (name, value) in response.headers {
+        if name.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+        if let Ok(header) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+            reply.add_header(header);
+        }
+    }
+    // Broken connections only concern the client on the other end.
+    let _ = request.respond(reply);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmlfmt::Value;
+    use crate::Client;
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("xml-rpc-{}-{}.sock", name, std::process::id()))
+    }
+
+    fn echo_server() -> Server {
+        let mut server = Server::new();
+        server.register_value("echo", Ok);
+        server
+    }
+
+    #[test]
+    fn answers_calls_over_the_socket() {
+        let path = socket_path("round-trip");
+        let bound = echo_server()
+            .bind_unix(&path, UnixOptions::new().mode(0o600))
+            .unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        thread::spawn(move || bound.run());
+
+        let client = Client::connect_uds(&path).unwrap();
+        let url = "http://localhost/RPC2".parse().unwrap();
+        let params = vec![Value::String("over the socket".into())];
+        let response = client.call_value(&url, "echo", params.clone()).unwrap();
+        assert_eq!(response, Ok(params));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn replaces_only_sockets_left_behind() {
+        let path = socket_path("stale");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let bound = echo_server().bind_unix(&path, UnixOptions::new()).unwrap();
+        drop(bound);
+        let _ = fs::remove_file(&path);
+
+        fs::write(&path, b"not a socket").unwrap();
+        assert!(echo_server().bind_unix(&path, UnixOptions::new()).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"not a socket");
+        fs::remove_file(&path).unwrap();
+    }
+}
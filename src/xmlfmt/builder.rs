@@ -0,0 +1,55 @@
+use super::{Members, Value};
+
+/// Builds a struct value member by member, for code that cannot use `xmlrpc_value!`, as in
+/// `Value::struct_builder().field("a", 1).field("b", "x").build()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructBuilder {
+    members: Members,
+}
+
+impl StructBuilder {
+    pub fn new() -> StructBuilder {
+        StructBuilder::default()
+    }
+
+    /// Adds the member, replacing any of the same name.
+    pub fn field<K: Into<String>, V: Into<Value>>(mut self, name: K, value: V) -> Self {
+        self.members.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> Value {
+        Value::Struct(self.members)
+    }
+}
+
+/// Builds an array value item by item, as `StructBuilder` does structs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArrayBuilder {
+    items: Vec<Value>,
+}
+
+impl ArrayBuilder {
+    pub fn new() -> ArrayBuilder {
+        ArrayBuilder::default()
+    }
+
+    pub fn push<V: Into<Value>>(mut self, value: V) -> Self {
+        self.items.push(value.into());
+        self
+    }
+
+    pub fn build(self) -> Value {
+        Value::Array(self.items)
+    }
+}
+
+impl Value {
+    pub fn struct_builder() -> StructBuilder {
+        StructBuilder::new()
+    }
+
+    pub fn array_builder() -> ArrayBuilder {
+        ArrayBuilder::new()
+    }
+}
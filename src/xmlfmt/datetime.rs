@@ -0,0 +1,166 @@
+use super::error::{Error, Result};
+use super::Value;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// The time of a `<dateTime.iso8601>`, as read from the text of `Value::DateTime`.
+///
+/// The specification leaves the format open, so besides its `19980717T14:08:55`, the extended
+/// format with dashes, times without colons or with a space before them, fractions of a second,
+/// offsets and plain dates are read too.
+///
+/// `Value::DateTime` keeps the text with the feature on as well. Features are additive, and any
+/// crate in a build may enable this one, so it must not change the type that others match on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateTime {
+    /// A time without a zone, as the specification has it.
+    Naive(NaiveDateTime),
+    /// A time with an offset, as in `1998-07-17T14:08:55+02:00` or `19980717T12:08:55Z`.
+    Offset(chrono::DateTime<FixedOffset>),
+}
+
+const TIME_FORMATS: [&str; 5] = [
+    "%Y%m%dT%H:%M:%S%.f",
+    "%Y%m%dT%H%M%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H%M%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+];
+
+const DATE_FORMATS: [&str; 2] = ["%Y%m%d", "%Y-%m-%d"];
+
+impl FromStr for DateTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DateTime> {
+        let s = s.trim();
+        let (time, offset) = split_offset(s);
+        let time = TIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+            .or_else(|| {
+                DATE_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDate::parse_from_str(time, format).ok())
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+            });
+        let time = match time {
+            Some(time) => time,
+            None => bail!("Invalid dateTime.iso8601 value: {}", s),
+        };
+        let offset = match offset {
+            Some(offset) => offset,
+            None => return Ok(DateTime::Naive(time)),
+        };
+        match parse_offset(offset).and_then(|offset| time.and_local_timezone(offset).single()) {
+            Some(time) => Ok(DateTime::Offset(time)),
+            None => bail!("Invalid offset in dateTime.iso8601 value: {}", s),
+        }
+    }
+}
+
+/// Splits the offset off the end of the time, where there is one.
+fn split_offset(s: &str) -> (&str, Option<&str>) {
+    if s.ends_with('Z') || s.ends_with('z') {
+        return (&s[..s.len() - 1], Some("Z"));
+    }
+    // Dashes before the time separate the parts of the date instead.
+    let time = match s.find(['T', 't', ' ']) {
+        Some(index) => index,
+        None => return (s, None),
+    };
+    match s[time..].rfind(['+', '-']) {
+        Some(index) => {
+            let (time, offset) = s.split_at(time + index);
+            (time, Some(offset))
+        }
+        None => (s, None),
+    }
+}
+
+/// Reads offsets such as `+02:00`, `-0530` and `+01`.
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    if offset == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let sign = match offset.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Writes the time in the format of the specification, with the fraction of a second and the
+/// offset only where there are any.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DateTime::Naive(ref time) => write!(f, "{}", time.format("%Y%m%dT%H:%M:%S%.f")),
+            DateTime::Offset(ref time) => write!(f, "{}", time.format("%Y%m%dT%H:%M:%S%.f%:z")),
+        }
+    }
+}
+
+/// Writes the time as `Display` does.
+impl Serialize for DateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Reads the time in any of the formats `FromStr` takes.
+impl<'de> Deserialize<'de> for DateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl From<NaiveDateTime> for DateTime {
+    fn from(time: NaiveDateTime) -> DateTime {
+        DateTime::Naive(time)
+    }
+}
+
+impl From<chrono::DateTime<FixedOffset>> for DateTime {
+    fn from(time: chrono::DateTime<FixedOffset>) -> DateTime {
+        DateTime::Offset(time)
+    }
+}
+
+/// Writes the time as `Display` does.
+impl From<DateTime> for Value {
+    fn from(time: DateTime) -> Value {
+        Value::DateTime(time.to_string())
+    }
+}
+
+impl Value {
+    /// Reads the time of a `<dateTime.iso8601>`, if it is in one of the formats `DateTime`
+    /// takes.
+    pub fn as_datetime(&self) -> Option<DateTime> {
+        match *self {
+            Value::DateTime(ref v) => v.parse().ok(),
+            _ => None,
+        }
+    }
+}
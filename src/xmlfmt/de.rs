@@ -1,11 +1,12 @@
 use super::error::{Error, Result};
-use super::Value;
+use super::value::VALUE_NAME;
+use super::{Members, Value};
 use serde::de::{
     DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
 };
 use serde::{self, Deserializer};
 use std;
-use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::vec;
 
 impl<'de> serde::Deserializer<'de> for Value {
@@ -18,6 +19,7 @@ impl<'de> serde::Deserializer<'de> for Value {
     {
         match self {
             Value::Int(v) => visitor.visit_i32(v),
+            Value::Int64(v) => visitor.visit_i64(v),
             Value::Bool(v) => visitor.visit_bool(v),
             Value::DateTime(v) | Value::String(v) => visitor.visit_string(v),
             Value::Double(v) => visitor.visit_f64(v),
@@ -50,6 +52,7 @@ impl<'de> serde::Deserializer<'de> for Value {
                     ))
                 }
             }
+            Value::Nil => visitor.visit_unit(),
         }
     }
 
@@ -232,20 +235,23 @@ impl<'de> serde::Deserializer<'de> for Value {
     where
         V: Visitor<'de>,
     {
-        if let Value::Array(mut v) = self {
-            let v1 = v.pop();
-            if !v.is_empty() {
-                return Err(serde::de::Error::invalid_value(
-                    Unexpected::Seq,
-                    &"array with a single element",
-                ));
-            }
-            match v1 {
-                Some(x) => visitor.visit_some(x),
-                None => visitor.visit_none(),
+        // Options are arrays of no value or one, unless encoded with the nil extension.
+        match self {
+            Value::Array(mut v) => {
+                let v1 = v.pop();
+                if !v.is_empty() {
+                    return Err(serde::de::Error::invalid_value(
+                        Unexpected::Seq,
+                        &"array with a single element",
+                    ));
+                }
+                match v1 {
+                    Some(x) => visitor.visit_some(x),
+                    None => visitor.visit_none(),
+                }
             }
-        } else {
-            Err(serde::de::Error::invalid_value(self.unexpected(), &visitor))
+            Value::Nil => visitor.visit_none(),
+            value => visitor.visit_some(value),
         }
     }
 
@@ -253,6 +259,9 @@ impl<'de> serde::Deserializer<'de> for Value {
     where
         V: Visitor<'de>,
     {
+        if let Value::Nil = self {
+            return visitor.visit_unit();
+        }
         if let Value::Struct(v) = self {
             if !v.is_empty() {
                 return Err(serde::de::Error::invalid_value(
@@ -287,7 +296,12 @@ impl<'de> serde::Deserializer<'de> for Value {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        // Sequences of bytes, as in `Vec<u8>`, are read from base64 too.
+        match self {
+            Value::Base64(v) => Value::Array(v.into_iter().map(|b| Value::Int(b.into())).collect())
+                .deserialize_any(visitor),
+            value => value.deserialize_any(visitor),
+        }
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
@@ -330,13 +344,32 @@ impl<'de> serde::Deserializer<'de> for Value {
 
     fn deserialize_enum<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // Values are read as themselves, by the variant of their type.
+        if name == VALUE_NAME {
+            let (variant, value) = match self {
+                Value::Int(_) => ("int", self),
+                Value::Int64(_) => ("int64", self),
+                Value::Bool(_) => ("bool", self),
+                Value::String(_) => ("string", self),
+                Value::Double(_) => ("double", self),
+                Value::DateTime(v) => ("dateTime", Value::String(v)),
+                Value::Base64(_) => ("base64", self),
+                Value::Array(_) => ("array", self),
+                Value::Struct(_) => ("struct", self),
+                Value::Nil => ("nil", Value::Struct(Members::new())),
+            };
+            return visitor.visit_enum(EnumDeserializer {
+                variant: variant.to_owned(),
+                value,
+            });
+        }
         match self {
             Value::Struct(members) => {
                 let mut member_iter = members.into_iter();
@@ -386,7 +419,7 @@ impl<'de> serde::Deserializer<'de> for SeqDeserializer {
         V: Visitor<'de>,
     {
         let len = self.iter.len();
-        let ret = try!(visitor.visit_seq(&mut self));
+        let ret = visitor.visit_seq(&mut self)?;
         let remaining = self.iter.len();
         if remaining == 0 {
             Ok(ret)
@@ -427,12 +460,12 @@ impl<'de> SeqAccess<'de> for SeqDeserializer {
 }
 
 struct MapDeserializer {
-    iter: <HashMap<String, Value> as IntoIterator>::IntoIter,
+    iter: <Members as IntoIterator>::IntoIter,
     value: Option<Value>,
 }
 
 impl MapDeserializer {
-    fn new(map: HashMap<String, Value>) -> Self {
+    fn new(map: Members) -> Self {
         MapDeserializer {
             iter: map.into_iter(),
             value: None,
@@ -581,11 +614,13 @@ impl FromI32 for i64 {
 
 fn handle_integer<'de, T, V>(value: Value, visitor: &V) -> Result<T>
 where
-    T: FromI32 + std::str::FromStr,
+    T: FromI32 + TryFrom<i64> + std::str::FromStr,
     V: Visitor<'de>,
 {
     match value {
         Value::Int(v) => Ok(T::from_i32(v)),
+        Value::Int64(v) => T::try_from(v)
+            .map_err(|_| serde::de::Error::invalid_value(Unexpected::Signed(v), visitor)),
         Value::String(v) => v
             .parse()
             .map_err(|_| serde::de::Error::invalid_value(Unexpected::Str(&v), visitor)),
@@ -0,0 +1,92 @@
+use super::Value;
+use std::fmt;
+
+/// A place where two values differ, at a path as `Value::get` takes, with the value on either
+/// side, or none where a member or item is missing from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+}
+
+/// Writes the path and both sides, as in `result.items[2]: 3 != 4`.
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "."
+        } else {
+            &self.path
+        };
+        write!(f, "{}: ", path)?;
+        write_side(f, &self.left)?;
+        f.write_str(" != ")?;
+        write_side(f, &self.right)
+    }
+}
+
+fn write_side(f: &mut fmt::Formatter, side: &Option<Value>) -> fmt::Result {
+    match *side {
+        Some(ref value) => write!(f, "{}", value),
+        None => f.write_str("missing"),
+    }
+}
+
+/// Lists where the two values differ, descending into structs member by member and arrays
+/// item by item, and otherwise comparing values whole, so that values of different types
+/// differ at their own path.
+///
+/// Struct members come in the order of the left struct followed by those only on the right.
+/// Equal values have no differences.
+pub fn diff(left: &Value, right: &Value) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    compare(&mut differences, String::new(), left, right);
+    differences
+}
+
+fn compare(differences: &mut Vec<Difference>, path: String, left: &Value, right: &Value) {
+    match (left, right) {
+        (Value::Struct(lefts), Value::Struct(rights)) => {
+            let names: Vec<&String> = lefts
+                .keys()
+                .chain(rights.keys().filter(|name| !lefts.contains_key(*name)))
+                .collect();
+            for name in names {
+                let path = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", path, name)
+                };
+                compare_sides(differences, path, lefts.get(name), rights.get(name));
+            }
+        }
+        (Value::Array(lefts), Value::Array(rights)) => {
+            for index in 0..lefts.len().max(rights.len()) {
+                let path = format!("{}[{}]", path, index);
+                compare_sides(differences, path, lefts.get(index), rights.get(index));
+            }
+        }
+        _ if left == right => {}
+        _ => differences.push(Difference {
+            path,
+            left: Some(left.clone()),
+            right: Some(right.clone()),
+        }),
+    }
+}
+
+fn compare_sides(
+    differences: &mut Vec<Difference>,
+    path: String,
+    left: Option<&Value>,
+    right: Option<&Value>,
+) {
+    match (left, right) {
+        (Some(left), Some(right)) => compare(differences, path, left, right),
+        (left, right) => differences.push(Difference {
+            path,
+            left: left.cloned(),
+            right: right.cloned(),
+        }),
+    }
+}
@@ -0,0 +1,52 @@
+use std::convert::TryFrom;
+
+/// The character encoding of the XML documents written out, such as the bodies of calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, which all XML parsers read.
+    #[default]
+    Utf8,
+    /// ISO-8859-1, also known as Latin-1, for legacy servers reading no other encoding.
+    ///
+    /// Characters outside of it are written as character references.
+    Latin1,
+}
+
+impl Encoding {
+    /// The name of the encoding in XML declarations and HTTP headers.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// Encodes the XML document, which UTF-8 leaves as it is. Other encodings are named in the
+    /// XML declaration, replacing any that the document starts with.
+    pub fn encode(self, xml: &str) -> Vec<u8> {
+        match self {
+            Encoding::Utf8 => xml.as_bytes().to_vec(),
+            Encoding::Latin1 => {
+                let xml = declare(xml, self.name());
+                let mut bytes = Vec::with_capacity(xml.len());
+                for c in xml.chars() {
+                    match u8::try_from(u32::from(c)) {
+                        Ok(byte) => bytes.push(byte),
+                        Err(_) => {
+                            bytes.extend_from_slice(format!("&#{};", u32::from(c)).as_bytes())
+                        }
+                    }
+                }
+                bytes
+            }
+        }
+    }
+}
+
+fn declare(xml: &str, encoding: &str) -> String {
+    let declaration = format!("<?xml version=\"1.0\" encoding=\"{}\"?>", encoding);
+    let rest = xml
+        .strip_prefix("<?xml ")
+        .and_then(|rest| rest.find("?>").map(|end| &rest[end + 2..]));
+    declaration + rest.unwrap_or(xml)
+}
@@ -20,6 +20,10 @@ error_chain! {
             description("Given structure is not supported")
             display("Given structure is not supported: {}", t)
         }
+        ValueTooLarge(limit: usize) {
+            description("Value exceeds the size limit")
+            display("Value exceeds the size limit of {} bytes", limit)
+        }
     }
 }
 
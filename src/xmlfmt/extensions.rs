@@ -0,0 +1,27 @@
+/// The extensions to XML-RPC that Rust values are encoded with, which the peer has to
+/// understand to read them.
+///
+/// None are used by default, keeping to the specification. Servers tell which ones they
+/// understand in their `system.getCapabilities` response, as `Capabilities::extensions` reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Extensions {
+    /// Encodes `None` as `<nil/>`, and `Some` as the value it holds, as Python's `xmlrpc` with
+    /// `allow_none` does, instead of as an array of no value or one.
+    pub nil: bool,
+    /// Encodes integers outside the range of `<i4>` as `<i8>`, instead of as strings.
+    pub i8: bool,
+    /// Fails to encode integers outside the range of the types used, instead of writing them
+    /// as strings, for peers keeping to the specification.
+    pub strict: bool,
+}
+
+impl Extensions {
+    /// Every extension, without the strict mode.
+    pub fn all() -> Extensions {
+        Extensions {
+            nil: true,
+            i8: true,
+            strict: false,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Conversions between values and JSON, for gateways between JSON and XML-RPC peers.
+//!
+//! JSON has no types of its own for `dateTime.iso8601` and `base64`, so these become strings,
+//! of the time in the XML-RPC format and of the bytes in standard base64, and come back as
+//! plain strings. Doubles that are not finite become `null`, and integers too large for an
+//! `i64` become doubles.
+
+use super::Value;
+use serde_json::{self, Map, Number};
+use std::convert::TryFrom;
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Value {
+        match value {
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(v) => Value::Bool(v),
+            serde_json::Value::Number(v) => match v.as_i64() {
+                Some(v) => match i32::try_from(v) {
+                    Ok(v) => Value::Int(v),
+                    Err(_) => Value::Int64(v),
+                },
+                None => Value::Double(v.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(v) => Value::String(v),
+            serde_json::Value::Array(v) => Value::Array(v.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(v) => Value::Struct(
+                v.into_iter()
+                    .map(|(name, value)| (name, Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> serde_json::Value {
+        match value {
+            Value::Int(v) => serde_json::Value::from(v),
+            Value::Int64(v) => serde_json::Value::from(v),
+            Value::Bool(v) => serde_json::Value::Bool(v),
+            Value::String(v) => serde_json::Value::String(v),
+            Value::Double(v) => Number::from_f64(v)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::DateTime(v) => serde_json::Value::String(v),
+            Value::Base64(v) => serde_json::Value::String(base64::encode(&v)),
+            Value::Nil => serde_json::Value::Null,
+            Value::Array(v) => {
+                serde_json::Value::Array(v.into_iter().map(serde_json::Value::from).collect())
+            }
+            Value::Struct(v) => serde_json::Value::Object(
+                v.into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::from(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/// Builds a `Value` from a literal of JSON-like syntax, with arrays in brackets, structs in
+/// braces with string keys, and `nil` for `Value::Nil`.
+///
+/// Any other expression is encoded with `into_value`, as the parameters of typed calls are,
+/// panicking if it cannot be.
+#[macro_export]
+macro_rules! xmlrpc_value {
+    (nil) => {
+        $crate::Value::Nil
+    };
+    ([ $($elements:tt)* ]) => {
+        $crate::Value::Array($crate::xmlrpc_value!(@array [] $($elements)*))
+    };
+    ({ $($members:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut members = $crate::Members::new();
+        $crate::xmlrpc_value!(@struct members $($members)*);
+        $crate::Value::Struct(members)
+    }};
+    ($other:expr) => {
+        $crate::into_value(&$other).expect("Failed to encode value.")
+    };
+
+    // Munches the elements of an array one at a time, collecting the values done so far.
+    (@array [$($done:expr,)*]) => {
+        vec![$($done,)*]
+    };
+    (@array [$($done:expr,)*] nil $(, $($rest:tt)*)?) => {
+        $crate::xmlrpc_value!(@array [$($done,)* $crate::Value::Nil,] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::xmlrpc_value!(@array [$($done,)* $crate::xmlrpc_value!([$($inner)*]),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::xmlrpc_value!(@array [$($done,)* $crate::xmlrpc_value!({$($inner)*}),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::xmlrpc_value!(@array [$($done,)* $crate::xmlrpc_value!($next),] $($rest)*)
+    };
+    (@array [$($done:expr,)*] $last:expr) => {
+        $crate::xmlrpc_value!(@array [$($done,)* $crate::xmlrpc_value!($last),])
+    };
+
+    // Munches the members of a struct one at a time, inserting them into the map.
+    (@struct $members:ident) => {};
+    (@struct $members:ident $key:literal : nil $(, $($rest:tt)*)?) => {
+        $members.insert(::std::string::String::from($key), $crate::Value::Nil);
+        $crate::xmlrpc_value!(@struct $members $($($rest)*)?);
+    };
+    (@struct $members:ident $key:literal : [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $members.insert(::std::string::String::from($key), $crate::xmlrpc_value!([$($inner)*]));
+        $crate::xmlrpc_value!(@struct $members $($($rest)*)?);
+    };
+    (@struct $members:ident $key:literal : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $members.insert(::std::string::String::from($key), $crate::xmlrpc_value!({$($inner)*}));
+        $crate::xmlrpc_value!(@struct $members $($($rest)*)?);
+    };
+    (@struct $members:ident $key:literal : $value:expr, $($rest:tt)*) => {
+        $members.insert(::std::string::String::from($key), $crate::xmlrpc_value!($value));
+        $crate::xmlrpc_value!(@struct $members $($rest)*);
+    };
+    (@struct $members:ident $key:literal : $value:expr) => {
+        $members.insert(::std::string::String::from($key), $crate::xmlrpc_value!($value));
+    };
+}
@@ -1,14 +1,35 @@
 use serde::{Deserialize, Serialize};
 
+mod builder;
+#[cfg(feature = "chrono")]
+mod datetime;
 mod de;
+mod diff;
+mod encoding;
 pub mod error;
+mod extensions;
+#[cfg(feature = "json")]
+mod json;
+#[macro_use]
+mod macros;
+mod options;
 pub mod parse;
+mod path;
+mod pretty;
 mod ser;
 #[cfg(test)]
 mod tests;
 pub mod value;
 
-pub use self::value::{Call, Fault, Params, Response, Value};
+pub use self::builder::{ArrayBuilder, StructBuilder};
+#[cfg(feature = "chrono")]
+pub use self::datetime::DateTime;
+pub use self::encoding::Encoding;
+pub use self::extensions::Extensions;
+pub use self::options::ParseOptions;
+pub use self::path::Patch;
+pub use self::pretty::ToPretty;
+pub use self::value::{Call, Fault, Members, Params, Response, Value};
 
 pub fn from_params<'a, T: Deserialize<'a>>(mut params: Params) -> error::Result<T> {
     use self::error::ResultExt;
@@ -22,7 +43,17 @@ pub fn from_params<'a, T: Deserialize<'a>>(mut params: Params) -> error::Result<
 }
 
 pub fn into_params<T: Serialize>(v: &T) -> error::Result<Params> {
-    Ok(match v.serialize(ser::Serializer {})? {
+    into_params_with(v, Extensions::default())
+}
+
+/// Encodes the value into a single value, even if it is a tuple or sequence.
+pub fn into_value<T: Serialize>(v: &T) -> error::Result<Value> {
+    v.serialize(ser::Serializer::default())
+}
+
+/// Encodes the value into parameters like `into_params`, using the extensions.
+pub fn into_params_with<T: Serialize>(v: &T, extensions: Extensions) -> error::Result<Params> {
+    Ok(match v.serialize(ser::Serializer::new(extensions))? {
         Value::Array(params) => params,
         data => vec![data],
     })
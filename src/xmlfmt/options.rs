@@ -0,0 +1,50 @@
+/// How closely the calls and responses read have to keep to the specification.
+///
+/// The default reads what it can of the deviations seen from other implementations, while
+/// `strict` fails on all of them, for validating peers. Their fields can be set on their own.
+///
+/// Values without a type are no deviation, but can be refused with `untyped_values`, for peers
+/// expected to type all of theirs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reads values without a type element, and empty ones like `<value/>`, as strings, as the
+    /// specification has it, rather than failing on them.
+    pub untyped_values: bool,
+    /// Skips elements the specification has no place for, such as extra children of calls,
+    /// parameters and members, rather than failing on them.
+    pub unknown_elements: bool,
+    /// Reads responses without a `<params>` element as having no parameters, as calls without
+    /// one are.
+    pub missing_params: bool,
+    /// Reads arrays without a `<data>` element, as in `<array/>`, as empty.
+    pub missing_data: bool,
+    /// Reads booleans written as `true` and `false`, in any case, and numbers other than 0 and
+    /// 1, as true unless they are 0.
+    pub loose_booleans: bool,
+}
+
+impl ParseOptions {
+    /// Fails on every deviation from the specification, still reading values without a type as
+    /// the strings it has them be.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            untyped_values: true,
+            unknown_elements: false,
+            missing_params: false,
+            missing_data: false,
+            loose_booleans: false,
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            untyped_values: true,
+            unknown_elements: true,
+            missing_params: true,
+            missing_data: true,
+            loose_booleans: true,
+        }
+    }
+}
@@ -1,237 +1,446 @@
-use super::error::{Result, ResultExt};
-use super::{Call, Fault, Response, Value};
+use super::error::{ErrorKind, Result, ResultExt};
+use super::{Call, Fault, Members, ParseOptions, Response, Value};
 use base64;
-use regex::Regex;
-use serde_xml_rs::deserialize;
 use std;
-use std::collections::HashMap;
-
-fn wrap_in_string(content: String) -> String {
-    lazy_static! {
-        static ref RE1: Regex = Regex::new(r"<value\s*/>").unwrap();
-        static ref RE2: Regex = Regex::new(r"<value\s*>\s*<string\s*/>\s*</value\s*>").unwrap();
-        static ref RE3: Regex = Regex::new(r"<value\s*>(?P<rest>[^<>]*)</value\s*>").unwrap();
-    }
-    RE3.replace_all(
-        &RE2.replace_all(
-            &RE1.replace_all(&content, "<value><string></string></value>"),
-            "<value><string></string></value>",
-        ),
-        "<value><string>$rest</string></value>",
-    )
-    .into()
-}
+use std::io::Read;
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
 
+/// Parses a single value, given as its typed element, or with the `<value>` around it.
 #[allow(dead_code)]
-pub fn xml<T: std::io::Read>(mut r: T) -> Result<Value> {
-    let mut content = String::new();
-    r.read_to_string(&mut content)
-        .chain_err(|| "Failed to read data source.")?;
-    let data: XmlValue = deserialize(std::io::Cursor::new(wrap_in_string(content)))
-        .chain_err(|| "Failed to parse XML-RPC data.")?;
-    data.into()
+pub fn xml<T: Read>(r: T) -> Result<Value> {
+    let mut reader = Reader::new(r, ParseOptions::default(), usize::MAX);
+    match reader.next()? {
+        XmlEvent::StartElement { name, .. } if name.local_name == "value" => reader.value(),
+        XmlEvent::StartElement { name, .. } => reader.typed(&name.local_name),
+        event => bail!("Expected a value, found {:?}.", event),
+    }
+    .chain_err(|| "Failed to parse XML-RPC data.")
 }
 
-pub fn call<T: std::io::Read>(mut r: T) -> Result<Call> {
-    let mut content = String::new();
-    r.read_to_string(&mut content)
-        .chain_err(|| "Failed to read data source.")?;
-    let data: XmlCall = deserialize(std::io::Cursor::new(wrap_in_string(content)))
-        .chain_err(|| "Failed to parse XML-RPC call.")?;
-    data.into()
+pub fn call<T: Read>(r: T) -> Result<Call> {
+    call_limited(r, usize::MAX)
 }
 
-pub fn response<T: std::io::Read>(mut r: T) -> Result<Response> {
-    let mut content = String::new();
-    r.read_to_string(&mut content)
-        .chain_err(|| "Failed to read data source.")?;
-    let data: XmlResponse = deserialize(std::io::Cursor::new(wrap_in_string(content)))
-        .chain_err(|| "Failed to parse XML-RPC response.")?;
-    data.into()
+/// Parses a call like `call`, but fails with `ErrorKind::ValueTooLarge` when a string or base64
+/// value holds more than `max_value` bytes, before any of them are decoded.
+///
+/// The call is parsed as it is read, so the reader may be a socket, and the data is never held
+/// in memory as a whole, only the values parsed from it.
+pub fn call_limited<T: Read>(r: T, max_value: usize) -> Result<Call> {
+    call_with(r, ParseOptions::default(), max_value)
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
-enum XmlValue {
-    #[serde(rename = "i4")]
-    I4(i32),
-    #[serde(rename = "int")]
-    Int(i32),
-    #[serde(rename = "boolean")]
-    Bool(i32),
-    #[serde(rename = "string")]
-    Str(String),
-    #[serde(rename = "double")]
-    Double(String),
-    #[serde(rename = "dateTime.iso8601")]
-    DateTime(String),
-    #[serde(rename = "base64")]
-    Base64(String),
-    #[serde(rename = "array")]
-    Array(XmlArray),
-    #[serde(rename = "struct")]
-    Struct(XmlStruct),
+/// Parses a call like `call_limited`, tolerating the deviations from the specification that
+/// the options allow.
+pub fn call_with<T: Read>(r: T, options: ParseOptions, max_value: usize) -> Result<Call> {
+    let call = Reader::new(r, options, max_value).call();
+    match call {
+        Err(ref err) if matches!(*err.kind(), ErrorKind::ValueTooLarge(_)) => call,
+        call => call.chain_err(|| "Failed to parse XML-RPC call."),
+    }
 }
 
-impl Into<Result<Value>> for XmlValue {
-    fn into(self) -> Result<Value> {
-        Ok(match self {
-            XmlValue::I4(v) | XmlValue::Int(v) => Value::Int(v),
-            XmlValue::Bool(v) => Value::Bool(v != 0),
-            XmlValue::Str(v) => Value::String(v),
-            XmlValue::Double(v) => Value::Double(v.parse().chain_err(|| "Failed to parse double")?),
-            XmlValue::DateTime(v) => Value::DateTime(v),
-            XmlValue::Base64(v) => {
-                Value::Base64(base64::decode(v.as_bytes()).chain_err(|| "Failed to parse base64")?)
-            }
-            XmlValue::Array(v) => {
-                let items: Result<Vec<Value>> = v.into();
-                Value::Array(items?)
-            }
-            XmlValue::Struct(v) => {
-                let items: Result<HashMap<String, Value>> = v.into();
-                Value::Struct(items?)
-            }
-        })
-    }
+pub fn response<T: Read>(r: T) -> Result<Response> {
+    response_with(r, ParseOptions::default())
+}
+
+/// Parses a response like `response`, tolerating the deviations from the specification that
+/// the options allow.
+pub fn response_with<T: Read>(r: T, options: ParseOptions) -> Result<Response> {
+    Reader::new(r, options, usize::MAX)
+        .response()
+        .chain_err(|| "Failed to parse XML-RPC response.")
+}
+
+/// Parses a response like `response`, but decodes the contents of all `<base64>` values into
+/// `sink` in document order, leaving them empty in the parsed response.
+///
+/// The decoded payload is written out in small chunks as the response is read, so at most the
+/// encoded text of one value is held in memory, rather than the decoded bytes of all of them.
+#[allow(dead_code)]
+pub fn response_base64_into<T: Read, W: std::io::Write>(r: T, sink: &mut W) -> Result<Response> {
+    response_base64_into_with(r, sink, ParseOptions::default())
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename = "methodCall")]
-struct XmlCall {
-    #[serde(rename = "methodName")]
-    pub name: String,
-    pub params: XmlParams,
+/// Parses a response like `response_base64_into`, tolerating the deviations from the
+/// specification that the options allow.
+pub fn response_base64_into_with<T: Read, W: std::io::Write>(
+    r: T,
+    sink: &mut W,
+    options: ParseOptions,
+) -> Result<Response> {
+    let mut reader = Reader::new(r, options, usize::MAX);
+    reader.sink = Some(sink);
+    reader
+        .response()
+        .chain_err(|| "Failed to parse XML-RPC response.")
 }
 
-impl Into<Result<Call>> for XmlCall {
-    fn into(self) -> Result<Call> {
-        let params: Result<Vec<Value>> = self.params.into();
+/// Reads a call or response element by element, building its values as they arrive.
+struct Reader<'s, R: Read> {
+    events: EventReader<R>,
+    options: ParseOptions,
+    max_value: usize,
+    /// Where base64 values are decoded to as they are read, instead of into the values.
+    sink: Option<&'s mut dyn std::io::Write>,
+}
+
+impl<'s, R: Read> Reader<'s, R> {
+    fn new(r: R, options: ParseOptions, max_value: usize) -> Reader<'s, R> {
+        let events = ParserConfig::new()
+            .coalesce_characters(false)
+            .create_reader(r);
+        Reader {
+            events,
+            options,
+            max_value,
+            sink: None,
+        }
+    }
+
+    fn call(&mut self) -> Result<Call> {
+        self.start("methodCall")?;
+        self.start("methodName")?;
+        let name = self.text(str::len)?;
+        let mut params = None;
+        while let Some(element) = self.element("call")? {
+            match element.as_str() {
+                "params" if params.is_none() => params = Some(self.params()?),
+                other => self.unknown(other, "call")?,
+            }
+        }
         Ok(Call {
-            name: self.name,
-            params: params?,
+            name,
+            params: params.unwrap_or_default(),
         })
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-enum XmlResponseResult {
-    #[serde(rename = "params")]
-    Success(XmlParams),
-    #[serde(rename = "fault")]
-    Failure { value: XmlValue },
-}
+    fn response(&mut self) -> Result<Response> {
+        self.start("methodResponse")?;
+        let mut response = None;
+        while let Some(element) = self.element("response")? {
+            match element.as_str() {
+                "params" if response.is_none() => response = Some(Ok(self.params()?)),
+                "fault" if response.is_none() => {
+                    self.start("value")?;
+                    let value = self.value()?;
+                    self.end()?;
+                    let fault = Fault::from_value(value)
+                        .chain_err(|| "Failed to decode fault structure")?;
+                    response = Some(Err(fault));
+                }
+                other => self.unknown(other, "response")?,
+            }
+        }
+        match response {
+            Some(response) => Ok(response),
+            None if self.options.missing_params => Ok(Ok(Vec::new())),
+            None => bail!("Response lacks parameters or a fault."),
+        }
+    }
 
-impl Into<Result<Response>> for XmlResponseResult {
-    fn into(self) -> Result<Response> {
-        match self {
-            XmlResponseResult::Success(params) => {
-                let params: Result<Vec<Value>> = params.into();
-                Ok(Ok(params?))
+    /// Parses the parameters, with their `<params>` element already opened.
+    fn params(&mut self) -> Result<Vec<Value>> {
+        let mut params = Vec::new();
+        while let Some(element) = self.element("params")? {
+            if element != "param" {
+                self.unknown(&element, "params")?;
+                continue;
+            }
+            let mut value = None;
+            while let Some(element) = self.element("param")? {
+                match element.as_str() {
+                    "value" if value.is_none() => value = Some(self.value()?),
+                    other => self.unknown(other, "param")?,
+                }
+            }
+            match value {
+                Some(value) => params.push(value),
+                None => bail!("Parameter lacks a value."),
             }
-            XmlResponseResult::Failure { value: v } => {
-                use serde::Deserialize;
+        }
+        Ok(params)
+    }
 
-                let val: Result<Value> = v.into();
+    /// Parses a value, with its `<value>` element already opened.
+    ///
+    /// Values without a type element are strings of all their text, whitespace included, while
+    /// whitespace around a type element is only there for layout.
+    fn value(&mut self) -> Result<Value> {
+        let mut text = String::new();
+        loop {
+            match self.raw()? {
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) | XmlEvent::CData(s) => {
+                    text.push_str(&s);
+                    check_limit(text.len(), self.max_value)?;
+                }
+                // Values without a type are strings.
+                XmlEvent::EndElement { .. } if self.options.untyped_values => {
+                    return Ok(Value::String(text))
+                }
+                XmlEvent::EndElement { .. } => bail!("Value lacks a type."),
+                XmlEvent::StartElement { name, .. } => {
+                    if !text.trim().is_empty() {
+                        bail!("Unexpected text before <{}>.", name.local_name);
+                    }
+                    let value = self.typed(&name.local_name)?;
+                    self.end_after(&name.local_name)?;
+                    return Ok(value);
+                }
+                event => bail!("Unexpected {:?} in value.", event),
+            }
+        }
+    }
 
-                Ok(Err(
-                    Fault::deserialize(val?).chain_err(|| "Failed to decode fault structure")?
-                ))
+    /// Reads up to the end of the value holding the typed element, with nothing but whitespace
+    /// after it.
+    fn end_after(&mut self, kind: &str) -> Result<()> {
+        loop {
+            match self.raw()? {
+                XmlEvent::Whitespace(_) => continue,
+                XmlEvent::Characters(s) | XmlEvent::CData(s) if s.trim().is_empty() => continue,
+                XmlEvent::Characters(_) | XmlEvent::CData(_) => {
+                    bail!("Unexpected text after <{}>.", kind)
+                }
+                XmlEvent::EndElement { .. } => return Ok(()),
+                event => bail!("Expected the end of the value, found {:?}.", event),
             }
         }
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-enum XmlResponse {
-    #[serde(rename = "methodResponse")]
-    Response(XmlResponseResult),
-}
+    fn typed(&mut self, kind: &str) -> Result<Value> {
+        Ok(match kind {
+            "i4" | "int" => Value::Int(
+                self.text(str::len)?
+                    .trim()
+                    .parse()
+                    .chain_err(|| "Failed to parse integer")?,
+            ),
+            "i8" => Value::Int64(
+                self.text(str::len)?
+                    .trim()
+                    .parse()
+                    .chain_err(|| "Failed to parse integer")?,
+            ),
+            "boolean" => Value::Bool(self.boolean()?),
+            "string" => Value::String(self.text(str::len)?),
+            "double" => Value::Double(
+                self.text(str::len)?
+                    .trim()
+                    .parse()
+                    .chain_err(|| "Failed to parse double")?,
+            ),
+            "dateTime.iso8601" => Value::DateTime(self.text(str::len)?),
+            "base64" if self.sink.is_some() => {
+                self.base64_into_sink()?;
+                Value::Base64(Vec::new())
+            }
+            // Every four characters of base64 decode to three bytes.
+            "base64" => {
+                let encoded = self.text(|v| v.len() / 4 * 3)?;
+                let encoded = encoded
+                    .bytes()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect::<Vec<_>>();
+                Value::Base64(base64::decode(&encoded).chain_err(|| "Failed to parse base64")?)
+            }
+            "array" => {
+                let mut items = Vec::new();
+                if self.child("data")? {
+                    while self.child("value")? {
+                        items.push(self.value()?);
+                    }
+                    self.end()?;
+                } else if !self.options.missing_data {
+                    bail!("Array lacks a <data> element.");
+                }
+                Value::Array(items)
+            }
+            "struct" => {
+                let mut members = Members::new();
+                while self.child("member")? {
+                    let (name, value) = self.member()?;
+                    members.insert(name, value);
+                }
+                Value::Struct(members)
+            }
+            "nil" => {
+                self.end()?;
+                Value::Nil
+            }
+            _ => bail!("Unknown value type <{}>.", kind),
+        })
+    }
 
-impl Into<Result<Response>> for XmlResponse {
-    fn into(self) -> Result<Response> {
-        match self {
-            XmlResponse::Response(v) => v.into(),
+    /// Parses a member of a struct, with its `<member>` element already opened.
+    fn member(&mut self) -> Result<(String, Value)> {
+        let (mut name, mut value) = (None, None);
+        while let Some(element) = self.element("member")? {
+            match element.as_str() {
+                "name" if name.is_none() => name = Some(self.text(str::len)?),
+                "value" if value.is_none() => value = Some(self.value()?),
+                other => self.unknown(other, "member")?,
+            }
+        }
+        match (name, value) {
+            (Some(name), Some(value)) => Ok((name, value)),
+            _ => bail!("Struct member lacks a name or value."),
         }
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlParams {
-    #[serde(rename = "param", default)]
-    pub params: Vec<XmlParamData>,
-}
+    /// Decodes the text of the base64 element just opened into the sink, a chunk at a time.
+    fn base64_into_sink(&mut self) -> Result<()> {
+        // A multiple of four characters, so that every chunk decodes on its own.
+        const CHUNK: usize = 16 * 1024;
+        let mut chunk = Vec::with_capacity(CHUNK);
+        loop {
+            let text = match self.raw()? {
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) | XmlEvent::CData(s) => s,
+                XmlEvent::EndElement { .. } => return self.write_base64(&chunk),
+                event => bail!("Unexpected {:?} in text.", event),
+            };
+            for byte in text.bytes().filter(|b| !b.is_ascii_whitespace()) {
+                chunk.push(byte);
+                if chunk.len() == CHUNK {
+                    self.write_base64(&chunk)?;
+                    chunk.clear();
+                }
+            }
+        }
+    }
 
-impl Into<Result<Vec<Value>>> for XmlParams {
-    fn into(self) -> Result<Vec<Value>> {
-        self.params
-            .into_iter()
-            .map(Into::<Result<Value>>::into)
-            .collect()
+    fn write_base64(&mut self, encoded: &[u8]) -> Result<()> {
+        let decoded = base64::decode(encoded).chain_err(|| "Failed to parse base64")?;
+        if let Some(ref mut sink) = self.sink {
+            sink.write_all(&decoded)
+                .chain_err(|| "Failed to write base64 data to sink.")?;
+        }
+        Ok(())
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlParamData {
-    pub value: XmlValue,
-}
+    /// Reads the text up to the end of the element, with `size` telling how large the text makes
+    /// the value, to be checked against the limit as it grows.
+    fn text(&mut self, size: fn(&str) -> usize) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            match self.raw()? {
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) | XmlEvent::CData(s) => {
+                    text.push_str(&s);
+                    check_limit(size(&text), self.max_value)?;
+                }
+                XmlEvent::EndElement { .. } => return Ok(text),
+                event => bail!("Unexpected {:?} in text.", event),
+            }
+        }
+    }
 
-impl Into<Result<Value>> for XmlParamData {
-    fn into(self) -> Result<Value> {
-        self.value.into()
+    /// Tells whether the next element is a child of the given name, or the end of the parent,
+    /// skipping other children if the options allow.
+    fn child(&mut self, expected: &str) -> Result<bool> {
+        loop {
+            match self.next()? {
+                XmlEvent::StartElement { ref name, .. } if name.local_name == expected => {
+                    return Ok(true)
+                }
+                XmlEvent::StartElement { .. } if self.options.unknown_elements => self.skip()?,
+                XmlEvent::EndElement { .. } => return Ok(false),
+                event => bail!("Expected <{}>, found {:?}.", expected, event),
+            }
+        }
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlArray {
-    #[serde(rename = "data")]
-    pub data: XmlArrayData,
-}
+    /// The name of the next child element of the parent, or none at its end.
+    fn element(&mut self, parent: &str) -> Result<Option<String>> {
+        match self.next()? {
+            XmlEvent::StartElement { name, .. } => Ok(Some(name.local_name)),
+            XmlEvent::EndElement { .. } => Ok(None),
+            event => bail!("Unexpected {:?} in {}.", event, parent),
+        }
+    }
 
-impl Into<Result<Vec<Value>>> for XmlArray {
-    fn into(self) -> Result<Vec<Value>> {
-        self.data.into()
+    /// Skips the element just opened if the options allow, or fails on it.
+    fn unknown(&mut self, element: &str, parent: &str) -> Result<()> {
+        if !self.options.unknown_elements {
+            bail!("Unexpected element <{}> in {}.", element, parent);
+        }
+        self.skip()
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlArrayData {
-    #[serde(default)]
-    pub value: Vec<XmlValue>,
-}
+    /// Skips past the end of the element just opened, with all it holds.
+    fn skip(&mut self) -> Result<()> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.raw()? {
+                XmlEvent::StartElement { .. } => depth += 1,
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => bail!("Unexpected end of the document."),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 
-impl Into<Result<Vec<Value>>> for XmlArrayData {
-    fn into(self) -> Result<Vec<Value>> {
-        self.value
-            .into_iter()
-            .map(Into::<Result<Value>>::into)
-            .collect()
+    /// Reads the text of a boolean, which is 0 or 1 unless the options allow more.
+    fn boolean(&mut self) -> Result<bool> {
+        let text = self.text(str::len)?;
+        let text = text.trim();
+        match text {
+            "0" => return Ok(false),
+            "1" => return Ok(true),
+            _ if !self.options.loose_booleans => bail!("Invalid boolean {}.", text),
+            _ => {}
+        }
+        if text.eq_ignore_ascii_case("true") {
+            return Ok(true);
+        }
+        if text.eq_ignore_ascii_case("false") {
+            return Ok(false);
+        }
+        text.parse::<i32>()
+            .map(|v| v != 0)
+            .chain_err(|| "Failed to parse boolean")
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlStruct {
-    #[serde(rename = "member", default)]
-    pub members: Vec<XmlStructItem>,
-}
+    fn start(&mut self, expected: &str) -> Result<()> {
+        match self.next()? {
+            XmlEvent::StartElement { ref name, .. } if name.local_name == expected => Ok(()),
+            event => bail!("Expected <{}>, found {:?}.", expected, event),
+        }
+    }
 
-impl Into<Result<HashMap<String, Value>>> for XmlStruct {
-    fn into(self) -> Result<HashMap<String, Value>> {
-        self.members
-            .into_iter()
-            .map(Into::<Result<(String, Value)>>::into)
-            .collect()
+    fn end(&mut self) -> Result<()> {
+        match self.next()? {
+            XmlEvent::EndElement { .. } => Ok(()),
+            event => bail!("Expected the end of an element, found {:?}.", event),
+        }
+    }
+
+    /// The next event, skipping whitespace between elements.
+    fn next(&mut self) -> Result<XmlEvent> {
+        loop {
+            match self.raw()? {
+                XmlEvent::Whitespace(_) => continue,
+                event => return Ok(event),
+            }
+        }
     }
-}
 
-#[derive(Debug, PartialEq, Deserialize)]
-struct XmlStructItem {
-    pub name: String,
-    pub value: XmlValue,
+    /// The next event of the document, skipping its declaration and comments.
+    fn raw(&mut self) -> Result<XmlEvent> {
+        loop {
+            match self.events.next().chain_err(|| "Malformed XML.")? {
+                XmlEvent::StartDocument { .. }
+                | XmlEvent::ProcessingInstruction { .. }
+                | XmlEvent::Comment(_) => continue,
+                event => return Ok(event),
+            }
+        }
+    }
 }
 
-impl Into<Result<(String, Value)>> for XmlStructItem {
-    fn into(self) -> Result<(String, Value)> {
-        let value: Result<Value> = self.value.into();
-        Ok((self.name, value?))
+fn check_limit(size: usize, limit: usize) -> Result<()> {
+    if size > limit {
+        bail!(ErrorKind::ValueTooLarge(limit));
     }
+    Ok(())
 }
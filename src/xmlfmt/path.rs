@@ -0,0 +1,159 @@
+use super::error::Result;
+use super::value::take_member;
+use super::Value;
+
+/// A step along a path into nested values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment<'a> {
+    Member(&'a str),
+    Index(usize),
+}
+
+/// Splits the path into its steps, of struct members separated by dots and array indices in
+/// brackets, or fails if it is malformed.
+fn segments(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    if path.is_empty() {
+        return Some(segments);
+    }
+    for segment in path.split('.') {
+        let (name, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if !name.is_empty() {
+            segments.push(Segment::Member(name));
+        } else if indices.is_empty() {
+            return None;
+        }
+        while !indices.is_empty() {
+            let rest = indices.strip_prefix('[')?;
+            let end = rest.find(']')?;
+            segments.push(Segment::Index(rest[..end].parse().ok()?));
+            indices = &rest[end + 1..];
+        }
+    }
+    Some(segments)
+}
+
+/// A change to a value, at a path as `Value::get` takes, applied with `Value::apply`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Patch {
+    /// Sets the value at the path, adding it to its struct or to the end of its array.
+    Set(String, Value),
+    /// Removes the value at the path from its struct or array.
+    Remove(String),
+    /// Merges the value into the one at the path, as `Value::merge` does.
+    Merge(String, Value),
+}
+
+impl Value {
+    /// Looks up the value nested at the path, of struct members separated by dots and array
+    /// indices in brackets, as in `result.items[2].name`, with an empty path for the value itself.
+    ///
+    /// Members with dots or brackets in their names are out of reach, and have to be looked up
+    /// with `as_struct`.
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        segments(path)?
+            .into_iter()
+            .try_fold(self, |value, segment| match (segment, value) {
+                (Segment::Member(name), Value::Struct(members)) => members.get(name),
+                (Segment::Index(index), Value::Array(items)) => items.get(index),
+                _ => None,
+            })
+    }
+
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut Value> {
+        segments(path)?.into_iter().try_fold(self, descend)
+    }
+
+    /// Merges the other value into this one, member by member for structs, with every other
+    /// value, arrays included, replaced by the other.
+    pub fn merge(&mut self, other: &Value) {
+        match (self, other) {
+            (Value::Struct(members), Value::Struct(others)) => {
+                for (name, other) in others {
+                    match members.get_mut(name) {
+                        Some(value) => value.merge(other),
+                        None => {
+                            members.insert(name.clone(), other.clone());
+                        }
+                    }
+                }
+            }
+            (value, other) => *value = other.clone(),
+        }
+    }
+
+    /// Applies the patches in order, or none of them if any fails, telling which.
+    pub fn apply(&mut self, patches: &[Patch]) -> Result<()> {
+        let mut patched = self.clone();
+        for patch in patches {
+            match *patch {
+                Patch::Set(ref path, ref value) => patched.set(path, value.clone())?,
+                Patch::Remove(ref path) => {
+                    patched.remove(path)?;
+                }
+                Patch::Merge(ref path, ref value) => match patched.get_mut(path) {
+                    Some(target) => target.merge(value),
+                    None => bail!("No value at {} to merge into.", path),
+                },
+            }
+        }
+        *self = patched;
+        Ok(())
+    }
+
+    /// Sets the value at the path, whose parent has to exist already.
+    fn set(&mut self, path: &str, value: Value) -> Result<()> {
+        let (parent, last) = match split_last(path) {
+            Some(split) => split,
+            None => bail!("Cannot set a value at {}.", path),
+        };
+        let parent = match parent.into_iter().try_fold(self, descend) {
+            Some(parent) => parent,
+            None => bail!("No value at the parent of {}.", path),
+        };
+        match (last, parent) {
+            (Segment::Member(name), Value::Struct(members)) => {
+                members.insert(name.to_owned(), value);
+            }
+            (Segment::Index(index), Value::Array(items)) if index < items.len() => {
+                items[index] = value;
+            }
+            (Segment::Index(index), Value::Array(items)) if index == items.len() => {
+                items.push(value);
+            }
+            _ => bail!("Cannot set a value at {}.", path),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &str) -> Result<Value> {
+        let removed = split_last(path).and_then(|(parent, last)| {
+            match (last, parent.into_iter().try_fold(self, descend)?) {
+                (Segment::Member(name), Value::Struct(members)) => take_member(members, name),
+                (Segment::Index(index), Value::Array(items)) if index < items.len() => {
+                    Some(items.remove(index))
+                }
+                _ => None,
+            }
+        });
+        match removed {
+            Some(value) => Ok(value),
+            None => bail!("No value at {} to remove.", path),
+        }
+    }
+}
+
+fn descend<'a>(value: &'a mut Value, segment: Segment) -> Option<&'a mut Value> {
+    match (segment, value) {
+        (Segment::Member(name), Value::Struct(members)) => members.get_mut(name),
+        (Segment::Index(index), Value::Array(items)) => items.get_mut(index),
+        _ => None,
+    }
+}
+
+/// Splits the path into the steps to the parent and the last one, unless it is empty.
+fn split_last(path: &str) -> Option<(Vec<Segment<'_>>, Segment<'_>)> {
+    let mut segments = segments(path)?;
+    let last = segments.pop()?;
+    Some((segments, last))
+}
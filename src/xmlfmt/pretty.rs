@@ -0,0 +1,126 @@
+use super::{Call, Response, Value};
+use std::fmt::{self, Write};
+
+/// Renders values for people to read, in logs and while debugging, rather than for peers.
+///
+/// The output resembles JSON, with `nil` and the types without a JSON counterpart marked, as in
+/// `base64(aGk=)` and `dateTime(19980717T14:08:55)`. `Display` writes values and calls on a
+/// single line, while `to_pretty_string` spreads arrays and structs over indented lines.
+pub trait ToPretty {
+    fn to_pretty_string(&self) -> String;
+}
+
+impl ToPretty for Value {
+    fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        let _ = write_value(&mut out, self, Some(0));
+        out
+    }
+}
+
+impl ToPretty for Call {
+    fn to_pretty_string(&self) -> String {
+        let mut out = self.name.clone();
+        let _ = write_params(&mut out, &self.params, Some(0));
+        out
+    }
+}
+
+/// Writes the parameters in parentheses, or the fault with its code and message.
+impl ToPretty for Response {
+    fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        let _ = match *self {
+            Ok(ref params) => write_params(&mut out, params, Some(0)),
+            Err(ref fault) if fault.members().is_empty() => write!(out, "{}", fault),
+            Err(ref fault) => {
+                let members = Value::Struct(fault.members().clone());
+                write!(out, "{} ", fault).and_then(|_| write_value(&mut out, &members, Some(0)))
+            }
+        };
+        out
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self, None)
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        write_params(f, &self.params, None)
+    }
+}
+
+/// Writes the value on a single line without an indent, or else over lines indented by as
+/// many levels.
+fn write_value<W: Write>(out: &mut W, value: &Value, indent: Option<usize>) -> fmt::Result {
+    match *value {
+        Value::Int(v) => write!(out, "{}", v),
+        Value::Int64(v) => write!(out, "{}", v),
+        Value::Bool(v) => write!(out, "{}", v),
+        Value::String(ref v) => write!(out, "{:?}", v),
+        Value::Double(v) => write!(out, "{:?}", v),
+        Value::DateTime(ref v) => write!(out, "dateTime({})", v),
+        Value::Base64(ref v) => write!(out, "base64({})", base64::encode(v)),
+        Value::Nil => out.write_str("nil"),
+        Value::Array(ref v) => write_list(out, ('[', ']'), v, indent, |out, item, indent| {
+            write_value(out, item, indent)
+        }),
+        Value::Struct(ref v) => {
+            write_list(out, ('{', '}'), v, indent, |out, (name, item), indent| {
+                write!(out, "{:?}: ", name)?;
+                write_value(out, item, indent)
+            })
+        }
+    }
+}
+
+fn write_params<W: Write>(out: &mut W, params: &[Value], indent: Option<usize>) -> fmt::Result {
+    write_list(out, ('(', ')'), params, indent, |out, param, indent| {
+        write_value(out, param, indent)
+    })
+}
+
+/// Writes the items between the brackets, separated by commas, and each on a line of its own
+/// when indented.
+fn write_list<W, I, F>(
+    out: &mut W,
+    (open, close): (char, char),
+    items: I,
+    indent: Option<usize>,
+    mut write_item: F,
+) -> fmt::Result
+where
+    W: Write,
+    I: IntoIterator,
+    F: FnMut(&mut W, I::Item, Option<usize>) -> fmt::Result,
+{
+    out.write_char(open)?;
+    let mut empty = true;
+    for item in items {
+        if !empty {
+            out.write_char(',')?;
+        }
+        match indent {
+            Some(level) => {
+                write!(out, "\n{:width$}", "", width = (level + 1) * 2)?;
+                write_item(out, item, Some(level + 1))?;
+            }
+            None => {
+                if !empty {
+                    out.write_char(' ')?;
+                }
+                write_item(out, item, None)?;
+            }
+        }
+        empty = false;
+    }
+    if let (Some(level), false) = (indent, empty) {
+        write!(out, "\n{:width$}", "", width = level * 2)?;
+    }
+    out.write_char(close)
+}
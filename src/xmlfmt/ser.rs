@@ -1,9 +1,38 @@
 use super::error::{Error, ErrorKind};
-use super::Value;
+use super::value::VALUE_NAME;
+use super::{Extensions, Members, Value};
 use serde::{self, Serialize};
-use std::collections::HashMap;
+use std::convert::TryFrom;
 
-pub struct Serializer;
+#[derive(Default)]
+pub struct Serializer {
+    extensions: Extensions,
+}
+
+impl Serializer {
+    pub fn new(extensions: Extensions) -> Serializer {
+        Serializer { extensions }
+    }
+
+    /// Encodes integers of types wider than `<i4>`, which are strings unless the extensions
+    /// say otherwise.
+    fn wide(self, v: i128) -> Result<Value, Error> {
+        if !self.extensions.i8 && !self.extensions.strict {
+            return Ok(Value::String(v.to_string()));
+        }
+        if let Ok(v) = i32::try_from(v) {
+            return Ok(Value::Int(v));
+        }
+        match i64::try_from(v) {
+            Ok(v) if self.extensions.i8 => Ok(Value::Int64(v)),
+            _ if self.extensions.strict => bail!(ErrorKind::UnsupportedData(format!(
+                "Integer {} is out of the range of the types used.",
+                v
+            ))),
+            _ => Ok(Value::String(v.to_string())),
+        }
+    }
+}
 
 impl serde::Serializer for Serializer {
     type Ok = Value;
@@ -34,7 +63,7 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_string()))
+        self.wide(i128::from(v))
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
@@ -46,11 +75,11 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_string()))
+        self.wide(i128::from(v))
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_string()))
+        self.wide(i128::from(v))
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
@@ -74,6 +103,9 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        if self.extensions.nil {
+            return Ok(Value::Nil);
+        }
         Ok(Value::Array(Vec::new()))
     }
 
@@ -81,11 +113,14 @@ impl serde::Serializer for Serializer {
     where
         T: Serialize,
     {
+        if self.extensions.nil {
+            return value.serialize(self);
+        }
         Ok(Value::Array(vec![value.serialize(self)?]))
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Struct(HashMap::new()))
+        Ok(Value::Struct(Members::new()))
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
@@ -94,11 +129,15 @@ impl serde::Serializer for Serializer {
 
     fn serialize_unit_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        let mut members = HashMap::new();
+        // `Value::Nil` is the only unit variant of values.
+        if name == VALUE_NAME {
+            return Ok(Value::Nil);
+        }
+        let mut members = Members::new();
         members.insert(variant.into(), self.serialize_unit()?);
         Ok(Value::Struct(members))
     }
@@ -116,7 +155,7 @@ impl serde::Serializer for Serializer {
 
     fn serialize_newtype_variant<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
@@ -124,7 +163,20 @@ impl serde::Serializer for Serializer {
     where
         T: Serialize,
     {
-        let mut members = HashMap::new();
+        // Values are encoded as themselves, whatever the extensions, with the types that plain
+        // integers and strings would not keep restored.
+        if name == VALUE_NAME {
+            let serializer = Serializer::new(Extensions {
+                i8: true,
+                ..Extensions::default()
+            });
+            return Ok(match (variant, value.serialize(serializer)?) {
+                ("int64", Value::Int(v)) => Value::Int64(i64::from(v)),
+                ("dateTime", Value::String(v)) => Value::DateTime(v),
+                (_, value) => value,
+            });
+        }
+        let mut members = Members::new();
         members.insert(variant.into(), value.serialize(self)?);
         Ok(Value::Struct(members))
     }
@@ -137,6 +189,7 @@ impl serde::Serializer for Serializer {
         Ok(SerializeVec {
             vec: Vec::with_capacity(len),
             variant: None,
+            extensions: self.extensions,
         })
     }
 
@@ -158,14 +211,16 @@ impl serde::Serializer for Serializer {
         Ok(SerializeVec {
             vec: Vec::with_capacity(len),
             variant: Some(variant.into()),
+            extensions: self.extensions,
         })
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         Ok(SerializeMap {
-            map: HashMap::new(),
+            map: Members::new(),
             next_key: None,
             variant: None,
+            extensions: self.extensions,
         })
     }
 
@@ -185,24 +240,26 @@ impl serde::Serializer for Serializer {
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         Ok(SerializeMap {
-            map: HashMap::new(),
+            map: Members::new(),
             next_key: None,
             variant: Some(variant.into()),
+            extensions: self.extensions,
         })
     }
 }
 
-fn to_value<T>(value: &T) -> Result<Value, Error>
+fn to_value<T>(value: &T, extensions: Extensions) -> Result<Value, Error>
 where
     T: Serialize,
 {
-    value.serialize(Serializer)
+    value.serialize(Serializer { extensions })
 }
 
 #[doc(hidden)]
 pub struct SerializeVec {
     vec: Vec<Value>,
     variant: Option<String>,
+    extensions: Extensions,
 }
 
 impl serde::ser::SerializeSeq for SerializeVec {
@@ -213,7 +270,7 @@ impl serde::ser::SerializeSeq for SerializeVec {
     where
         T: Serialize,
     {
-        self.vec.push(to_value(&value)?);
+        self.vec.push(to_value(&value, self.extensions)?);
         Ok(())
     }
 
@@ -221,7 +278,7 @@ impl serde::ser::SerializeSeq for SerializeVec {
         let content = Value::Array(self.vec);
         Ok(match self.variant {
             Some(variant) => {
-                let mut members = HashMap::new();
+                let mut members = Members::new();
                 members.insert(variant, content);
                 Value::Struct(members)
             }
@@ -280,9 +337,10 @@ impl serde::ser::SerializeTupleVariant for SerializeVec {
 
 #[doc(hidden)]
 pub struct SerializeMap {
-    map: HashMap<String, Value>,
+    map: Members,
     next_key: Option<String>,
     variant: Option<String>,
+    extensions: Extensions,
 }
 
 impl serde::ser::SerializeMap for SerializeMap {
@@ -293,9 +351,10 @@ impl serde::ser::SerializeMap for SerializeMap {
     where
         T: Serialize,
     {
-        match to_value(&key)? {
+        match to_value(&key, self.extensions)? {
             Value::Bool(v) => self.next_key = Some(v.to_string()),
             Value::Int(v) => self.next_key = Some(v.to_string()),
+            Value::Int64(v) => self.next_key = Some(v.to_string()),
             Value::Double(v) => self.next_key = Some(v.to_string()),
             Value::String(s) => self.next_key = Some(s),
             _ => bail!(ErrorKind::UnsupportedData(
@@ -313,7 +372,7 @@ impl serde::ser::SerializeMap for SerializeMap {
         // Panic because this indicates a bug in the program rather than an
         // expected failure.
         let key = key.expect("serialize_value called before serialize_key");
-        self.map.insert(key, to_value(&value)?);
+        self.map.insert(key, to_value(&value, self.extensions)?);
         Ok(())
     }
 
@@ -321,7 +380,7 @@ impl serde::ser::SerializeMap for SerializeMap {
         let content = Value::Struct(self.map);
         Ok(match self.variant {
             Some(variant) => {
-                let mut members = HashMap::new();
+                let mut members = Members::new();
                 members.insert(variant, content);
                 Value::Struct(members)
             }
@@ -0,0 +1,82 @@
+use super::super::value::ToXml;
+use super::super::{parse, DateTime, Response, Value};
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+#[test]
+fn reads_common_datetime_formats() {
+    let time = NaiveDate::from_ymd_opt(1998, 7, 17)
+        .unwrap()
+        .and_hms_opt(14, 8, 55)
+        .unwrap();
+    for text in &[
+        "19980717T14:08:55",
+        "19980717T140855",
+        "1998-07-17T14:08:55",
+        "1998-07-17 14:08:55",
+        " 19980717T14:08:55\n",
+    ] {
+        assert_eq!(text.parse::<DateTime>().unwrap(), DateTime::Naive(time));
+    }
+    assert_eq!(
+        "1998-07-17T14:08:55.250".parse::<DateTime>().unwrap(),
+        DateTime::Naive(time + chrono::Duration::milliseconds(250))
+    );
+    assert_eq!(
+        "1998-07-17".parse::<DateTime>().unwrap(),
+        DateTime::Naive(
+            NaiveDate::from_ymd_opt(1998, 7, 17)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        )
+    );
+    let east = FixedOffset::east_opt(2 * 3600).unwrap();
+    let west = FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap();
+    assert_eq!(
+        "1998-07-17T14:08:55+02:00".parse::<DateTime>().unwrap(),
+        DateTime::Offset(east.from_local_datetime(&time).unwrap())
+    );
+    assert_eq!(
+        "19980717T14:08:55-0530".parse::<DateTime>().unwrap(),
+        DateTime::Offset(west.from_local_datetime(&time).unwrap())
+    );
+    assert_eq!(
+        "19980717T14:08:55Z".parse::<DateTime>().unwrap(),
+        DateTime::Offset(
+            FixedOffset::east_opt(0)
+                .unwrap()
+                .from_local_datetime(&time)
+                .unwrap()
+        )
+    );
+    assert!("33".parse::<DateTime>().is_err());
+    assert!("19980717T14:08:55+2".parse::<DateTime>().is_err());
+}
+
+#[test]
+fn writes_datetimes_in_format_of_specification() {
+    let time: DateTime = "1998-07-17 14:08:55".parse().unwrap();
+    assert_eq!(time.to_string(), "19980717T14:08:55");
+    let time: DateTime = "1998-07-17T14:08:55.5+02:00".parse().unwrap();
+    assert_eq!(time.to_string(), "19980717T14:08:55.500+02:00");
+    let response: Response = Ok(vec![Value::from(time)]);
+    let data = parse::response(response.to_xml().as_bytes()).unwrap();
+    assert_eq!(data, response);
+    assert_eq!(data.unwrap()[0].as_datetime(), Some(time));
+}
+
+#[test]
+fn keeps_unparseable_datetimes_as_sent() {
+    let data = r#"<?xml version="1.0"?><methodResponse><params>
+<param><value><dateTime.iso8601>yesterday</dateTime.iso8601></value></param>
+<param><value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value></param>
+</params></methodResponse>"#;
+    let data = parse::response(data.as_bytes()).unwrap().unwrap();
+    assert_eq!(data[0], Value::DateTime("yesterday".into()));
+    assert_eq!(data[0].as_datetime(), None);
+    assert_eq!(
+        data[1].as_datetime(),
+        Some("1998-07-17T14:08:55".parse().unwrap())
+    );
+    assert_eq!(Value::from(8).as_datetime(), None);
+}
@@ -1,4 +1,4 @@
-use super::super::Value;
+use super::super::{Members, Value};
 use serde::Deserialize;
 use serde_bytes;
 use std::collections::HashMap;
@@ -46,6 +46,21 @@ fn reads_integers_as_ints_or_strings_if_too_big() {
     );
 }
 
+#[test]
+fn reads_i8_integers_within_range() {
+    assert_eq!(
+        -8_000_000_000i64,
+        i64::deserialize(Value::Int64(-8_000_000_000)).unwrap()
+    );
+    assert_eq!(
+        4_200_000_000u32,
+        u32::deserialize(Value::Int64(4_200_000_000)).unwrap()
+    );
+    assert_eq!(42u8, u8::deserialize(Value::Int64(42)).unwrap());
+    assert!(i32::deserialize(Value::Int64(4_200_000_000)).is_err());
+    assert!(u64::deserialize(Value::Int64(-1)).is_err());
+}
+
 #[test]
 fn reads_floats() {
     assert_eq!(3.25f32, f32::deserialize(Value::Double(3.25f64)).unwrap());
@@ -72,6 +87,15 @@ fn reads_bytes_as_base64() {
     assert_eq!(data, vec![48, 49, 50, 51]);
 }
 
+#[test]
+fn reads_byte_sequences_from_base64() {
+    let data: Vec<u8> = Vec::deserialize(Value::Base64(vec![0, 49, 255])).unwrap();
+    assert_eq!(data, vec![0, 49, 255]);
+    let data: Vec<u8> = Vec::deserialize(Value::Array(vec![Value::Int(7)])).unwrap();
+    assert_eq!(data, vec![7]);
+    assert!(Vec::<String>::deserialize(Value::Base64(vec![1])).is_err());
+}
+
 #[test]
 fn reads_options_as_one_elem_or_empty_array() {
     let none: Option<i32> = None;
@@ -86,6 +110,14 @@ fn reads_options_as_one_elem_or_empty_array() {
     );
 }
 
+#[test]
+fn reads_options_as_nil_or_value() {
+    let none: Option<i32> = None;
+    assert_eq!(none, Option::deserialize(Value::Nil).unwrap());
+    assert_eq!(Some(33i32), Option::deserialize(Value::Int(33)).unwrap());
+    assert_eq!((), <()>::deserialize(Value::Nil).unwrap());
+}
+
 #[test]
 fn reads_units_as_empty_struct() {
     #[derive(Debug, Deserialize, PartialEq)]
@@ -93,7 +125,7 @@ fn reads_units_as_empty_struct() {
 
     assert_eq!(
         Helper,
-        Helper::deserialize(Value::Struct(HashMap::new())).unwrap()
+        Helper::deserialize(Value::Struct(Members::new())).unwrap()
     );
 }
 
@@ -165,7 +197,7 @@ fn reads_struct_as_struct() {
         qux: bool,
     };
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("foo".into(), Value::Int(4));
     members.insert("bar".into(), Value::String("1000000000000".into()));
     members.insert("baz".into(), Value::String("hello".into()));
@@ -189,7 +221,7 @@ fn reads_map_as_struct() {
     data.insert(String::from("bar"), vec![]);
     data.insert(String::from("baz"), vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "foo".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -210,7 +242,7 @@ fn map_accepts_string_keys() {
     data.insert(String::from("bar"), vec![]);
     data.insert(String::from("baz"), vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "foo".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -231,7 +263,7 @@ fn map_accepts_integer_keys() {
     data.insert(-33, vec![]);
     data.insert(44, vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "12".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -252,7 +284,7 @@ fn map_accepts_char_keys() {
     data.insert('b', vec![]);
     data.insert('c', vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "a".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -272,7 +304,7 @@ fn map_accepts_boolean_keys() {
     data.insert(true, vec![44i8, 12]);
     data.insert(false, vec![]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "true".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -292,21 +324,21 @@ fn reads_variant_as_one_member_struct() {
         Qux { alpha: i32, beta: Vec<bool> },
     };
 
-    let mut members = HashMap::new();
-    members.insert("Foo".into(), Value::Struct(HashMap::new()));
+    let mut members = Members::new();
+    members.insert("Foo".into(), Value::Struct(Members::new()));
     assert_eq!(
         Helper::Foo,
         Helper::deserialize(Value::Struct(members)).unwrap()
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("Bar".into(), Value::Int(44));
     assert_eq!(
         Helper::Bar(44),
         Helper::deserialize(Value::Struct(members)).unwrap()
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "Baz".into(),
         Value::Array(vec![Value::Bool(false), Value::String("tsk".into())]),
@@ -316,7 +348,7 @@ fn reads_variant_as_one_member_struct() {
         Helper::deserialize(Value::Struct(members)).unwrap()
     );
 
-    let mut submembers = HashMap::new();
+    let mut submembers = Members::new();
     submembers.insert("alpha".into(), Value::Int(-4));
     submembers.insert(
         "beta".into(),
@@ -327,7 +359,7 @@ fn reads_variant_as_one_member_struct() {
         ]),
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("Qux".into(), Value::Struct(submembers));
     assert_eq!(
         Helper::Qux {
@@ -337,3 +369,28 @@ fn reads_variant_as_one_member_struct() {
         Helper::deserialize(Value::Struct(members)).unwrap()
     );
 }
+
+fn every_kind_of_value() -> Value {
+    let mut members = Members::new();
+    members.insert("nil".into(), Value::Nil);
+    members.insert("time".into(), Value::DateTime("19980717T14:08:55".into()));
+    Value::Array(vec![
+        Value::Int(3),
+        Value::Int64(4),
+        Value::Bool(true),
+        Value::String("txt".into()),
+        Value::Double(0.5),
+        Value::Base64(b"raw".to_vec()),
+        Value::Struct(members),
+    ])
+}
+
+#[test]
+fn reads_values_as_themselves() {
+    let value = every_kind_of_value();
+    assert_eq!(Value::deserialize(value.clone()).unwrap(), value);
+    assert_eq!(Value::deserialize(Value::Nil).unwrap(), Value::Nil);
+    let params = vec![Value::Int(3), Value::String("txt".into())];
+    let (first, second): (Value, String) = super::super::from_params(params).unwrap();
+    assert_eq!((first, second), (Value::Int(3), "txt".to_owned()));
+}
@@ -0,0 +1,33 @@
+use super::super::value::ToXml;
+use super::super::{Call, Encoding, Value};
+
+fn call(text: &str) -> Call {
+    Call {
+        name: "echo".into(),
+        params: vec![Value::String(text.into())],
+    }
+}
+
+#[test]
+fn leaves_utf8_documents_unchanged() {
+    let xml = call("Grüße €").to_xml();
+    assert_eq!(Encoding::Utf8.encode(&xml), xml.as_bytes());
+}
+
+#[test]
+fn declares_latin1_and_writes_single_bytes() {
+    let body = Encoding::Latin1.encode(&call("Grüße").to_xml());
+    let expected = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><methodCall>\
+        <methodName>echo</methodName><params><param><value><string>Gr\xfc\xdfe\
+        </string></value></param></params></methodCall>\n";
+    assert_eq!(body, expected.to_vec());
+}
+
+#[test]
+fn writes_characters_outside_latin1_as_references() {
+    let body = Encoding::Latin1.encode("<a>€ and 😀</a>");
+    assert_eq!(
+        body,
+        b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>&#8364; and &#128512;</a>".to_vec()
+    );
+}
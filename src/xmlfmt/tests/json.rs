@@ -0,0 +1,97 @@
+use super::super::{Call, Fault, Members, Response, Value};
+
+#[test]
+fn converts_json_into_values() {
+    let json: serde_json::Value = serde_json::from_str(
+        r#"{"id": 7, "big": 8000000000, "huge": 18446744073709551615, "ratio": 0.5,
+            "ok": true, "name": "ann", "tags": ["a", null]}"#,
+    )
+    .unwrap();
+    let mut members = Members::new();
+    members.insert("id".to_owned(), Value::Int(7));
+    members.insert("big".to_owned(), Value::Int64(8_000_000_000));
+    members.insert(
+        "huge".to_owned(),
+        Value::Double(18_446_744_073_709_551_615.0),
+    );
+    members.insert("ratio".to_owned(), Value::Double(0.5));
+    members.insert("ok".to_owned(), Value::Bool(true));
+    members.insert("name".to_owned(), Value::String("ann".into()));
+    members.insert("tags".to_owned(), xmlrpc_value!(["a", nil]));
+    assert_eq!(Value::from(json), Value::Struct(members));
+}
+
+#[test]
+fn converts_values_into_json() {
+    let mut members = Members::new();
+    members.insert("id".to_owned(), Value::Int(7));
+    members.insert("big".to_owned(), Value::Int64(8_000_000_000));
+    members.insert("ratio".to_owned(), Value::Double(0.5));
+    members.insert("nan".to_owned(), Value::Double(f64::NAN));
+    members.insert("ok".to_owned(), Value::Bool(true));
+    members.insert("tags".to_owned(), xmlrpc_value!(["a", nil]));
+    members.insert(
+        "time".to_owned(),
+        Value::DateTime("19980717T14:08:55".into()),
+    );
+    members.insert("data".to_owned(), Value::Base64(b"hi".to_vec()));
+    let expected: serde_json::Value = serde_json::from_str(
+        r#"{"id": 7, "big": 8000000000, "ratio": 0.5, "nan": null, "ok": true,
+            "tags": ["a", null], "time": "19980717T14:08:55", "data": "aGk="}"#,
+    )
+    .unwrap();
+    assert_eq!(serde_json::Value::from(Value::Struct(members)), expected);
+}
+
+#[test]
+fn round_trips_json_without_loss() {
+    let json: serde_json::Value =
+        serde_json::from_str(r#"{"a": [1, -2.5, "x", false, null], "b": {"c": {}}}"#).unwrap();
+    assert_eq!(serde_json::Value::from(Value::from(json.clone())), json);
+}
+
+#[test]
+fn stores_values_as_tagged_json() {
+    let mut members = Members::new();
+    members.insert("id".to_owned(), Value::Int(7));
+    members.insert("data".to_owned(), Value::Base64(b"hi".to_vec()));
+    members.insert("none".to_owned(), Value::Nil);
+    let value = Value::Array(vec![Value::Struct(members), Value::Int64(-1)]);
+    let json = serde_json::to_value(&value).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(
+        r#"{"array": [{"struct": {"id": {"int": 7}, "data": {"base64": [104, 105]},
+            "none": "nil"}}, {"int64": -1}]}"#,
+    )
+    .unwrap();
+    assert_eq!(json, expected);
+    assert_eq!(serde_json::from_value::<Value>(json).unwrap(), value);
+}
+
+#[test]
+fn stores_calls_and_responses_as_json() {
+    let call = Call {
+        name: "log".to_owned(),
+        params: vec![
+            Value::DateTime("19980717T14:08:55".into()),
+            Value::Double(0.5),
+            Value::from("x"),
+        ],
+    };
+    let text = serde_json::to_string(&call).unwrap();
+    assert_eq!(serde_json::from_str::<Call>(&text).unwrap(), call);
+
+    let response: Response = Ok(vec![Value::Bool(true)]);
+    let text = serde_json::to_string(&response).unwrap();
+    assert_eq!(serde_json::from_str::<Response>(&text).unwrap(), response);
+
+    let fault = Fault::new(4, "Too many parameters.").with_member("param", Value::Int(2));
+    let json = serde_json::to_value(&fault).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(
+        r#"{"faultCode": 4, "faultString": "Too many parameters.", "param": {"int": 2}}"#,
+    )
+    .unwrap();
+    assert_eq!(json, expected);
+    let response: Response = Err(fault);
+    let text = serde_json::to_string(&response).unwrap();
+    assert_eq!(serde_json::from_str::<Response>(&text).unwrap(), response);
+}
@@ -0,0 +1,58 @@
+use super::super::error::ErrorKind;
+use super::super::parse;
+use super::super::Value;
+
+fn call(value: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\"?><methodCall><methodName>put</methodName><params>\
+         <param><value>{}</value></param></params></methodCall>",
+        value
+    )
+}
+
+#[test]
+fn accepts_values_within_the_limit() {
+    let data = call("<base64>aGVsbG8h</base64>");
+    let call = parse::call_limited(data.as_bytes(), 6).expect("Failed to parse the call");
+    assert_eq!(call.params, vec![Value::Base64(b"hello!".to_vec())]);
+}
+
+#[test]
+fn rejects_long_strings() {
+    let data = call("<string>too long</string>");
+    let err = parse::call_limited(data.as_bytes(), 4).unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::ValueTooLarge(4)));
+}
+
+#[test]
+fn rejects_large_values_nested_in_arrays_and_structs() {
+    let data = call(
+        "<struct><member><name>files</name><value><array><data>\
+         <value><base64>aGVsbG8h</base64></value></data></array></value></member></struct>",
+    );
+    let err = parse::call_limited(data.as_bytes(), 5).unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::ValueTooLarge(5)));
+}
+
+#[test]
+fn rejects_long_names_and_numbers() {
+    let padded = format!("<i4>{:>100}</i4>", 1);
+    for value in &[
+        padded.as_str(),
+        "<int>123456789</int>",
+        "<i8>1234567890123</i8>",
+        "<double>0.000000001</double>",
+        "<boolean>        1</boolean>",
+        "<struct><member><name>seventeen</name><value><i4>1</i4></value></member></struct>",
+    ] {
+        let err = parse::call_limited(call(value).as_bytes(), 8).unwrap_err();
+        assert!(
+            matches!(*err.kind(), ErrorKind::ValueTooLarge(8)),
+            "{}",
+            value
+        );
+    }
+    let data = call("<i4>1</i4>").replace("put", "a.long.method");
+    let err = parse::call_limited(data.as_bytes(), 8).unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::ValueTooLarge(8)));
+}
@@ -1,3 +1,10 @@
+#[cfg(feature = "chrono")]
+mod datetime;
 mod de;
+mod encoding;
+#[cfg(feature = "json")]
+mod json;
+mod limits;
 mod parsevalue;
 mod ser;
+mod value;
@@ -1,6 +1,5 @@
 use super::super::*;
 use serde::Deserialize;
-use std::collections::HashMap;
 
 static BAD_DATA: &'static str = "Bad data provided";
 
@@ -75,7 +74,7 @@ fn reads_array_xml_value() {
 fn reads_empty_struct_xml_value() {
     let data = r#"<?xml version="1.0"?><struct></struct>"#;
     let data = parse::xml(data.as_bytes()).expect(BAD_DATA);
-    assert_eq!(data, Value::Struct(HashMap::<String, Value>::new()));
+    assert_eq!(data, Value::Struct(Members::new()));
 }
 
 #[test]
@@ -105,9 +104,152 @@ fn reads_tagged_and_untagged_strings() {
     );
 }
 
+#[test]
+fn reads_untyped_values_as_strings_with_their_whitespace() {
+    for &(value, expected) in &[
+        ("<value>  spaced  </value>", "  spaced  "),
+        ("<value>\n</value>", "\n"),
+        ("<value>a &amp; b <![CDATA[<c>]]></value>", "a & b <c>"),
+        ("<value>split<!-- comment -->text</value>", "splittext"),
+        ("<value>\n  <string> typed </string>\n</value>", " typed "),
+    ] {
+        let data = format!(
+            r#"<?xml version="1.0"?><methodResponse><params><param>{}</param></params></methodResponse>"#,
+            value
+        );
+        let response = parse::response(data.as_bytes()).expect(BAD_DATA);
+        assert_eq!(response, Ok(vec![Value::String(expected.into())]));
+        let data = format!(
+            r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName><params><param>{}</param></params></methodCall>"#,
+            value
+        );
+        let call = parse::call(data.as_bytes()).expect(BAD_DATA);
+        assert_eq!(call.params, vec![Value::String(expected.into())]);
+        let value = parse::xml(value.as_bytes()).expect(BAD_DATA);
+        assert_eq!(value, Value::String(expected.into()));
+    }
+}
+
+#[test]
+fn rejects_text_mixed_with_typed_values() {
+    for value in &[
+        "<value>text <i4>1</i4></value>",
+        "<value><i4>1</i4> text</value>",
+        "<value><i4>1</i4><i4>2</i4></value>",
+    ] {
+        let data = format!(
+            r#"<?xml version="1.0"?><methodResponse><params><param>{}</param></params></methodResponse>"#,
+            value
+        );
+        assert!(parse::response(data.as_bytes()).is_err());
+    }
+    let data = "<value>\t<i4>1</i4>\r\n</value>";
+    assert_eq!(parse::xml(data.as_bytes()).expect(BAD_DATA), Value::Int(1));
+}
+
+#[test]
+fn reads_empty_struct_members_and_array_items() {
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><struct>
+        <member><name>bare</name><value/></member>
+        <member><name>open</name><value></value></member>
+        <member><name>typed</name><value><string/></value></member>
+        <member><name>spaced</name><value> <string></string> </value></member>
+        <member><name/><value><string>unnamed</string></value></member>
+        <member><name>items</name><value><array><data>
+            <value/><value><string/></value><value><base64/></value><value><array/></value>
+        </data></array></value></member>
+    </struct></value></param></params></methodResponse>"#;
+    let mut members = Members::new();
+    for name in &["bare", "open", "typed", "spaced"] {
+        members.insert((*name).into(), Value::String(String::new()));
+    }
+    members.insert(String::new(), Value::String("unnamed".into()));
+    members.insert(
+        "items".into(),
+        Value::Array(vec![
+            Value::String(String::new()),
+            Value::String(String::new()),
+            Value::Base64(vec![]),
+            Value::Array(vec![]),
+        ]),
+    );
+    let expected = Ok(vec![Value::Struct(members)]);
+    assert_eq!(parse::response(data.as_bytes()).expect(BAD_DATA), expected);
+
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><array/></value>
+        </param></params></methodResponse>"#;
+    assert!(parse::response_with(data.as_bytes(), ParseOptions::strict()).is_err());
+}
+
+#[test]
+fn reads_untyped_and_empty_values_when_strict() {
+    let data = r#"<?xml version="1.0"?><methodResponse><params>
+        <param><value> text </value></param>
+        <param><value/></param>
+        <param><value><struct>
+            <member><name>bare</name><value/></member>
+            <member><name>open</name><value></value></member>
+            <member><name>typed</name><value><string/></value></member>
+            <member><name>items</name><value><array><data>
+                <value/><value><string/></value><value><base64/></value>
+            </data></array></value></member>
+        </struct></value></param>
+    </params></methodResponse>"#;
+    let mut members = Members::new();
+    for name in &["bare", "open", "typed"] {
+        members.insert((*name).into(), Value::String(String::new()));
+    }
+    members.insert(
+        "items".into(),
+        Value::Array(vec![
+            Value::String(String::new()),
+            Value::String(String::new()),
+            Value::Base64(vec![]),
+        ]),
+    );
+    let expected = Ok(vec![
+        Value::String(" text ".into()),
+        Value::String(String::new()),
+        Value::Struct(members),
+    ]);
+    let strict = ParseOptions::strict();
+    assert_eq!(
+        parse::response_with(data.as_bytes(), strict).expect(BAD_DATA),
+        expected
+    );
+
+    let untyped = ParseOptions {
+        untyped_values: false,
+        ..strict
+    };
+    assert!(parse::response_with(data.as_bytes(), untyped).is_err());
+}
+
+#[test]
+fn decodes_empty_values_into_strings() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Named {
+        name: String,
+        tags: Vec<String>,
+    }
+
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><struct>
+        <member><name>name</name><value/></member>
+        <member><name>tags</name><value><array><data><value><string/></value></data></array></value></member>
+    </struct></value></param></params></methodResponse>"#;
+    let params = parse::response(data.as_bytes()).expect(BAD_DATA).unwrap();
+    assert_eq!(
+        from_params::<Named>(params).expect(BAD_DATA),
+        Named {
+            name: String::new(),
+            tags: vec![String::new()],
+        }
+    );
+}
+
 #[test]
 fn reads_struct_xml_value() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     let data = r#"<?xml version="1.0"?>
@@ -127,7 +269,7 @@ fn reads_struct_xml_value() {
 
 #[test]
 fn reads_response() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     fields.insert("bar2".into(), Value::String("baz2".into()));
@@ -182,18 +324,26 @@ fn reads_fault() {
     </fault>
 </methodResponse>"#;
     let data = parse::response(data.as_bytes()).expect(BAD_DATA);
-    assert_eq!(
-        data,
-        Err(Fault {
-            code: 4,
-            message: "Too many parameters.".into(),
-        })
-    );
+    assert_eq!(data, Err(Fault::new(4, "Too many parameters.")));
+}
+
+#[test]
+fn converts_fault_into_error() {
+    use crate::error::{Error, ErrorKind};
+
+    let fault = Fault::new(4, "Too many parameters.");
+    assert_eq!(fault.to_string(), "XML-RPC fault 4: Too many parameters.");
+    let err = Error::from(fault.clone());
+    assert_eq!(err.to_string(), fault.to_string());
+    match *err.kind() {
+        ErrorKind::Fault(ref inner) => assert_eq!(**inner, fault),
+        ref kind => panic!("Unexpected error kind: {:?}", kind),
+    }
 }
 
 #[test]
 fn reads_call() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     let data = r#"<?xml version="1.0"?>
@@ -227,6 +377,77 @@ fn reads_call() {
     );
 }
 
+/// Hands out the data a byte at a time, as a slow connection would.
+struct Trickle<'a>(&'a [u8]);
+
+impl<'a> std::io::Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.split_first() {
+            Some((&byte, rest)) if !buf.is_empty() => {
+                buf[0] = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn reads_call_as_it_arrives() {
+    let data = r#"<?xml version="1.0"?>
+<!-- A comment before the call -->
+<methodCall>
+    <methodName>foobar</methodName>
+    <params>
+        <param><value>Rock &amp; Roll</value></param>
+        <param><value><string><![CDATA[<raw>]]></string></value></param>
+        <param><value/></param>
+        <param><value><array><data>
+            <value><boolean>1</boolean></value>
+            <value><double>-4.5</double></value>
+            <value><base64>aGVs
+                bG8h</base64></value>
+        </data></array></value></param>
+    </params>
+</methodCall>"#;
+    let data = parse::call(Trickle(data.as_bytes())).expect(BAD_DATA);
+    assert_eq!(data.name, String::from("foobar"));
+    assert_eq!(
+        data.params,
+        vec![
+            Value::String("Rock & Roll".into()),
+            Value::String("<raw>".into()),
+            Value::String("".into()),
+            Value::Array(vec![
+                Value::Bool(true),
+                Value::Double(-4.5),
+                Value::Base64(b"hello!".to_vec()),
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn reads_call_without_params() {
+    let data = r#"<?xml version="1.0"?><methodCall><methodName>ping</methodName></methodCall>"#;
+    let data = parse::call(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(data.name, String::from("ping"));
+    assert_eq!(data.params, vec![]);
+}
+
+#[test]
+fn rejects_malformed_calls() {
+    let data = r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName><params>
+        <param><value><i4>33</int></value></param></params></methodCall>"#;
+    assert!(parse::call(data.as_bytes()).is_err());
+    let data = r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName><params>
+        <param><value><float>1.5</float></value></param></params></methodCall>"#;
+    assert!(parse::call(data.as_bytes()).is_err());
+    let data = r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName><params>"#;
+    assert!(parse::call(data.as_bytes()).is_err());
+}
+
 #[test]
 fn reads_array_structure_xml_value() {
     let data = r#"<?xml version="1.0"?>
@@ -274,6 +495,79 @@ fn writes_pod_xml_value() {
     ser_and_de(Value::Base64("ASDF=".into()));
 }
 
+#[test]
+fn reads_and_writes_nil() {
+    let data = r#"<?xml version="1.0"?>
+<methodCall>
+    <methodName>foobar</methodName>
+    <params>
+        <param><value><nil/></value></param>
+        <param><value><array><data><value><nil></nil></value></data></array></value></param>
+    </params>
+</methodCall>"#;
+    let data = parse::call(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(
+        data.params,
+        vec![Value::Nil, Value::Array(vec![Value::Nil])]
+    );
+    ser_and_de(Value::Nil);
+    ser_and_de(Value::Array(vec![Value::Int(1), Value::Nil]));
+}
+
+#[test]
+fn reads_and_writes_i8() {
+    let data = r#"<?xml version="1.0"?>
+<methodCall xmlns:ex="http://ws.apache.org/xmlrpc/namespaces/extensions">
+    <methodName>foobar</methodName>
+    <params>
+        <param><value><i8>-8000000000</i8></value></param>
+        <param><value><ex:i8>42</ex:i8></value></param>
+    </params>
+</methodCall>"#;
+    let data = parse::call(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(
+        data.params,
+        vec![Value::Int64(-8_000_000_000), Value::Int64(42)]
+    );
+    let data = r#"<?xml version="1.0"?>
+<methodResponse xmlns:ex="http://ws.apache.org/xmlrpc/namespaces/extensions">
+    <params><param><value><ex:i8>8000000000</ex:i8></value></param></params>
+</methodResponse>"#;
+    let data = parse::response(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(data, Ok(vec![Value::Int64(8_000_000_000)]));
+    ser_and_de(Value::Int64(8_000_000_000_000_000_000));
+}
+
+#[test]
+fn keeps_struct_members_in_order() {
+    use super::super::value::ToXml;
+    let members = r#"<struct>
+    <member><name>zeta</name><value><i4>1</i4></value></member>
+    <member><name>alpha</name><value><i4>2</i4></value></member>
+    <member><name>mu</name><value><i4>3</i4></value></member>
+</struct>"#;
+    let data = format!(r#"<?xml version="1.0"?>{}"#, members);
+    let value = parse::xml(data.as_bytes()).expect(BAD_DATA);
+    let names = |value: &Value| {
+        value
+            .as_struct()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&value), ["zeta", "alpha", "mu"]);
+    let data = format!(
+        r#"<?xml version="1.0"?><methodCall><methodName>f</methodName><params><param><value>{}</value></param></params></methodCall>"#,
+        members
+    );
+    let call = parse::call(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(names(&call.params[0]), ["zeta", "alpha", "mu"]);
+    let xml = value.to_xml();
+    assert!(xml.find("zeta").unwrap() < xml.find("alpha").unwrap());
+    assert!(xml.find("alpha").unwrap() < xml.find("mu").unwrap());
+}
+
 #[test]
 fn writes_array_xml_value() {
     ser_and_de(Value::Array(vec![
@@ -285,7 +579,7 @@ fn writes_array_xml_value() {
 
 #[test]
 fn writes_struct_xml_value() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     ser_and_de(Value::Struct(fields));
@@ -293,7 +587,7 @@ fn writes_struct_xml_value() {
 
 #[test]
 fn writes_response() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     let params = vec![Value::String("South Dakota".into()), Value::Struct(fields)];
@@ -302,15 +596,44 @@ fn writes_response() {
 
 #[test]
 fn writes_fault() {
-    ser_and_de_response_value(Err(Fault {
-        code: 4,
-        message: "Too many parameters.".into(),
-    }));
+    ser_and_de_response_value(Err(Fault::new(4, "Too many parameters.")));
+}
+
+#[test]
+fn writes_fault_with_members() {
+    ser_and_de_response_value(Err(Fault::new(22, "Invalid <date>")
+        .with_member("field", Value::String("date".into()))
+        .with_member("retry", Value::Bool(false))));
+}
+
+#[test]
+fn writes_escaped_member_names() {
+    let mut members = Members::new();
+    members.insert("a<b & c>".into(), Value::Int(1));
+    ser_and_de(Value::Struct(members.clone()));
+    ser_and_de_response_value(Err(
+        Fault::new(1, "Bad").with_member("<id>", Value::Struct(members))
+    ));
+}
+
+#[test]
+fn reads_fault_members() {
+    let data = r#"<?xml version="1.0"?>
+<methodResponse><fault><value><struct>
+    <member><name>faultCode</name><value><int>22</int></value></member>
+    <member><name>faultString</name><value><string>Invalid date</string></value></member>
+    <member><name>field</name><value><string>date</string></value></member>
+</struct></value></fault></methodResponse>"#;
+    let data = parse::response(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(
+        data,
+        Err(Fault::new(22, "Invalid date").with_member("field", Value::String("date".into())))
+    );
 }
 
 #[test]
 fn writes_call() {
-    let mut fields = HashMap::<String, Value>::new();
+    let mut fields = Members::new();
     fields.insert("foo".into(), Value::Int(42));
     fields.insert("bar".into(), Value::String("baz".into()));
     ser_and_de_call_value(Call {
@@ -331,3 +654,121 @@ fn reads_and_writes_empty_call() {
 fn reads_and_writes_empty_response() {
     ser_and_de_response_value(Ok(vec![]))
 }
+
+#[test]
+fn reads_response_base64_into_sink() {
+    let data = r#"<?xml version="1.0"?>
+<methodResponse>
+  <params>
+    <param>
+      <value><array><data>
+        <value><base64>Zm9v
+YmFy</base64></value>
+        <value><int>3</int></value>
+        <value><base64 >IQ==</base64></value>
+      </data></array></value>
+    </param>
+  </params>
+</methodResponse>"#;
+    let mut sink = Vec::new();
+    let data = parse::response_base64_into(data.as_bytes(), &mut sink).expect(BAD_DATA);
+    assert_eq!(sink, b"foobar!".to_vec());
+    assert_eq!(
+        data,
+        Ok(vec![Value::Array(vec![
+            Value::Base64(vec![]),
+            Value::Int(3),
+            Value::Base64(vec![]),
+        ])])
+    );
+}
+
+#[test]
+fn reads_large_base64_into_sink() {
+    let payload = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let data = format!(
+        r#"<?xml version="1.0"?><methodResponse><params><param><value><base64>{}</base64></value></param></params></methodResponse>"#,
+        base64::encode(&payload)
+    );
+    let mut sink = Vec::new();
+    let data = parse::response_base64_into(data.as_bytes(), &mut sink).expect(BAD_DATA);
+    assert_eq!(sink, payload);
+    assert_eq!(data, Ok(vec![Value::Base64(vec![])]));
+}
+
+#[test]
+fn reads_split_base64_into_sink() {
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><struct>
+        <member><name>a</name><value><base64>Zm<![CDATA[9vYm]]><!-- note -->Fy
+        </base64></value></member>
+        <member><name>b</name><value><base64/></value></member>
+        </struct></value></param></params></methodResponse>"#;
+    let mut sink = Vec::new();
+    let data = parse::response_base64_into(data.as_bytes(), &mut sink).expect(BAD_DATA);
+    assert_eq!(sink, b"foobar".to_vec());
+    let mut members = Members::new();
+    members.insert("a".into(), Value::Base64(vec![]));
+    members.insert("b".into(), Value::Base64(vec![]));
+    assert_eq!(data, Ok(vec![Value::Struct(members)]));
+
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param>
+        <value><base64>Zm9v!</base64></value></param></params></methodResponse>"#;
+    assert!(parse::response_base64_into(data.as_bytes(), &mut Vec::new()).is_err());
+}
+
+#[test]
+fn reads_deviations_from_the_specification_by_default() {
+    let data = r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName>
+        <extra>ignored</extra><params><param><value><boolean>true</boolean></value></param>
+        <param><note /><value><boolean>2</boolean></value></param></params></methodCall>"#;
+    let call = parse::call(data.as_bytes()).expect(BAD_DATA);
+    assert_eq!(call.params, vec![Value::Bool(true), Value::Bool(true)]);
+
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><struct>
+        <member><name>a</name><type>int</type><value><i4>1</i4></value></member>
+        <comment>ignored</comment></struct></value></param></params></methodResponse>"#;
+    let mut members = Members::new();
+    members.insert("a".into(), Value::Int(1));
+    assert_eq!(
+        parse::response(data.as_bytes()).expect(BAD_DATA),
+        Ok(vec![Value::Struct(members)])
+    );
+    let data = r#"<?xml version="1.0"?><methodResponse></methodResponse>"#;
+    assert_eq!(
+        parse::response(data.as_bytes()).expect(BAD_DATA),
+        Ok(vec![])
+    );
+}
+
+#[test]
+fn rejects_deviations_from_the_specification_when_strict() {
+    let strict = ParseOptions::strict();
+    let call = |body: &str| {
+        let data = format!(
+            r#"<?xml version="1.0"?><methodCall><methodName>foo</methodName>{}</methodCall>"#,
+            body
+        );
+        parse::call_with(data.as_bytes(), strict, usize::MAX)
+    };
+    assert!(call("<extra/><params></params>").is_err());
+    assert!(call("<params><param><note/><value><i4>1</i4></value></param></params>").is_err());
+    assert!(
+        call("<params><param><value><boolean>true</boolean></value></param></params>").is_err()
+    );
+    assert!(call("<params><param><value><boolean>2</boolean></value></param></params>").is_err());
+    assert_eq!(
+        call("<params><param><value><boolean> 1 </boolean></value></param></params>")
+            .expect(BAD_DATA)
+            .params,
+        vec![Value::Bool(true)]
+    );
+    assert_eq!(call("").expect(BAD_DATA).params, vec![]);
+
+    let data = r#"<?xml version="1.0"?><methodResponse></methodResponse>"#;
+    assert!(parse::response_with(data.as_bytes(), strict).is_err());
+    let data = r#"<?xml version="1.0"?><methodResponse><params><param><value><struct>
+        <member><name>a</name><type>int</type><value><i4>1</i4></value></member>
+        </struct></value></param></params></methodResponse>"#;
+    assert!(parse::response_with(data.as_bytes(), strict).is_err());
+    assert!(parse::response_with(data.as_bytes(), ParseOptions::default()).is_ok());
+}
@@ -1,75 +1,142 @@
 use super::super::ser::Serializer;
-use super::super::Value;
+use super::super::{Extensions, Members, Value};
 use serde::Serialize;
 use serde_bytes::Bytes;
 use std::collections::HashMap;
 
 #[test]
 fn writes_bool() {
-    assert_eq!(true.serialize(Serializer {}).unwrap(), Value::Bool(true));
-    assert_eq!(false.serialize(Serializer {}).unwrap(), Value::Bool(false));
+    assert_eq!(
+        true.serialize(Serializer::default()).unwrap(),
+        Value::Bool(true)
+    );
+    assert_eq!(
+        false.serialize(Serializer::default()).unwrap(),
+        Value::Bool(false)
+    );
 }
 
 #[test]
 fn writes_integers_as_ints_or_strings_if_too_big() {
-    assert_eq!(200u8.serialize(Serializer {}).unwrap(), Value::Int(200));
     assert_eq!(
-        42_000u16.serialize(Serializer {}).unwrap(),
+        200u8.serialize(Serializer::default()).unwrap(),
+        Value::Int(200)
+    );
+    assert_eq!(
+        42_000u16.serialize(Serializer::default()).unwrap(),
         Value::Int(42_000)
     );
     assert_eq!(
-        4_200_000_000u32.serialize(Serializer {}).unwrap(),
+        4_200_000_000u32.serialize(Serializer::default()).unwrap(),
         Value::String("4200000000".into())
     );
     assert_eq!(
         10_000_000_000_000_000_000u64
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::String("10000000000000000000".into())
     );
     assert_eq!(
-        (-42 as i8).serialize(Serializer {}).unwrap(),
+        (-42 as i8).serialize(Serializer::default()).unwrap(),
         Value::Int(-42)
     );
     assert_eq!(
-        (-26_000 as i16).serialize(Serializer {}).unwrap(),
+        (-26_000 as i16).serialize(Serializer::default()).unwrap(),
         Value::Int(-26_000)
     );
     assert_eq!(
-        (-2_000_000_000 as i32).serialize(Serializer {}).unwrap(),
+        (-2_000_000_000 as i32)
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::Int(-2000000000)
     );
     assert_eq!(
         (-8_000_000_000_000_000_000 as i64)
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::String("-8000000000000000000".into())
     );
-    assert_eq!(42i8.serialize(Serializer {}).unwrap(), Value::Int(42));
     assert_eq!(
-        26_000i16.serialize(Serializer {}).unwrap(),
+        42i8.serialize(Serializer::default()).unwrap(),
+        Value::Int(42)
+    );
+    assert_eq!(
+        26_000i16.serialize(Serializer::default()).unwrap(),
         Value::Int(26_000)
     );
     assert_eq!(
-        2_000_000_000i32.serialize(Serializer {}).unwrap(),
+        2_000_000_000i32.serialize(Serializer::default()).unwrap(),
         Value::Int(2000000000)
     );
     assert_eq!(
         8_000_000_000_000_000_000i64
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::String("8000000000000000000".into())
     );
 }
 
+#[test]
+fn writes_integers_as_i8_if_too_big_with_i8_extension() {
+    let i8 = Extensions {
+        i8: true,
+        ..Extensions::default()
+    };
+    assert_eq!(
+        42i64.serialize(Serializer::new(i8)).unwrap(),
+        Value::Int(42)
+    );
+    assert_eq!(
+        4_200_000_000u32.serialize(Serializer::new(i8)).unwrap(),
+        Value::Int64(4_200_000_000)
+    );
+    assert_eq!(
+        (-8_000_000_000_000_000_000 as i64)
+            .serialize(Serializer::new(i8))
+            .unwrap(),
+        Value::Int64(-8_000_000_000_000_000_000)
+    );
+    assert_eq!(
+        10_000_000_000_000_000_000u64
+            .serialize(Serializer::new(i8))
+            .unwrap(),
+        Value::String("10000000000000000000".into())
+    );
+}
+
+#[test]
+fn fails_writing_integers_out_of_range_in_strict_mode() {
+    let strict = Extensions {
+        strict: true,
+        ..Extensions::default()
+    };
+    assert_eq!(
+        42u64.serialize(Serializer::new(strict)).unwrap(),
+        Value::Int(42)
+    );
+    assert!(4_200_000_000u32.serialize(Serializer::new(strict)).is_err());
+    let strict = Extensions {
+        i8: true,
+        strict: true,
+        ..Extensions::default()
+    };
+    assert_eq!(
+        4_200_000_000u32.serialize(Serializer::new(strict)).unwrap(),
+        Value::Int64(4_200_000_000)
+    );
+    assert!(10_000_000_000_000_000_000u64
+        .serialize(Serializer::new(strict))
+        .is_err());
+}
+
 #[test]
 fn writes_floats() {
     assert_eq!(
-        3.25f32.serialize(Serializer {}).unwrap(),
+        3.25f32.serialize(Serializer::default()).unwrap(),
         Value::Double(3.25f64)
     );
     assert_eq!(
-        3.25f64.serialize(Serializer {}).unwrap(),
+        3.25f64.serialize(Serializer::default()).unwrap(),
         Value::Double(3.25f64)
     );
 }
@@ -77,11 +144,11 @@ fn writes_floats() {
 #[test]
 fn writes_chars_as_strings() {
     assert_eq!(
-        'A'.serialize(Serializer {}).unwrap(),
+        'A'.serialize(Serializer::default()).unwrap(),
         Value::String("A".into())
     );
     assert_eq!(
-        ' '.serialize(Serializer {}).unwrap(),
+        ' '.serialize(Serializer::default()).unwrap(),
         Value::String(" ".into())
     );
 }
@@ -89,12 +156,12 @@ fn writes_chars_as_strings() {
 #[test]
 fn writes_strings() {
     assert_eq!(
-        "static string".serialize(Serializer {}).unwrap(),
+        "static string".serialize(Serializer::default()).unwrap(),
         Value::String("static string".into())
     );
     assert_eq!(
         String::from("string object")
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::String("string object".into())
     );
@@ -103,7 +170,9 @@ fn writes_strings() {
 #[test]
 fn writes_bytes_as_base64() {
     assert_eq!(
-        Bytes::new(b"0123").serialize(Serializer {}).unwrap(),
+        Bytes::new(b"0123")
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::Base64(vec![48, 49, 50, 51])
     );
 }
@@ -112,32 +181,52 @@ fn writes_bytes_as_base64() {
 fn writes_options_as_one_elem_or_empty_array() {
     let none: Option<i32> = None;
     assert_eq!(
-        none.serialize(Serializer {}).unwrap(),
+        none.serialize(Serializer::default()).unwrap(),
         Value::Array(Vec::new())
     );
     assert_eq!(
-        Some(33i32).serialize(Serializer {}).unwrap(),
+        Some(33i32).serialize(Serializer::default()).unwrap(),
         Value::Array(vec![Value::Int(33)])
     );
     assert_eq!(
-        Some("txt").serialize(Serializer {}).unwrap(),
+        Some("txt").serialize(Serializer::default()).unwrap(),
         Value::Array(vec![Value::String("txt".into())])
     );
 }
 
+#[test]
+fn writes_options_as_nil_or_value_with_nil_extension() {
+    let nil = Extensions {
+        nil: true,
+        ..Extensions::default()
+    };
+    let none: Option<i32> = None;
+    assert_eq!(none.serialize(Serializer::new(nil)).unwrap(), Value::Nil);
+    assert_eq!(
+        Some(33i32).serialize(Serializer::new(nil)).unwrap(),
+        Value::Int(33)
+    );
+    assert_eq!(
+        vec![None, Some("txt")]
+            .serialize(Serializer::new(nil))
+            .unwrap(),
+        Value::Array(vec![Value::Nil, Value::String("txt".into())])
+    );
+}
+
 #[test]
 fn writes_units_as_empty_struct() {
     assert_eq!(
-        ().serialize(Serializer {}).unwrap(),
-        Value::Struct(HashMap::new())
+        ().serialize(Serializer::default()).unwrap(),
+        Value::Struct(Members::new())
     );
 
     #[derive(Serialize)]
     struct Helper;
 
     assert_eq!(
-        Helper.serialize(Serializer {}).unwrap(),
-        Value::Struct(HashMap::new())
+        Helper.serialize(Serializer::default()).unwrap(),
+        Value::Struct(Members::new())
     );
 }
 
@@ -149,11 +238,13 @@ fn writes_newtype_struct_as_its_content() {
     struct HelperString(String);
 
     assert_eq!(
-        HelperInt(33).serialize(Serializer {}).unwrap(),
+        HelperInt(33).serialize(Serializer::default()).unwrap(),
         Value::Int(33)
     );
     assert_eq!(
-        HelperString("txt".into()).serialize(Serializer {}).unwrap(),
+        HelperString("txt".into())
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::String("txt".into())
     );
 }
@@ -161,7 +252,9 @@ fn writes_newtype_struct_as_its_content() {
 #[test]
 fn writes_vector_as_array() {
     assert_eq!(
-        vec![33, 15, 44, 12].serialize(Serializer {}).unwrap(),
+        vec![33, 15, 44, 12]
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::Array(vec![
             Value::Int(33),
             Value::Int(15),
@@ -170,7 +263,9 @@ fn writes_vector_as_array() {
         ])
     );
     assert_eq!(
-        vec!['a', 'b', 'c', 'd'].serialize(Serializer {}).unwrap(),
+        vec!['a', 'b', 'c', 'd']
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::Array(vec![
             Value::String("a".into()),
             Value::String("b".into()),
@@ -184,7 +279,7 @@ fn writes_vector_as_array() {
 fn writes_tuple_as_array() {
     assert_eq!(
         (4, 1_000_000_000_000i64, "hello", true)
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::Array(vec![
             Value::Int(4),
@@ -199,7 +294,7 @@ fn writes_tuple_as_array() {
 
     assert_eq!(
         Helper(4, 1_000_000_000_000u64, "hello".into(), true)
-            .serialize(Serializer {})
+            .serialize(Serializer::default())
             .unwrap(),
         Value::Array(vec![
             Value::Int(4),
@@ -220,7 +315,7 @@ fn writes_struct_as_struct() {
         qux: bool,
     };
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("foo".into(), Value::Int(4));
     members.insert("bar".into(), Value::String("1000000000000".into()));
     members.insert("baz".into(), Value::String("hello".into()));
@@ -233,7 +328,7 @@ fn writes_struct_as_struct() {
             baz: "hello".into(),
             qux: true,
         }
-        .serialize(Serializer {})
+        .serialize(Serializer::default())
         .unwrap(),
         Value::Struct(members)
     );
@@ -246,7 +341,7 @@ fn writes_map_as_struct() {
     data.insert("bar", vec![]);
     data.insert("baz", vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "foo".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -258,7 +353,7 @@ fn writes_map_as_struct() {
     );
 
     assert_eq!(
-        data.serialize(Serializer {}).unwrap(),
+        data.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 }
@@ -270,7 +365,7 @@ fn map_accepts_string_keys() {
     data.insert(String::from("bar"), vec![]);
     data.insert(String::from("baz"), vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "foo".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -282,7 +377,7 @@ fn map_accepts_string_keys() {
     );
 
     assert_eq!(
-        data.serialize(Serializer {}).unwrap(),
+        data.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 }
@@ -294,7 +389,7 @@ fn map_accepts_integer_keys() {
     data.insert(-33, vec![]);
     data.insert(44, vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "12".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -306,7 +401,7 @@ fn map_accepts_integer_keys() {
     );
 
     assert_eq!(
-        data.serialize(Serializer {}).unwrap(),
+        data.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 }
@@ -318,7 +413,7 @@ fn map_accepts_char_keys() {
     data.insert('b', vec![]);
     data.insert('c', vec![-3, 44, 28]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "a".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -330,7 +425,7 @@ fn map_accepts_char_keys() {
     );
 
     assert_eq!(
-        data.serialize(Serializer {}).unwrap(),
+        data.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 }
@@ -341,7 +436,7 @@ fn map_accepts_boolean_keys() {
     data.insert(true, vec![44i8, 12]);
     data.insert(false, vec![]);
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "true".into(),
         Value::Array(vec![Value::Int(44), Value::Int(12)]),
@@ -349,7 +444,7 @@ fn map_accepts_boolean_keys() {
     members.insert("false".into(), Value::Array(vec![]));
 
     assert_eq!(
-        data.serialize(Serializer {}).unwrap(),
+        data.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 }
@@ -360,7 +455,7 @@ fn rejects_maps_with_unsupported_keys() {
     data.insert(Some(4), vec![44i8, 12]);
     data.insert(Some(3), vec![]);
     data.insert(Some(2), vec![-3, 44, 28]);
-    data.serialize(Serializer {}).unwrap_err();
+    data.serialize(Serializer::default()).unwrap_err();
 }
 
 #[test]
@@ -373,31 +468,33 @@ fn writes_variant_as_one_member_struct() {
         Qux { alpha: i32, beta: Vec<bool> },
     };
 
-    let mut members = HashMap::new();
-    members.insert("Foo".into(), Value::Struct(HashMap::new()));
+    let mut members = Members::new();
+    members.insert("Foo".into(), Value::Struct(Members::new()));
     assert_eq!(
-        Helper::Foo.serialize(Serializer {}).unwrap(),
+        Helper::Foo.serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("Bar".into(), Value::Int(44));
     assert_eq!(
-        Helper::Bar(44).serialize(Serializer {}).unwrap(),
+        Helper::Bar(44).serialize(Serializer::default()).unwrap(),
         Value::Struct(members)
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert(
         "Baz".into(),
         Value::Array(vec![Value::Bool(false), Value::String("tsk".into())]),
     );
     assert_eq!(
-        Helper::Baz(false, "tsk").serialize(Serializer {}).unwrap(),
+        Helper::Baz(false, "tsk")
+            .serialize(Serializer::default())
+            .unwrap(),
         Value::Struct(members)
     );
 
-    let mut submembers = HashMap::new();
+    let mut submembers = Members::new();
     submembers.insert("alpha".into(), Value::Int(-4));
     submembers.insert(
         "beta".into(),
@@ -408,15 +505,45 @@ fn writes_variant_as_one_member_struct() {
         ]),
     );
 
-    let mut members = HashMap::new();
+    let mut members = Members::new();
     members.insert("Qux".into(), Value::Struct(submembers));
     assert_eq!(
         Helper::Qux {
             alpha: -4,
             beta: vec![true, false, true],
         }
-        .serialize(Serializer {})
+        .serialize(Serializer::default())
         .unwrap(),
         Value::Struct(members)
     );
 }
+
+fn every_kind_of_value() -> Value {
+    let mut members = Members::new();
+    members.insert("nil".into(), Value::Nil);
+    members.insert("time".into(), Value::DateTime("19980717T14:08:55".into()));
+    Value::Array(vec![
+        Value::Int(3),
+        Value::Int64(4),
+        Value::Bool(true),
+        Value::String("txt".into()),
+        Value::Double(0.5),
+        Value::Base64(b"raw".to_vec()),
+        Value::Struct(members),
+    ])
+}
+
+#[test]
+fn writes_values_as_themselves() {
+    let value = every_kind_of_value();
+    for extensions in &[Extensions::default(), Extensions::all()] {
+        let serializer = Serializer::new(*extensions);
+        assert_eq!(value.serialize(serializer).unwrap(), value);
+    }
+    assert_eq!(
+        Value::Nil.serialize(Serializer::default()).unwrap(),
+        Value::Nil
+    );
+    let params = super::super::into_params(&(Value::Int(3), 4)).unwrap();
+    assert_eq!(params, vec![Value::Int(3), Value::Int(4)]);
+}
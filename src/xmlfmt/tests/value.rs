@@ -0,0 +1,462 @@
+use super::super::value::{diff, Difference};
+use super::super::{Call, Fault, Members, Patch, Response, ToPretty, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[test]
+fn reads_values_with_accessors() {
+    assert_eq!(Value::Int(-3).as_i32(), Some(-3));
+    assert_eq!(Value::Int64(42).as_i32(), Some(42));
+    assert_eq!(Value::Int64(8_000_000_000).as_i32(), None);
+    assert_eq!(Value::Int(-3).as_i64(), Some(-3));
+    assert_eq!(Value::Bool(true).as_bool(), Some(true));
+    assert_eq!(Value::String("txt".into()).as_str(), Some("txt"));
+    assert_eq!(Value::DateTime("33".into()).as_str(), None);
+    assert_eq!(Value::Double(-4.5).as_f64(), Some(-4.5));
+    assert_eq!(Value::Base64(b"raw".to_vec()).as_bytes(), Some(&b"raw"[..]));
+    assert_eq!(
+        Value::Array(vec![Value::Nil]).as_array(),
+        Some(&[Value::Nil][..])
+    );
+    assert!(Value::Struct(Members::new())
+        .as_struct()
+        .unwrap()
+        .is_empty());
+    assert_eq!(Value::String("1".into()).as_i32(), None);
+}
+
+#[test]
+fn converts_values_into_primitives() {
+    assert_eq!(i32::try_from(Value::Int(7)).unwrap(), 7);
+    assert_eq!(
+        i64::try_from(Value::Int64(8_000_000_000)).unwrap(),
+        8_000_000_000
+    );
+    assert_eq!(
+        String::try_from(Value::String("txt".into())).unwrap(),
+        "txt"
+    );
+    assert_eq!(
+        Vec::<Value>::try_from(Value::Array(vec![Value::Int(1)])).unwrap(),
+        vec![Value::Int(1)]
+    );
+    let err = bool::try_from(Value::String("yes".into())).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Issue while decoding data structure: invalid type: string \"yes\", expected a boolean"
+    );
+    assert!(i32::try_from(Value::Int64(8_000_000_000)).is_err());
+}
+
+#[test]
+fn looks_up_nested_values_by_path() {
+    let mut item = Members::new();
+    item.insert("name".to_owned(), Value::String("third".into()));
+    let items = Value::Array(vec![Value::Int(1), Value::Int(2), Value::Struct(item)]);
+    let mut result = Members::new();
+    result.insert("items".to_owned(), items);
+    result.insert(
+        "grid".to_owned(),
+        Value::Array(vec![Value::Array(vec![Value::Int(5), Value::Int(6)])]),
+    );
+    let mut fields = Members::new();
+    fields.insert("result".to_owned(), Value::Struct(result));
+    let value = Value::Struct(fields);
+
+    assert_eq!(
+        value.get("result.items[2].name"),
+        Some(&Value::String("third".into()))
+    );
+    assert_eq!(value.get("result.items[1]"), Some(&Value::Int(2)));
+    assert_eq!(value.get("result.grid[0][1]"), Some(&Value::Int(6)));
+    assert_eq!(value.get(""), Some(&value));
+    assert_eq!(Value::Array(vec![Value::Nil]).get("[0]"), Some(&Value::Nil));
+    assert_eq!(value.get("result.items[3]"), None);
+    assert_eq!(value.get("result.missing"), None);
+    assert_eq!(value.get("result.items.name"), None);
+    assert_eq!(value.get("result..items"), None);
+    assert_eq!(value.get("result.items[x]"), None);
+    assert_eq!(value.get("result.items[1"), None);
+}
+
+#[test]
+fn merges_struct_values_deeply() {
+    let mut value = xmlrpc_value!({
+        "name": "old",
+        "options": { "depth": 1, "wide": true },
+        "tags": ["a", "b"]
+    });
+    value.merge(&xmlrpc_value!({
+        "options": { "depth": 2, "color": "red" },
+        "tags": ["c"],
+        "extra": nil
+    }));
+    assert_eq!(
+        value,
+        xmlrpc_value!({
+            "name": "old",
+            "options": { "depth": 2, "wide": true, "color": "red" },
+            "tags": ["c"],
+            "extra": nil
+        })
+    );
+
+    let mut scalar = Value::Int(1);
+    scalar.merge(&xmlrpc_value!({ "a": 1 }));
+    assert_eq!(scalar, xmlrpc_value!({ "a": 1 }));
+}
+
+#[test]
+fn applies_patches_at_paths() {
+    let mut value = xmlrpc_value!({
+        "user": { "name": "ann", "age": 30 },
+        "items": [1, 2, 3]
+    });
+    value
+        .apply(&[
+            Patch::Set("user.name".to_owned(), Value::from("bob")),
+            Patch::Remove("user.age".to_owned()),
+            Patch::Set("items[0]".to_owned(), Value::Int(10)),
+            Patch::Set("items[3]".to_owned(), Value::Int(4)),
+            Patch::Remove("items[1]".to_owned()),
+            Patch::Merge("user".to_owned(), xmlrpc_value!({ "admin": true })),
+        ])
+        .unwrap();
+    assert_eq!(
+        value,
+        xmlrpc_value!({
+            "user": { "name": "bob", "admin": true },
+            "items": [10, 3, 4]
+        })
+    );
+    *value.get_mut("items[0]").unwrap() = Value::Int(0);
+    assert_eq!(value.get("items[0]"), Some(&Value::Int(0)));
+}
+
+#[test]
+fn applies_no_patches_when_one_fails() {
+    let original = xmlrpc_value!({ "user": { "name": "ann" }, "items": [1] });
+
+    let mut value = original.clone();
+    assert!(value
+        .apply(&[
+            Patch::Set("user.name".to_owned(), Value::from("bob")),
+            Patch::Remove("user.missing".to_owned()),
+        ])
+        .is_err());
+    assert_eq!(value, original);
+
+    for patch in [
+        Patch::Set("missing.name".to_owned(), Value::Nil),
+        Patch::Set("items[2]".to_owned(), Value::Nil),
+        Patch::Set("user[0]".to_owned(), Value::Nil),
+        Patch::Set("".to_owned(), Value::Nil),
+        Patch::Remove("items[1]".to_owned()),
+        Patch::Merge("missing".to_owned(), Value::Nil),
+    ] {
+        assert!(value.apply(&[patch]).is_err());
+    }
+    assert_eq!(value, original);
+}
+
+#[test]
+fn finds_nothing_out_of_range_or_through_other_values() {
+    let original = xmlrpc_value!({ "name": "ann", "items": [1, { "id": 2 }] });
+    let mut value = original.clone();
+    for path in [
+        "items[2]",
+        "items[99].id",
+        "items[18446744073709551616]",
+        "items[0].id",
+        "items[0][0]",
+        "name.first",
+        "name[0]",
+        "items.id",
+        "[0]",
+    ] {
+        assert_eq!(value.get(path), None, "{}", path);
+        assert_eq!(value.get_mut(path), None, "{}", path);
+    }
+
+    for patch in [
+        Patch::Set("items[3]".to_owned(), Value::Nil),
+        Patch::Set("items[0].id".to_owned(), Value::Nil),
+        Patch::Set("name.first".to_owned(), Value::Nil),
+        Patch::Set("items.id".to_owned(), Value::Nil),
+        Patch::Remove("items[2]".to_owned()),
+        Patch::Remove("name.first".to_owned()),
+        Patch::Remove("items[1][0]".to_owned()),
+        Patch::Merge("items[2]".to_owned(), Value::Nil),
+        Patch::Merge("name.first".to_owned(), Value::Nil),
+    ] {
+        assert!(value.apply(&[patch.clone()]).is_err(), "{:?}", patch);
+    }
+    assert_eq!(value, original);
+}
+
+#[test]
+fn lists_differences_between_values() {
+    let left = xmlrpc_value!({
+        "id": 1,
+        "user": { "name": "ann", "age": 30 },
+        "items": [1, 2, 3]
+    });
+    let right = xmlrpc_value!({
+        "id": 1,
+        "user": { "name": "bob", "admin": true },
+        "items": [1, "2"]
+    });
+    let mut differences = diff(&left, &right);
+    differences.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(
+        differences,
+        vec![
+            Difference {
+                path: "items[1]".to_owned(),
+                left: Some(Value::Int(2)),
+                right: Some(Value::from("2")),
+            },
+            Difference {
+                path: "items[2]".to_owned(),
+                left: Some(Value::Int(3)),
+                right: None,
+            },
+            Difference {
+                path: "user.admin".to_owned(),
+                left: None,
+                right: Some(Value::Bool(true)),
+            },
+            Difference {
+                path: "user.age".to_owned(),
+                left: Some(Value::Int(30)),
+                right: None,
+            },
+            Difference {
+                path: "user.name".to_owned(),
+                left: Some(Value::from("ann")),
+                right: Some(Value::from("bob")),
+            },
+        ]
+    );
+    assert_eq!(diff(&left, &left), vec![]);
+}
+
+#[test]
+fn lists_differences_of_lengths_and_types() {
+    let short = xmlrpc_value!([1]);
+    let long = xmlrpc_value!([1, 2, [3]]);
+    assert_eq!(
+        diff(&short, &long),
+        vec![
+            Difference {
+                path: "[1]".to_owned(),
+                left: None,
+                right: Some(Value::Int(2)),
+            },
+            Difference {
+                path: "[2]".to_owned(),
+                left: None,
+                right: Some(xmlrpc_value!([3])),
+            },
+        ]
+    );
+    let lines: Vec<String> = diff(&long, &xmlrpc_value!([]))
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "[0]: 1 != missing",
+            "[1]: 2 != missing",
+            "[2]: [3] != missing"
+        ]
+    );
+
+    let left = xmlrpc_value!({ "list": [1], "count": 1, "user": { "id": 1 }, "tags": [] });
+    let right = xmlrpc_value!({ "list": { "0": 1 }, "count": "1", "user": nil, "tags": {} });
+    let differences = diff(&left, &right);
+    let paths: Vec<&str> = differences.iter().map(|d| d.path.as_str()).collect();
+    let mut sorted = paths.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, vec!["count", "list", "tags", "user"]);
+    for difference in &differences {
+        assert_eq!(difference.left.as_ref(), left.get(&difference.path));
+        assert_eq!(difference.right.as_ref(), right.get(&difference.path));
+    }
+    assert_eq!(
+        diff(&Value::Int(1), &Value::Int64(1)),
+        vec![Difference {
+            path: String::new(),
+            left: Some(Value::Int(1)),
+            right: Some(Value::Int64(1)),
+        }]
+    );
+}
+
+#[test]
+fn displays_differences_with_paths() {
+    let differences = diff(
+        &xmlrpc_value!({ "a": [1] }),
+        &xmlrpc_value!({ "a": [2, nil] }),
+    );
+    let lines: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+    assert_eq!(lines, vec!["a[0]: 1 != 2", "a[1]: missing != nil"]);
+    assert_eq!(
+        diff(&Value::Int(1), &Value::from("1"))[0].to_string(),
+        r#".: 1 != "1""#
+    );
+}
+
+#[test]
+fn builds_values_with_builders() {
+    let tags = Value::array_builder().push("a").push(2).push(false).build();
+    let value = Value::struct_builder()
+        .field("a", 1)
+        .field("b", "x")
+        .field(String::from("tags"), tags)
+        .field("ratio", 0.5)
+        .field("b", "y")
+        .build();
+    assert_eq!(
+        value,
+        xmlrpc_value!({ "a": 1, "b": "y", "tags": ["a", 2, false], "ratio": 0.5 })
+    );
+    assert_eq!(Value::struct_builder().build(), xmlrpc_value!({}));
+    assert_eq!(Value::array_builder().build(), xmlrpc_value!([]));
+}
+
+#[test]
+fn builds_values_with_macro() {
+    let id = 3;
+    let value = xmlrpc_value!({
+        "name": "demo",
+        "ids": [1, -2, id],
+        "nested": { "empty": [], "none": {}, "parent": nil },
+        "big": 1u64 << 40,
+    });
+    let mut nested = Members::new();
+    nested.insert("empty".to_owned(), Value::Array(vec![]));
+    nested.insert("none".to_owned(), Value::Struct(Members::new()));
+    nested.insert("parent".to_owned(), Value::Nil);
+    let mut fields = Members::new();
+    fields.insert("name".to_owned(), Value::String("demo".into()));
+    fields.insert(
+        "ids".to_owned(),
+        Value::Array(vec![Value::Int(1), Value::Int(-2), Value::Int(3)]),
+    );
+    fields.insert("nested".to_owned(), Value::Struct(nested));
+    fields.insert("big".to_owned(), Value::String("1099511627776".into()));
+    assert_eq!(value, Value::Struct(fields));
+    assert_eq!(
+        xmlrpc_value!([nil, [true], 2.5]),
+        Value::Array(vec![
+            Value::Nil,
+            Value::Array(vec![Value::Bool(true)]),
+            Value::Double(2.5),
+        ])
+    );
+    assert_eq!(xmlrpc_value!("txt"), Value::String("txt".into()));
+}
+
+#[test]
+fn converts_primitives_into_values() {
+    assert_eq!(Value::from(7), Value::Int(7));
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from("txt"), Value::String("txt".into()));
+    assert_eq!(
+        Value::from(String::from("txt")),
+        Value::String("txt".into())
+    );
+    assert_eq!(Value::from(-4.5), Value::Double(-4.5));
+    assert_eq!(Value::from(b"raw".to_vec()), Value::Base64(b"raw".to_vec()));
+    assert_eq!(
+        Value::from(vec!["a", "b"]),
+        Value::Array(vec![Value::String("a".into()), Value::String("b".into())])
+    );
+    let mut fields = HashMap::new();
+    fields.insert("ids".to_owned(), vec![1, 2]);
+    let mut expected = Members::new();
+    expected.insert(
+        "ids".to_owned(),
+        Value::Array(vec![Value::Int(1), Value::Int(2)]),
+    );
+    assert_eq!(Value::from(fields), Value::Struct(expected));
+    let params: Vec<Value> = vec![3.into(), "name".into()];
+    assert_eq!(params, vec![Value::Int(3), Value::String("name".into())]);
+}
+
+#[test]
+fn displays_values_on_a_single_line() {
+    let value = xmlrpc_value!([1, "a \"b\"", 2.0, true, nil, [], {}, { "k": [3] }]);
+    assert_eq!(
+        value.to_string(),
+        r#"[1, "a \"b\"", 2.0, true, nil, [], {}, {"k": [3]}]"#
+    );
+    assert_eq!(Value::Base64(b"hi".to_vec()).to_string(), "base64(aGk=)");
+    let call = Call {
+        name: "add".into(),
+        params: vec![Value::Int(1), Value::Int64(2)],
+    };
+    assert_eq!(call.to_string(), "add(1, 2)");
+}
+
+#[test]
+fn pretty_prints_values_over_indented_lines() {
+    assert_eq!(
+        xmlrpc_value!({ "ids": [1, [2]] }).to_pretty_string(),
+        "{\n  \"ids\": [\n    1,\n    [\n      2\n    ]\n  ]\n}"
+    );
+    assert_eq!(xmlrpc_value!({}).to_pretty_string(), "{}");
+    assert_eq!(xmlrpc_value!([]).to_pretty_string(), "[]");
+    let call = Call {
+        name: "add".into(),
+        params: vec![Value::Int(1), Value::Int(2)],
+    };
+    assert_eq!(call.to_pretty_string(), "add(\n  1,\n  2\n)");
+    let response: Response = Ok(vec![Value::String("ok".into())]);
+    assert_eq!(response.to_pretty_string(), "(\n  \"ok\"\n)");
+    let response: Response =
+        Err(Fault::new(4, "Too many parameters.").with_member("at", Value::Int(2)));
+    assert_eq!(
+        response.to_pretty_string(),
+        "XML-RPC fault 4: Too many parameters. {\n  \"at\": 2\n}"
+    );
+}
+
+#[test]
+fn pretty_prints_empty_values_on_their_lines() {
+    assert_eq!(
+        xmlrpc_value!({ "a": [], "b": {}, "c": [[], {}] }).to_pretty_string(),
+        "{\n  \"a\": [],\n  \"b\": {},\n  \"c\": [\n    [],\n    {}\n  ]\n}"
+    );
+    let call = Call {
+        name: "ping".into(),
+        params: vec![],
+    };
+    assert_eq!(call.to_pretty_string(), "ping()");
+    assert_eq!(call.to_string(), "ping()");
+    let response: Response = Ok(vec![]);
+    assert_eq!(response.to_pretty_string(), "()");
+    assert_eq!(Value::String(String::new()).to_pretty_string(), r#""""#);
+    assert_eq!(Value::Base64(vec![]).to_string(), "base64()");
+}
+
+#[test]
+fn escapes_strings_and_member_names() {
+    let value = Value::String("a \"b\" \\ c\nd\te\u{1}".into());
+    assert_eq!(value.to_string(), r#""a \"b\" \\ c\nd\te\u{1}""#);
+    assert_eq!(value.to_pretty_string(), value.to_string());
+
+    let value = xmlrpc_value!({ "say \"hi\"\n": ["x\ny"] });
+    assert_eq!(value.to_string(), r#"{"say \"hi\"\n": ["x\ny"]}"#);
+    assert_eq!(
+        value.to_pretty_string(),
+        "{\n  \"say \\\"hi\\\"\\n\": [\n    \"x\\ny\"\n  ]\n}"
+    );
+    let call = Call {
+        name: "echo".into(),
+        params: vec![Value::String("\"".into())],
+    };
+    assert_eq!(call.to_string(), r#"echo("\"")"#);
+}
@@ -1,25 +1,48 @@
+pub use super::diff::{diff, Difference};
+
+use super::error;
 use base64;
-use serde::de::Unexpected;
+use serde::de::{self, MapAccess, Unexpected, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_bytes;
 use std;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use xml::escape::escape_str_pcdata;
 
-#[derive(Clone, Debug, PartialEq)]
+/// A value of any type.
+///
+/// Values implement `Serialize` and `Deserialize` by themselves, for storing them in logs,
+/// caches and queues, with their types tagged, as in `{"int": 3}` or `{"base64": [104, 105]}`
+/// in JSON. Encoded as parameters, as by `into_params`, or read from them, they stand for
+/// themselves instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "$xml-rpc::Value", rename_all = "camelCase")]
 pub enum Value {
     Int(i32),
+    /// The `<i8>` of the extension, which not every peer understands.
+    Int64(i64),
     Bool(bool),
     String(String),
     Double(f64),
+    /// The text of the `<dateTime.iso8601>`, in whatever format it came, which `as_datetime`
+    /// reads with the `chrono` feature.
     DateTime(String),
+    /// The bytes decoded, which are only base64 on the wire.
+    #[serde(with = "serde_bytes")]
     Base64(Vec<u8>),
     Array(Vec<Value>),
-    Struct(HashMap<String, Value>),
+    Struct(Members),
+    /// The `<nil/>` of the extension, which not every peer understands.
+    Nil,
 }
 
 impl Value {
     pub fn unexpected(&self) -> Unexpected {
         match *self {
             Value::Int(v) => Unexpected::Signed(i64::from(v)),
+            Value::Int64(v) => Unexpected::Signed(v),
             Value::Bool(v) => Unexpected::Bool(v),
             Value::String(ref v) => Unexpected::Str(v),
             Value::Double(v) => Unexpected::Float(v),
@@ -27,18 +50,212 @@ impl Value {
             Value::Base64(ref v) => Unexpected::Bytes(v),
             Value::Array(_) => Unexpected::Seq,
             Value::Struct(_) => Unexpected::Map,
+            Value::Nil => Unexpected::Unit,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Value::Int(v) => Some(v),
+            Value::Int64(v) => i32::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    /// The integer, whether an `<i4>` or an `<i8>`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Int(v) => Some(i64::from(v)),
+            Value::Int64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Double(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Value::Base64(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&Members> {
+        match *self {
+            Value::Struct(ref v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Value {
+        Value::Int(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Value {
+        Value::Bool(v)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(v: &'a str) -> Value {
+        Value::String(v.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Value {
+        Value::String(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Value {
+        Value::Double(v)
+    }
+}
+
+/// Bytes are `<base64>`, rather than arrays of integers.
+impl From<Vec<u8>> for Value {
+    fn from(v: Vec<u8>) -> Value {
+        Value::Base64(v)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Value {
+        Value::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(v: HashMap<String, T>) -> Value {
+        Value::Struct(v.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// Implements `TryFrom<Value>` for the type with the accessor, failing as decoding does.
+macro_rules! impl_try_from {
+    ($($ty:ty => $accessor:ident, $expected:expr;)*) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = error::Error;
+
+                fn try_from(value: Value) -> error::Result<$ty> {
+                    match value.$accessor() {
+                        Some(v) => Ok(v),
+                        None => Err(de::Error::invalid_type(value.unexpected(), &$expected)),
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_try_from! {
+    i32 => as_i32, "a 32-bit integer";
+    i64 => as_i64, "an integer";
+    bool => as_bool, "a boolean";
+    f64 => as_f64, "a double";
+}
+
+impl TryFrom<Value> for String {
+    type Error = error::Error;
+
+    fn try_from(value: Value) -> error::Result<String> {
+        match value {
+            Value::String(v) => Ok(v),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"a string")),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = error::Error;
+
+    fn try_from(value: Value) -> error::Result<Vec<u8>> {
+        match value {
+            Value::Base64(v) => Ok(v),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"base64")),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<Value> {
+    type Error = error::Error;
+
+    fn try_from(value: Value) -> error::Result<Vec<Value>> {
+        match value {
+            Value::Array(v) => Ok(v),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"an array")),
+        }
+    }
+}
+
+impl TryFrom<Value> for HashMap<String, Value> {
+    type Error = error::Error;
+
+    fn try_from(value: Value) -> error::Result<HashMap<String, Value>> {
+        match value {
+            Value::Struct(v) => Ok(v.into_iter().collect()),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"a struct")),
         }
     }
 }
 
 pub type Params = Vec<Value>;
 
-#[derive(Clone, Debug, PartialEq, Deserialize)]
+/// The name `Value` goes by with serde, by which the serializer and deserializer of parameters
+/// tell it from other enums.
+pub(crate) const VALUE_NAME: &str = "$xml-rpc::Value";
+
+/// The members of a struct, which keep the order they are read or inserted in, for peers and
+/// tests that depend on it.
+pub type Members = indexmap::IndexMap<String, Value>;
+
+/// Takes the member out of the struct, keeping the others in order.
+pub(crate) fn take_member(members: &mut Members, name: &str) -> Option<Value> {
+    members.shift_remove(name)
+}
+
+/// The error a server answers a call with.
+///
+/// Handlers failing with errors of their own can return them with `?`, given an implementation
+/// of `From<TheirError> for Fault` choosing the code and message.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Fault {
-    #[serde(rename = "faultCode")]
     pub code: i32,
-    #[serde(rename = "faultString")]
     pub message: String,
+    members: Members,
 }
 
 impl Fault {
@@ -49,13 +266,107 @@ impl Fault {
         Fault {
             code,
             message: message.into(),
+            members: Members::new(),
+        }
+    }
+
+    /// Adds the member to the fault struct.
+    pub fn with_member<K: Into<String>>(mut self, name: K, value: Value) -> Self {
+        self.members.insert(name.into(), value);
+        self
+    }
+
+    /// Members of the fault struct besides the code and message, which some servers use for
+    /// details such as the parameter at fault.
+    pub fn members(&self) -> &Members {
+        &self.members
+    }
+
+    /// Takes the members of the fault struct besides the code and message.
+    pub fn into_members(self) -> Members {
+        self.members
+    }
+
+    /// Reads the fault from the struct of a fault response, keeping its other members.
+    pub(crate) fn from_value(value: Value) -> error::Result<Fault> {
+        Fault::deserialize(value)
+    }
+
+    /// Writes the members besides the code and message, as found in fault structs.
+    pub(crate) fn members_xml(&self) -> String {
+        self.members
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "<member><name>{}</name>{}</member>",
+                    escape_str_pcdata(name),
+                    value.to_xml()
+                )
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "XML-RPC fault {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for Fault {}
+
+/// Writes the fault as the struct of a fault response, with the code and message among the
+/// other members.
+impl Serialize for Fault {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.members.len() + 2))?;
+        map.serialize_entry("faultCode", &self.code)?;
+        map.serialize_entry("faultString", &self.message)?;
+        for (name, value) in &self.members {
+            map.serialize_entry(name, value)?;
         }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Fault {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Fault, D::Error> {
+        deserializer.deserialize_map(FaultVisitor)
+    }
+}
+
+struct FaultVisitor;
+
+impl<'de> Visitor<'de> for FaultVisitor {
+    type Value = Fault;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a fault struct")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Fault, A::Error> {
+        let (mut code, mut message, mut members) = (None, None, Members::new());
+        while let Some(name) = map.next_key::<String>()? {
+            match name.as_str() {
+                "faultCode" => code = Some(map.next_value()?),
+                "faultString" => message = Some(map.next_value()?),
+                _ => {
+                    let value = map.next_value()?;
+                    members.insert(name, value);
+                }
+            }
+        }
+        Ok(Fault {
+            code: code.ok_or_else(|| de::Error::missing_field("faultCode"))?,
+            message: message.ok_or_else(|| de::Error::missing_field("faultString"))?,
+            members,
+        })
     }
 }
 
 pub type Response = std::result::Result<Params, Fault>;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Call {
     pub name: String,
     pub params: Params,
@@ -89,10 +400,11 @@ impl ToXml for Response {
                     .map(|param| format!("<param>{}</param>", param.to_xml()))
                     .collect::<String>()
             ),
-            Err(Fault { code, ref message }) => format!(
+            Err(ref fault) => format!(
                 include_str!("templates/response_fault.xml"),
-                code = code,
-                message = message
+                code = fault.code,
+                message = escape_str_pcdata(&fault.message),
+                members = fault.members_xml()
             ),
         }
     }
@@ -102,6 +414,7 @@ impl ToXml for Value {
     fn to_xml(&self) -> String {
         match *self {
             Value::Int(v) => format!("<value><i4>{}</i4></value>", v),
+            Value::Int64(v) => format!("<value><i8>{}</i8></value>", v),
             Value::Bool(v) => format!(
                 "<value><boolean>{}</boolean></value>",
                 if v { 1 } else { 0 }
@@ -125,11 +438,12 @@ impl ToXml for Value {
                 v.iter()
                     .map(|(key, value)| format!(
                         "<member><name>{}</name>{}</member>",
-                        key,
+                        escape_str_pcdata(key),
                         value.to_xml()
                     ))
                     .collect::<String>()
             ),
+            Value::Nil => "<value><nil/></value>".into(),
         }
     }
 }